@@ -1,5 +1,8 @@
 use super::*;
-use crate::message_handlers::{add_resource_from_text, handle_single_item, start_resource_picker};
+use crate::message_handlers::{
+    add_resource_from_text, add_resource_to_default, handle_single_item, save_single_item,
+    start_resource_picker,
+};
 
 pub(super) async fn handle_callback(
     bot: Bot,
@@ -18,12 +21,26 @@ pub(super) async fn handle_callback(
             handle_picker_callback(bot, q, state).await?;
         } else if data.starts_with("add:") {
             handle_add_callback(bot, q, state).await?;
+        } else if data.starts_with("title:") {
+            handle_title_callback(bot, q, state).await?;
         } else if data.starts_with("res:") {
             handle_resource_callback(bot, q, state).await?;
+        } else if data.starts_with("rb:") {
+            handle_resource_browser_callback(bot, q, state).await?;
         } else if data.starts_with("dl:") {
             handle_download_callback(bot, q, state).await?;
         } else if data.starts_with("msgdel") {
             handle_message_delete_callback(bot, q).await?;
+        } else if data.starts_with("trash:") {
+            handle_trash_callback(bot, q, state).await?;
+        } else if data.starts_with("dup:") {
+            handle_duplicates_callback(bot, q, state).await?;
+        } else if data.starts_with("rf:") {
+            handle_finished_pick_callback(bot, q, state).await?;
+        } else if data.starts_with("queue:") {
+            handle_queue_callback(bot, q, state).await?;
+        } else if data.starts_with("snap:") {
+            handle_snapshot_callback(bot, q, state).await?;
         } else if data.starts_with("undos:") {
             handle_undos_callback(bot, q, state).await?;
         } else if data.starts_with("undo:") {
@@ -84,16 +101,29 @@ async fn handle_add_callback(
             )
             .await?;
         }
-        "resource" => {
-            start_resource_picker(
-                &bot,
-                message.chat.id,
-                &state,
-                &prompt.text,
-                Some(prompt.source_message_id),
-            )
-            .await?;
-        }
+        "resource" => match default_resource_path(&state.config) {
+            Some(path) => {
+                add_resource_to_default(
+                    &bot,
+                    message.chat.id,
+                    &state,
+                    path,
+                    &prompt.text,
+                    Some(prompt.source_message_id),
+                )
+                .await?;
+            }
+            None => {
+                start_resource_picker(
+                    &bot,
+                    message.chat.id,
+                    &state,
+                    &prompt.text,
+                    Some(prompt.source_message_id),
+                )
+                .await?;
+            }
+        },
         "cancel" => {}
         _ => {
             let mut prompts = state.add_prompts.lock().await;
@@ -108,6 +138,70 @@ async fn handle_add_callback(
     Ok(())
 }
 
+async fn handle_title_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let Some(message) = q.message.clone() else {
+        return Ok(());
+    };
+    let Some(data) = q.data.as_deref() else {
+        return Ok(());
+    };
+    let mut parts = data.split(':');
+    let _ = parts.next();
+    let prompt_id = match parts.next() {
+        Some(id) => id.to_string(),
+        None => return Ok(()),
+    };
+    let action = match parts.next() {
+        Some(action) => action,
+        None => return Ok(()),
+    };
+
+    let prompt = {
+        let mut prompts = state.title_prompts.lock().await;
+        let prompt = match prompts.remove(&prompt_id) {
+            Some(prompt) => prompt,
+            None => {
+                bot.answer_callback_query(q.id).await?;
+                return Ok(());
+            }
+        };
+        if prompt.chat_id != message.chat.id.0 || prompt.message_id != message.id {
+            prompts.insert(prompt_id.clone(), prompt);
+            bot.answer_callback_query(q.id).await?;
+            return Ok(());
+        }
+        prompt
+    };
+
+    let text = match action {
+        _ if prompt.expires_at <= now_ts() => prompt.link.clone(),
+        "use" => format!("[{}]({})", prompt.title, prompt.link),
+        "raw" => prompt.link.clone(),
+        _ => {
+            let mut prompts = state.title_prompts.lock().await;
+            prompts.insert(prompt_id, prompt);
+            bot.answer_callback_query(q.id).await?;
+            return Ok(());
+        }
+    };
+    save_single_item(
+        bot.clone(),
+        message.chat.id,
+        state.clone(),
+        &text,
+        prompt.source_message_id,
+    )
+    .await?;
+
+    let _ = bot.delete_message(message.chat.id, message.id).await;
+    bot.answer_callback_query(q.id).await?;
+    Ok(())
+}
+
 async fn handle_resource_callback(
     bot: Bot,
     q: CallbackQuery,
@@ -159,7 +253,7 @@ async fn handle_resource_callback(
                         &state,
                         path,
                         &picker.text,
-                        picker.source_message_id.clone(),
+                        picker.source_message_id,
                     )
                     .await?;
                     let _ = bot.delete_message(message.chat.id, message.id).await;
@@ -172,10 +266,10 @@ async fn handle_resource_callback(
         }
         "new" => {
             let prompt_text = "Send the new resource filename (example: Resources.md).";
-            let sent = bot.send_message(message.chat.id, prompt_text).await?;
+            let sent = send(&bot, &state.config, message.chat.id, prompt_text).await?;
             let prompt = ResourceFilenamePrompt {
                 text: picker.text.clone(),
-                source_message_id: picker.source_message_id.clone(),
+                source_message_id: picker.source_message_id,
                 prompt_message_id: sent.id,
                 expires_at: now_ts() + RESOURCE_PROMPT_TTL_SECS,
             };
@@ -191,6 +285,34 @@ async fn handle_resource_callback(
             }
             let _ = bot.delete_message(message.chat.id, message.id).await;
         }
+        "other" => {
+            if let Some(path) = picker.added_to.as_ref() {
+                let entry_block = resource_block_from_text(&picker.text);
+                let result = {
+                    let _guard = state.write_lock.lock().await;
+                    delete_entry_sync(path, &entry_block, None)
+                };
+                match result {
+                    Ok(_) => {
+                        let _ = bot.delete_message(message.chat.id, message.id).await;
+                        start_resource_picker(&bot, message.chat.id, &state, &picker.text, None)
+                            .await?;
+                    }
+                    Err(err) => {
+                        send_error(
+                            &bot,
+                            &state.config,
+                            message.chat.id,
+                            &format!("Couldn't move item: {}", err),
+                        )
+                        .await?;
+                        reinsert = true;
+                    }
+                }
+            } else {
+                reinsert = true;
+            }
+        }
         "cancel" => {
             let _ = bot.delete_message(message.chat.id, message.id).await;
         }
@@ -211,6 +333,114 @@ async fn handle_resource_callback(
     Ok(())
 }
 
+async fn handle_resource_browser_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let Some(message) = q.message.clone() else {
+        return Ok(());
+    };
+    let Some(data) = q.data.as_deref() else {
+        return Ok(());
+    };
+    let mut parts = data.split(':');
+    let _ = parts.next();
+    let browser_id = match parts.next() {
+        Some(id) => id.to_string(),
+        None => return Ok(()),
+    };
+    let action = match parts.next() {
+        Some(action) => action,
+        None => return Ok(()),
+    };
+
+    let browser = {
+        let mut browsers = state.resource_browsers.lock().await;
+        let browser = match browsers.remove(&browser_id) {
+            Some(browser) => browser,
+            None => {
+                bot.answer_callback_query(q.id).await?;
+                return Ok(());
+            }
+        };
+        if browser.chat_id != message.chat.id.0 || browser.message_id != message.id {
+            browsers.insert(browser_id.clone(), browser);
+            bot.answer_callback_query(q.id).await?;
+            return Ok(());
+        }
+        browser
+    };
+
+    let mut reinsert = false;
+    match action {
+        "file" => {
+            let path = parts
+                .next()
+                .and_then(|p| p.parse::<usize>().ok())
+                .and_then(|index| browser.files.get(index).cloned());
+            if let Some(path) = path {
+                let entries = read_entries(&path)?.1;
+                let session_id = short_id();
+                let mut session = ListSession {
+                    id: session_id.clone(),
+                    chat_id: message.chat.id.0,
+                    kind: SessionKind::Resource { path },
+                    entries,
+                    view: ListView::Peek {
+                        mode: ListMode::Top,
+                        page: 0,
+                    },
+                    seen_random: HashSet::new(),
+                    message_id: Some(message.id),
+                    sent_media_message_ids: Vec::new(),
+                    hide_media: false,
+                    session_undo: Vec::new(),
+                    last_activity: now_ts(),
+                };
+                let peeked_snapshot = state.peeked.lock().await.clone();
+                show_list_view(
+                    &bot,
+                    message.chat.id,
+                    &state,
+                    &mut session,
+                    &peeked_snapshot,
+                )
+                .await?;
+                state
+                    .sessions
+                    .lock()
+                    .await
+                    .insert(session_id.clone(), session);
+                state
+                    .active_sessions
+                    .lock()
+                    .await
+                    .insert(message.chat.id.0, session_id);
+            } else {
+                reinsert = true;
+            }
+        }
+        "cancel" => {
+            let _ = bot.delete_message(message.chat.id, message.id).await;
+        }
+        _ => {
+            reinsert = true;
+        }
+    }
+
+    if reinsert {
+        state
+            .resource_browsers
+            .lock()
+            .await
+            .insert(browser_id, browser);
+    }
+
+    bot.answer_callback_query(q.id).await?;
+    Ok(())
+}
+
 async fn handle_download_callback(
     bot: Bot,
     q: CallbackQuery,
@@ -254,93 +484,29 @@ async fn handle_download_callback(
     bot.answer_callback_query(q.id).await?;
 
     match action {
-        "send" => {
-            if !matches!(picker.mode, DownloadPickerMode::Links) {
-                reinsert = true;
-            } else {
-                let index = parts.next().and_then(|p| p.parse::<usize>().ok());
-                if let Some(index) = index {
-                    if let Some(link) = picker.links.get(index).cloned() {
-                        let link_for_probe = link.clone();
-                        let options = tokio::task::spawn_blocking(move || {
-                            run_ytdlp_list_formats(&link_for_probe)
-                        })
-                        .await
-                        .context("yt-dlp formats task failed")?;
-                        match options {
-                            Ok(options) => {
-                                let text = build_download_quality_text(
-                                    &link,
-                                    DownloadAction::Send,
-                                    &options,
-                                );
-                                let kb = build_download_quality_keyboard(&picker_id, &options);
-                                bot.edit_message_text(message.chat.id, message.id, text)
-                                    .reply_markup(kb)
-                                    .await?;
-                                picker.mode = DownloadPickerMode::Quality {
-                                    link_index: index,
-                                    action: DownloadAction::Send,
-                                    options,
-                                };
-                                reinsert = true;
-                            }
-                            Err(err) => {
-                                send_error(&bot, message.chat.id, &err.to_string()).await?;
-                                reinsert = true;
-                            }
-                        }
-                    } else {
-                        reinsert = true;
-                    }
-                } else {
-                    reinsert = true;
-                }
-            }
-        }
-        "save" => {
-            if !matches!(picker.mode, DownloadPickerMode::Links) {
-                reinsert = true;
-            } else {
-                let index = parts.next().and_then(|p| p.parse::<usize>().ok());
-                if let Some(index) = index {
-                    if let Some(link) = picker.links.get(index).cloned() {
-                        let link_for_probe = link.clone();
-                        let options = tokio::task::spawn_blocking(move || {
-                            run_ytdlp_list_formats(&link_for_probe)
-                        })
-                        .await
-                        .context("yt-dlp formats task failed")?;
-                        match options {
-                            Ok(options) => {
-                                let text = build_download_quality_text(
-                                    &link,
-                                    DownloadAction::Save,
-                                    &options,
-                                );
-                                let kb = build_download_quality_keyboard(&picker_id, &options);
-                                bot.edit_message_text(message.chat.id, message.id, text)
-                                    .reply_markup(kb)
-                                    .await?;
-                                picker.mode = DownloadPickerMode::Quality {
-                                    link_index: index,
-                                    action: DownloadAction::Save,
-                                    options,
-                                };
-                                reinsert = true;
-                            }
-                            Err(err) => {
-                                send_error(&bot, message.chat.id, &err.to_string()).await?;
-                                reinsert = true;
-                            }
-                        }
-                    } else {
-                        reinsert = true;
-                    }
-                } else {
-                    reinsert = true;
-                }
+        "send" | "save" | "sendsave" | "link" => {
+            let download_action = match action {
+                "send" => Some(DownloadAction::Send),
+                "save" => Some(DownloadAction::Save),
+                "sendsave" => Some(DownloadAction::SendAndSave),
+                _ => download_action_shortcut(state.config.default_download_action),
+            };
+            let index = parts.next().and_then(|p| p.parse::<usize>().ok());
+            if let (Some(download_action), Some(index), DownloadPickerMode::Links) =
+                (download_action, index, &picker.mode)
+            {
+                show_download_quality_menu(
+                    &bot,
+                    &state,
+                    &message,
+                    &picker_id,
+                    &mut picker,
+                    index,
+                    download_action,
+                )
+                .await?;
             }
+            reinsert = true;
         }
         "quality" => {
             let selected = parts.next().and_then(|p| p.parse::<usize>().ok());
@@ -358,12 +524,67 @@ async fn handle_download_callback(
                     options.get(selected).cloned(),
                 ) {
                     match action {
+                        // Subtitles are sent whatever the action; there is no media to keep.
+                        _ if option.format_selector == SUBTITLES_FORMAT_SELECTOR => {
+                            match download_and_send_subtitles(&bot, message.chat.id, &link).await {
+                                Ok(true) => {
+                                    let _ = bot.delete_message(message.chat.id, message.id).await;
+                                }
+                                Ok(false) => {
+                                    send_error(
+                                        &bot,
+                                        &state.config,
+                                        message.chat.id,
+                                        "No subtitles found",
+                                    )
+                                    .await?;
+                                    reinsert = true;
+                                }
+                                Err(err) => {
+                                    send_error(
+                                        &bot,
+                                        &state.config,
+                                        message.chat.id,
+                                        &err.to_string(),
+                                    )
+                                    .await?;
+                                    reinsert = true;
+                                }
+                            }
+                        }
+                        _ if option.format_selector == PLAYLIST_FORMAT_SELECTOR => {
+                            match download_playlist(&bot, &state, message.chat.id, &link, *action)
+                                .await
+                            {
+                                Ok(note) => {
+                                    send_message_with_delete_button(
+                                        &bot,
+                                        &state.config,
+                                        message.chat.id,
+                                        note,
+                                    )
+                                    .await?;
+                                    let _ = bot.delete_message(message.chat.id, message.id).await;
+                                }
+                                Err(err) => {
+                                    send_error(
+                                        &bot,
+                                        &state.config,
+                                        message.chat.id,
+                                        &err.to_string(),
+                                    )
+                                    .await?;
+                                    reinsert = true;
+                                }
+                            }
+                        }
                         DownloadAction::Send => {
                             match download_and_send_link(
                                 &bot,
                                 message.chat.id,
                                 &link,
                                 &option.format_selector,
+                                state.config.max_send_bytes,
                             )
                             .await
                             {
@@ -371,7 +592,13 @@ async fn handle_download_callback(
                                     let _ = bot.delete_message(message.chat.id, message.id).await;
                                 }
                                 Err(err) => {
-                                    send_error(&bot, message.chat.id, &err.to_string()).await?;
+                                    send_error(
+                                        &bot,
+                                        &state.config,
+                                        message.chat.id,
+                                        &err.to_string(),
+                                    )
+                                    .await?;
                                     reinsert = true;
                                 }
                             }
@@ -382,12 +609,59 @@ async fn handle_download_callback(
                             {
                                 Ok(path) => {
                                     let note = format!("Saved to {}", path.display());
-                                    send_message_with_delete_button(&bot, message.chat.id, note)
-                                        .await?;
+                                    send_message_with_delete_button(
+                                        &bot,
+                                        &state.config,
+                                        message.chat.id,
+                                        note,
+                                    )
+                                    .await?;
+                                    let _ = bot.delete_message(message.chat.id, message.id).await;
+                                }
+                                Err(err) => {
+                                    send_error(
+                                        &bot,
+                                        &state.config,
+                                        message.chat.id,
+                                        &err.to_string(),
+                                    )
+                                    .await?;
+                                    reinsert = true;
+                                }
+                            }
+                        }
+                        DownloadAction::SendAndSave => {
+                            match download_save_and_send_link(
+                                &bot,
+                                &state,
+                                message.chat.id,
+                                &link,
+                                &option.format_selector,
+                            )
+                            .await
+                            {
+                                Ok((path, size_error)) => {
+                                    let mut note = format!("Saved to {}", path.display());
+                                    if let Some(size_error) = size_error {
+                                        note = format!("{}\n{}", note, size_error);
+                                    }
+                                    send_message_with_delete_button(
+                                        &bot,
+                                        &state.config,
+                                        message.chat.id,
+                                        note,
+                                    )
+                                    .await?;
                                     let _ = bot.delete_message(message.chat.id, message.id).await;
                                 }
                                 Err(err) => {
-                                    send_error(&bot, message.chat.id, &err.to_string()).await?;
+                                    send_error(
+                                        &bot,
+                                        &state.config,
+                                        message.chat.id,
+                                        &err.to_string(),
+                                    )
+                                    .await?;
                                     reinsert = true;
                                 }
                             }
@@ -403,8 +677,12 @@ async fn handle_download_callback(
         "back" => {
             if matches!(picker.mode, DownloadPickerMode::Quality { .. }) {
                 let text = build_download_picker_text(&picker.links);
-                let kb = build_download_picker_keyboard(&picker_id, &picker.links);
-                bot.edit_message_text(message.chat.id, message.id, text)
+                let kb = build_download_picker_keyboard(
+                    &picker_id,
+                    &picker.links,
+                    state.config.default_download_action,
+                );
+                edit(&bot, &state.config, message.chat.id, message.id, text)
                     .reply_markup(kb)
                     .await?;
                 picker.mode = DownloadPickerMode::Links;
@@ -416,7 +694,7 @@ async fn handle_download_callback(
         "add" => {
             if matches!(picker.mode, DownloadPickerMode::Links) {
                 let prompt_text = "Send a link to add.";
-                let sent = bot.send_message(message.chat.id, prompt_text).await?;
+                let sent = send(&bot, &state.config, message.chat.id, prompt_text).await?;
                 let prompt = DownloadLinkPrompt {
                     links: picker.links.clone(),
                     prompt_message_id: sent.id,
@@ -456,6 +734,42 @@ async fn handle_download_callback(
     Ok(())
 }
 
+async fn show_download_quality_menu(
+    bot: &Bot,
+    state: &std::sync::Arc<AppState>,
+    message: &Message,
+    picker_id: &str,
+    picker: &mut DownloadPickerState,
+    index: usize,
+    action: DownloadAction,
+) -> Result<()> {
+    let Some(link) = picker.links.get(index).cloned() else {
+        return Ok(());
+    };
+    let link_for_probe = link.clone();
+    let options = tokio::task::spawn_blocking(move || run_ytdlp_list_formats(&link_for_probe))
+        .await
+        .context("yt-dlp formats task failed")?;
+    match options {
+        Ok(options) => {
+            let text = build_download_quality_text(&link, action, &options);
+            let kb = build_download_quality_keyboard(picker_id, &options);
+            edit(bot, &state.config, message.chat.id, message.id, text)
+                .reply_markup(kb)
+                .await?;
+            picker.mode = DownloadPickerMode::Quality {
+                link_index: index,
+                action,
+                options,
+            };
+        }
+        Err(err) => {
+            send_error(bot, &state.config, message.chat.id, &err.to_string()).await?;
+        }
+    }
+    Ok(())
+}
+
 async fn handle_message_delete_callback(bot: Bot, q: CallbackQuery) -> Result<()> {
     if let Some(message) = q.message.clone() {
         let _ = bot.delete_message(message.chat.id, message.id).await;
@@ -503,10 +817,14 @@ async fn handle_list_callback(
         }
         session
     };
+    session.last_activity = now_ts();
 
-    let peeked_snapshot = state.peeked.lock().await.clone();
+    let mut peeked_snapshot = state.peeked.lock().await.clone();
     let mut refresh_list_view = true;
     let mut close_session = false;
+    // Set when this action itself peeked items, so an auto-reset waits for
+    // the next pass instead of undoing what was just marked.
+    let mut just_peeked = false;
 
     let action_result: Result<()> = async {
         match action {
@@ -553,7 +871,10 @@ async fn handle_list_callback(
                 };
             }
             "close" => {
-                if matches!(&session.kind, SessionKind::Search { .. }) {
+                if matches!(
+                    &session.kind,
+                    SessionKind::Search { .. } | SessionKind::Resource { .. }
+                ) {
                     delete_embedded_media_messages(
                         &bot,
                         message.chat.id,
@@ -569,24 +890,72 @@ async fn handle_list_callback(
                     refresh_list_view = false;
                 }
             }
+            "media" => {
+                session.hide_media = !session.hide_media;
+            }
+            "export" => {
+                refresh_list_view = false;
+                if !session.entries.is_empty() {
+                    let (name, content) = session_export(&session);
+                    bot.send_document(
+                        message.chat.id,
+                        InputFile::memory(content.into_bytes()).file_name(name),
+                    )
+                    .await?;
+                }
+            }
+            "peekmedia" => {
+                // Leaves the view and peeked set alone; the media is cleared
+                // with the rest on the next navigation.
+                refresh_list_view = false;
+                let position = parts.next().and_then(|p| p.parse::<usize>().ok());
+                if let Some(position) = position {
+                    let paths =
+                        peek_item_media_paths(&session, &peeked_snapshot, position, &state.config);
+                    let sent = send_media_paths(&bot, message.chat.id, paths).await?;
+                    session.sent_media_message_ids.extend(sent);
+                }
+            }
             "random" => {
                 if matches!(&session.kind, SessionKind::List) {
                     if session.entries.is_empty() {
                         // Stay in place.
                     } else {
-                        let mut remaining: Vec<usize> = (0..session.entries.len())
-                            .filter(|i| !session.seen_random.contains(i))
-                            .filter(|i| {
-                                session
-                                    .entries
-                                    .get(*i)
-                                    .map(|entry| !peeked_snapshot.contains(&entry.block_string()))
-                                    .unwrap_or(false)
-                            })
-                            .collect();
+                        let mut remaining = random_candidates(
+                            &session.entries,
+                            &session.seen_random,
+                            &peeked_snapshot,
+                            state.config.random_skip_archived,
+                        );
+                        if remaining.is_empty()
+                            && should_auto_reset_peeked(
+                                &state.config,
+                                &session.entries,
+                                &peeked_snapshot,
+                            )
+                        {
+                            reset_peeked(&state).await?;
+                            peeked_snapshot.clear();
+                            session.seen_random.clear();
+                            send_ephemeral(
+                                &bot,
+                                &state.config,
+                                message.chat.id,
+                                PEEKED_AUTO_RESET_TEXT,
+                                ACK_TTL_SECS,
+                            )
+                            .await?;
+                            remaining = random_candidates(
+                                &session.entries,
+                                &session.seen_random,
+                                &peeked_snapshot,
+                                state.config.random_skip_archived,
+                            );
+                        }
                         if remaining.is_empty() {
                             send_ephemeral(
                                 &bot,
+                                &state.config,
                                 message.chat.id,
                                 "Everything's been peeked already.",
                                 ACK_TTL_SECS,
@@ -605,7 +974,7 @@ async fn handle_list_callback(
                                 let return_to = Box::new(session.view.clone());
                                 session.view = ListView::Selected { return_to, index };
                                 if let Some(entry) = session.entries.get(index) {
-                                    state.peeked.lock().await.insert(entry.block_string());
+                                    mark_peeked(&state, entry).await?;
                                 }
                             }
                         }
@@ -617,9 +986,15 @@ async fn handle_list_callback(
                     let pick_index = parts.next().and_then(|p| p.parse::<usize>().ok());
                     if let Some(pick_index) = pick_index {
                         if let Some(entry_index) =
-                            peek_indices_for_session(&session, &peeked_snapshot, mode, page)
-                                .get(pick_index.saturating_sub(1))
-                                .copied()
+                            peek_indices_for_session(
+                                &session,
+                                &peeked_snapshot,
+                                mode,
+                                page,
+                                state.config.page_size,
+                            )
+                            .get(pick_index.saturating_sub(1))
+                            .copied()
                         {
                             let return_to = Box::new(ListView::Peek { mode, page });
                             session.view = ListView::Selected {
@@ -628,11 +1003,109 @@ async fn handle_list_callback(
                             };
                             if matches!(&session.kind, SessionKind::List) {
                                 if let Some(entry) = session.entries.get(entry_index) {
-                                    state.peeked.lock().await.insert(entry.block_string());
+                                    mark_peeked(&state, entry).await?;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            "togglepeek" => {
+                if let (ListView::Selected { index, .. }, SessionKind::List) =
+                    (&session.view, &session.kind)
+                {
+                    if let Some(entry) = session.entries.get(*index) {
+                        let mut peeked = state.peeked.lock().await;
+                        toggle_peeked(&mut peeked, entry);
+                        save_peeked(&state.peeked_path, &peeked)?;
+                        peeked_snapshot = peeked.clone();
+                    }
+                }
+            }
+            "peekall" => {
+                if let (ListView::Peek { mode, page }, SessionKind::List) =
+                    (session.view.clone(), &session.kind)
+                {
+                    let shown = peek_indices_for_session(
+                        &session,
+                        &peeked_snapshot,
+                        mode,
+                        page,
+                        state.config.page_size,
+                    );
+                    {
+                        let mut peeked = state.peeked.lock().await;
+                        for index in shown {
+                            if let Some(entry) = session.entries.get(index) {
+                                peeked.insert(entry.block_string());
+                            }
+                        }
+                        save_peeked(&state.peeked_path, &peeked)?;
+                        peeked_snapshot = peeked.clone();
+                    }
+                    just_peeked = true;
+                    normalize_peek_view(
+                        &mut session,
+                        &peeked_snapshot,
+                        state.config.page_size,
+                    );
+                }
+            }
+            "finishpage" => {
+                if let (ListView::Peek { mode, page }, SessionKind::List) =
+                    (session.view.clone(), &session.kind)
+                {
+                    // Indices shift as items are removed, so resolve the blocks first.
+                    let blocks: Vec<String> = peek_indices_for_session(
+                        &session,
+                        &peeked_snapshot,
+                        mode,
+                        page,
+                        state.config.page_size,
+                    )
+                    .into_iter()
+                    .filter_map(|index| session.entries.get(index).map(|e| e.block_string()))
+                    .collect();
+                    let mut moved = 0;
+                    let mut queued = 0;
+                    for block in &blocks {
+                        let op = QueuedOp {
+                            kind: QueuedOpKind::MoveToFinished,
+                            entry: block.clone(),
+                            resource_path: None,
+                            updated_entry: None,
+                            position: None,
+                        };
+                        match apply_user_op(&state, &op).await? {
+                            UserOpOutcome::Applied(ApplyOutcome::Applied) => {
+                                let undo_id =
+                                    add_undo(&state, UndoKind::MoveToFinished, block.clone())
+                                        .await?;
+                                if let Some(index) =
+                                    session.entries.iter().position(|e| e.block_string() == *block)
+                                {
+                                    let entry = session.entries.remove(index);
+                                    session.session_undo.push(SessionUndo {
+                                        undo_id,
+                                        entry,
+                                        index,
+                                    });
                                 }
+                                moved += 1;
                             }
+                            UserOpOutcome::Applied(_) => {}
+                            UserOpOutcome::Queued => queued += 1,
                         }
                     }
+                    normalize_peek_view(&mut session, &peeked_snapshot, state.config.page_size);
+                    if !blocks.is_empty() {
+                        let mut text = format!("Moved {} of {}.", moved, blocks.len());
+                        if queued > 0 {
+                            text.push_str(&format!(" {} queued for retry.", queued));
+                        }
+                        send_ephemeral(&bot, &state.config, message.chat.id, &text, ACK_TTL_SECS)
+                            .await?;
+                    }
                 }
             }
             "finish" => {
@@ -643,6 +1116,83 @@ async fn handle_list_callback(
                     };
                 }
             }
+            "archived" => {
+                if let ListView::Selected { index, .. } = session.view.clone() {
+                    let marked = session.entries.get(index).and_then(|entry| {
+                        mark_archived(entry).map(|marked| (entry.block_string(), marked))
+                    });
+                    if let Some((entry_block, marked)) = marked {
+                        let op = QueuedOp {
+                            kind: QueuedOpKind::UpdateEntry,
+                            entry: entry_block,
+                            resource_path: session_target_path(&session),
+                            updated_entry: Some(marked.block_string()),
+                            position: None,
+                        };
+                        match apply_user_op(&state, &op).await? {
+                            UserOpOutcome::Applied(ApplyOutcome::Applied) => {
+                                session.entries[index] = marked;
+                            }
+                            UserOpOutcome::Applied(ApplyOutcome::NotFound) => {
+                                send_error(&bot, &state.config, message.chat.id, "Item not found.")
+                                    .await?;
+                            }
+                            UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+                            | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {}
+                            UserOpOutcome::Queued => {
+                                send_error(
+                                    &bot,
+                                    &state.config,
+                                    message.chat.id,
+                                    "Write failed; queued for retry.",
+                                )
+                                .await?;
+                            }
+                        }
+                    }
+                }
+            }
+            "pin" | "unpin" => {
+                if let ListView::Selected { index, .. } = session.view.clone() {
+                    let updated = session.entries.get(index).and_then(|entry| {
+                        let updated = if action == "pin" {
+                            pin_entry(entry)
+                        } else {
+                            unpin_entry(entry)
+                        };
+                        updated.map(|updated| (entry.block_string(), updated))
+                    });
+                    if let Some((entry_block, updated)) = updated {
+                        let op = QueuedOp {
+                            kind: QueuedOpKind::UpdateEntry,
+                            entry: entry_block,
+                            resource_path: session_target_path(&session),
+                            updated_entry: Some(updated.block_string()),
+                            position: None,
+                        };
+                        match apply_user_op(&state, &op).await? {
+                            UserOpOutcome::Applied(ApplyOutcome::Applied) => {
+                                session.entries[index] = updated;
+                            }
+                            UserOpOutcome::Applied(ApplyOutcome::NotFound) => {
+                                send_error(&bot, &state.config, message.chat.id, "Item not found.")
+                                    .await?;
+                            }
+                            UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+                            | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {}
+                            UserOpOutcome::Queued => {
+                                send_error(
+                                    &bot,
+                                    &state.config,
+                                    message.chat.id,
+                                    "Write failed; queued for retry.",
+                                )
+                                .await?;
+                            }
+                        }
+                    }
+                }
+            }
             "finish_now" => {
                 if let ListView::FinishConfirm { selected, index } = session.view.clone() {
                     let entry_block = session.entries.get(index).map(|e| e.block_string());
@@ -652,31 +1202,50 @@ async fn handle_list_callback(
                             entry: entry_block.clone(),
                             resource_path: None,
                             updated_entry: None,
+                            position: None,
                         };
                         match apply_user_op(&state, &op).await? {
                             UserOpOutcome::Applied(ApplyOutcome::Applied) => {
-                                session.entries.remove(index);
+                                let entry = session.entries.remove(index);
                                 if let ListView::Selected { return_to, .. } = *selected {
                                     session.view = *return_to;
                                 } else {
                                     session.view = ListView::Menu;
                                 }
-                                normalize_peek_view(&mut session, &peeked_snapshot);
-                                send_ephemeral(&bot, message.chat.id, "Moved.", ACK_TTL_SECS)
-                                    .await?;
-                                let _ =
+                                normalize_peek_view(
+                                    &mut session,
+                                    &peeked_snapshot,
+                                    state.config.page_size,
+                                );
+                                send_ephemeral(
+                                    &bot,
+                                    &state.config,
+                                    message.chat.id,
+                                    "Moved.",
+                                    ACK_TTL_SECS,
+                                )
+                                .await?;
+                                let undo_id =
                                     add_undo(&state, UndoKind::MoveToFinished, entry_block).await?;
+                                session.session_undo.push(SessionUndo {
+                                    undo_id,
+                                    entry,
+                                    index,
+                                });
                             }
                             UserOpOutcome::Applied(ApplyOutcome::NotFound) => {
-                                send_error(&bot, message.chat.id, "Item not found.").await?;
+                                send_error(&bot, &state.config, message.chat.id, "Item not found.")
+                                    .await?;
                                 session.view = *selected;
                             }
-                            UserOpOutcome::Applied(ApplyOutcome::Duplicate) => {
+                            UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+                            | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {
                                 session.view = *selected;
                             }
                             UserOpOutcome::Queued => {
                                 send_error(
                                     &bot,
+                                    &state.config,
                                     message.chat.id,
                                     "Write failed; queued for retry.",
                                 )
@@ -689,49 +1258,184 @@ async fn handle_list_callback(
             }
             "finish_title" => {
                 if let ListView::FinishConfirm { selected, index } = session.view.clone() {
-                    let selected_view = *selected;
-                    if let Some(entry) = session.entries.get(index) {
-                        let text = entry.display_lines().join("\n");
-                        let links = extract_links(&text);
-                        if let Some(link) = links.first().cloned() {
-                            let prompt_text = "Send a title for the finished item.";
-                            let sent = bot.send_message(message.chat.id, prompt_text).await?;
-                            let return_to = match selected_view.clone() {
-                                ListView::Selected { return_to, .. } => *return_to,
-                                _ => ListView::Menu,
+                    let links = session
+                        .entries
+                        .get(index)
+                        .map(|entry| {
+                            let text = entry.display_lines().join("\n");
+                            extract_links(&text, state.config.min_link_length)
+                        })
+                        .unwrap_or_default();
+                    session.view = *selected.clone();
+                    match finish_title_step(links) {
+                        FinishTitleStep::NoLink => {
+                            let error = if index < session.entries.len() {
+                                "No link found for a title."
+                            } else {
+                                "Item not found."
                             };
-                            let prompt = FinishTitlePrompt {
-                                session_id: session.id.clone(),
-                                chat_id,
-                                entry: entry.block_string(),
-                                link,
-                                return_to,
-                                prompt_message_id: sent.id,
-                                expires_at: now_ts() + FINISH_TITLE_PROMPT_TTL_SECS,
+                            send_error(&bot, &state.config, message.chat.id, error).await?;
+                        }
+                        FinishTitleStep::Prompt(link) => {
+                            let mode = FinishMode::Title { link };
+                            open_finish_prompt(&bot, &state, &session, index, &selected, mode)
+                                .await?;
+                        }
+                        FinishTitleStep::PickLink(links) => {
+                            session.view = ListView::FinishLinkPick {
+                                selected,
+                                index,
+                                links,
                             };
-                            let previous = state
-                                .finish_title_prompts
-                                .lock()
-                                .await
-                                .insert(chat_id, prompt);
-                            if let Some(previous) = previous {
-                                let _ = bot
-                                    .delete_message(message.chat.id, previous.prompt_message_id)
-                                    .await;
-                            }
-                            session.view = selected_view;
-                        } else {
-                            send_error(&bot, message.chat.id, "No link found for a title.").await?;
-                            session.view = selected_view;
                         }
+                    }
+                }
+            }
+            "finish_link" => {
+                if let ListView::FinishLinkPick {
+                    selected,
+                    index,
+                    links,
+                } = session.view.clone()
+                {
+                    let link = parts
+                        .next()
+                        .and_then(|p| p.parse::<usize>().ok())
+                        .and_then(|choice| links.get(choice).cloned());
+                    if let Some(link) = link {
+                        let mode = FinishMode::Title { link };
+                        if !open_finish_prompt(&bot, &state, &session, index, &selected, mode)
+                            .await?
+                        {
+                            send_error(&bot, &state.config, message.chat.id, "Item not found.")
+                                .await?;
+                        }
+                        session.view = *selected;
+                    }
+                }
+            }
+            "finish_note" => {
+                if let ListView::FinishConfirm { selected, index } = session.view.clone() {
+                    if !open_finish_prompt(&bot, &state, &session, index, &selected, FinishMode::Note)
+                        .await?
+                    {
+                        send_error(&bot, &state.config, message.chat.id, "Item not found.").await?;
+                    }
+                    session.view = *selected;
+                }
+            }
+            "finish_resource" => {
+                if let ListView::FinishConfirm { selected, index } = session.view.clone() {
+                    let files = list_resource_files(&state.config.resources_path)?;
+                    if files.is_empty() {
+                        send_error(
+                            &bot,
+                            &state.config,
+                            message.chat.id,
+                            "No resource files found.",
+                        )
+                        .await?;
                     } else {
-                        send_error(&bot, message.chat.id, "Item not found.").await?;
-                        session.view = selected_view;
+                        session.view = ListView::FinishResourcePick {
+                            selected,
+                            index,
+                            files,
+                        };
+                    }
+                }
+            }
+            "finish_res" => {
+                if let ListView::FinishResourcePick {
+                    selected,
+                    index,
+                    files,
+                } = session.view.clone()
+                {
+                    let resource_path = parts
+                        .next()
+                        .and_then(|p| p.parse::<usize>().ok())
+                        .and_then(|choice| files.get(choice).cloned());
+                    let entry_block = session.entries.get(index).map(|e| e.block_string());
+                    if let (Some(resource_path), Some(entry_block)) = (resource_path, entry_block) {
+                        let op = QueuedOp {
+                            kind: QueuedOpKind::FinishToResource,
+                            entry: entry_block.clone(),
+                            resource_path: Some(resource_path.clone()),
+                            updated_entry: None,
+                            position: None,
+                        };
+                        // Run the op directly, rather than through `apply_user_op`,
+                        // to learn whether the resource block was added.
+                        let result = {
+                            let _guard = state.write_lock.lock().await;
+                            run_finish_to_resource(&state, &op).await
+                        };
+                        match result {
+                            Ok((ApplyOutcome::Applied, added)) => {
+                                let entry = session.entries.remove(index);
+                                if let ListView::Selected { return_to, .. } = *selected {
+                                    session.view = *return_to;
+                                } else {
+                                    session.view = ListView::Menu;
+                                }
+                                normalize_peek_view(
+                                    &mut session,
+                                    &peeked_snapshot,
+                                    state.config.page_size,
+                                );
+                                let note = format!(
+                                    "Moved to finished and added to {}.",
+                                    resource_file_label(&resource_path)
+                                );
+                                send_ephemeral(
+                                    &bot,
+                                    &state.config,
+                                    message.chat.id,
+                                    &note,
+                                    ACK_TTL_SECS,
+                                )
+                                .await?;
+                                let kind = UndoKind::FinishToResource {
+                                    resource_path,
+                                    added,
+                                };
+                                let undo_id = add_undo(&state, kind, entry_block).await?;
+                                session.session_undo.push(SessionUndo {
+                                    undo_id,
+                                    entry,
+                                    index,
+                                });
+                            }
+                            Ok((ApplyOutcome::NotFound, _)) => {
+                                send_error(&bot, &state.config, message.chat.id, "Item not found.")
+                                    .await?;
+                                session.view = *selected;
+                            }
+                            Ok((ApplyOutcome::Duplicate | ApplyOutcome::AlreadyFinished, _)) => {
+                                session.view = *selected;
+                            }
+                            // Not queued: a retry couldn't record whether it added the
+                            // resource block, so its undo might delete one that was there.
+                            Err(err) => {
+                                error!("write failed: {:#}", err);
+                                send_error(
+                                    &bot,
+                                    &state.config,
+                                    message.chat.id,
+                                    "Write failed; nothing was moved.",
+                                )
+                                .await?;
+                                session.view = *selected;
+                            }
+                        }
                     }
                 }
             }
             "finish_cancel" => {
-                if let ListView::FinishConfirm { selected, .. } = session.view.clone() {
+                if let ListView::FinishConfirm { selected, .. }
+                | ListView::FinishLinkPick { selected, .. }
+                | ListView::FinishResourcePick { selected, .. } = session.view.clone()
+                {
                     session.view = *selected;
                 }
             }
@@ -742,7 +1446,101 @@ async fn handle_list_callback(
                         start_resource_picker(&bot, message.chat.id, &state, &text, None).await?;
                         refresh_list_view = false;
                     } else {
-                        send_error(&bot, message.chat.id, "Item not found.").await?;
+                        send_error(&bot, &state.config, message.chat.id, "Item not found.").await?;
+                    }
+                }
+            }
+            "edit" | "title" | "category" | "due" | "moveto" => {
+                let mode = match action {
+                    "edit" => Some(EditMode::Text),
+                    "category" => Some(EditMode::Category),
+                    "due" => Some(EditMode::Due),
+                    "moveto" => Some(EditMode::Position),
+                    _ => selected_title_link(&session).map(|link| EditMode::Title { link }),
+                };
+                let opened = match mode {
+                    Some(mode) => open_edit_prompt(&bot, &state, &session, mode).await?,
+                    None => false,
+                };
+                if opened {
+                    refresh_list_view = false;
+                } else {
+                    send_error(&bot, &state.config, message.chat.id, "Item not found.").await?;
+                }
+            }
+            "undo_last" => {
+                if let Some(last) = session.session_undo.pop() {
+                    match take_undo_record(&state, &last.undo_id).await? {
+                        Some(record) => {
+                            let mut op = undo_op_for_record(&record, &state.config);
+                            let file_entries = read_entries(&state.config.read_later_path)?.1;
+                            op.position = Some(undo_file_position(
+                                &file_entries,
+                                &session.entries,
+                                last.index,
+                            ));
+                            match apply_user_op(&state, &op).await? {
+                                UserOpOutcome::Applied(ApplyOutcome::Applied) => {
+                                    reinsert_session_entry(
+                                        &mut session.entries,
+                                        last.entry,
+                                        last.index,
+                                    );
+                                    normalize_peek_view(
+                                        &mut session,
+                                        &peeked_snapshot,
+                                        state.config.page_size,
+                                    );
+                                    send_ephemeral(
+                                        &bot,
+                                        &state.config,
+                                        message.chat.id,
+                                        "Undone.",
+                                        ACK_TTL_SECS,
+                                    )
+                                    .await?;
+                                }
+                                UserOpOutcome::Applied(ApplyOutcome::NotFound)
+                                | UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+                                | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {
+                                    send_error(
+                                        &bot,
+                                        &state.config,
+                                        message.chat.id,
+                                        "Already undone.",
+                                    )
+                                    .await?;
+                                }
+                                UserOpOutcome::Queued => {
+                                    send_error(
+                                        &bot,
+                                        &state.config,
+                                        message.chat.id,
+                                        "Write failed; queued for retry.",
+                                    )
+                                    .await?;
+                                }
+                            }
+                        }
+                        None => {
+                            send_error(&bot, &state.config, message.chat.id, "Undo not found.")
+                                .await?;
+                        }
+                    }
+                }
+            }
+            "debug" => {
+                if let ListView::Selected { index, .. } = session.view.clone() {
+                    if let Some(entry) = session.entries.get(index) {
+                        let text = format!(
+                            "Raw item:\n\n{}",
+                            escape_nonprintable(&entry.block_string())
+                        );
+                        send_message_with_delete_button(&bot, &state.config, message.chat.id, text)
+                            .await?;
+                        refresh_list_view = false;
+                    } else {
+                        send_error(&bot, &state.config, message.chat.id, "Item not found.").await?;
                     }
                 }
             }
@@ -767,7 +1565,13 @@ async fn handle_list_callback(
                 {
                     if now_ts() > expires_at {
                         session.view = *selected;
-                        send_error(&bot, message.chat.id, "Delete confirmation expired.").await?;
+                        send_error(
+                            &bot,
+                            &state.config,
+                            message.chat.id,
+                            "Delete confirmation expired.",
+                        )
+                        .await?;
                     } else {
                         session.view = ListView::DeleteConfirm {
                             selected,
@@ -788,7 +1592,13 @@ async fn handle_list_callback(
                 {
                     if now_ts() > expires_at {
                         session.view = *selected;
-                        send_error(&bot, message.chat.id, "Delete confirmation expired.").await?;
+                        send_error(
+                            &bot,
+                            &state.config,
+                            message.chat.id,
+                            "Delete confirmation expired.",
+                        )
+                        .await?;
                     } else {
                         let entry_block = session.entries.get(index).map(|e| e.block_string());
                         if let Some(entry_block) = entry_block {
@@ -797,26 +1607,45 @@ async fn handle_list_callback(
                                 entry: entry_block.clone(),
                                 resource_path: None,
                                 updated_entry: None,
+                                position: None,
                             };
                             match apply_user_op(&state, &op).await? {
                                 UserOpOutcome::Applied(ApplyOutcome::Applied) => {
-                                    session.entries.remove(index);
+                                    let entry = session.entries.remove(index);
                                     if let ListView::Selected { return_to, .. } = *selected {
                                         session.view = *return_to;
                                     } else {
                                         session.view = ListView::Menu;
                                     }
-                                    normalize_peek_view(&mut session, &peeked_snapshot);
-                                    let _ = add_undo(&state, UndoKind::Delete, entry_block).await?;
+                                    normalize_peek_view(
+                                        &mut session,
+                                        &peeked_snapshot,
+                                        state.config.page_size,
+                                    );
+                                    let undo_id =
+                                        add_undo(&state, UndoKind::Delete, entry_block).await?;
+                                    session.session_undo.push(SessionUndo {
+                                        undo_id,
+                                        entry,
+                                        index,
+                                    });
                                 }
                                 UserOpOutcome::Applied(ApplyOutcome::NotFound) => {
-                                    send_error(&bot, message.chat.id, "Item not found.").await?;
+                                    send_error(
+                                        &bot,
+                                        &state.config,
+                                        message.chat.id,
+                                        "Item not found.",
+                                    )
+                                    .await?;
                                     session.view = *selected;
                                 }
-                                UserOpOutcome::Applied(ApplyOutcome::Duplicate) => {}
+                                UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+                                | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {}
                                 UserOpOutcome::Queued => {
                                     send_error(
                                         &bot,
+                                        &state.config,
                                         message.chat.id,
                                         "Write failed; queued for retry.",
                                     )
@@ -833,48 +1662,174 @@ async fn handle_list_callback(
                     session.view = *selected;
                 }
             }
-            _ => {}
-        }
-
-        if close_session {
-            return Ok(());
-        }
-
-        if refresh_list_view {
-            session.message_id = Some(message.id);
-            let (text, kb) =
-                render_list_view(&session.id, &session, &peeked_snapshot, &state.config);
-            match bot
-                .edit_message_text(message.chat.id, message.id, text)
-                .reply_markup(kb)
-                .await
+            "finish_all"
+                if matches!(session.kind, SessionKind::Search { .. })
+                    && matches!(session.view, ListView::Menu)
+                    && !session.entries.is_empty() =>
             {
-                Ok(_) => {}
-                Err(err) if is_message_not_modified_error(&err) => {}
-                Err(err) => {
-                    error!(
-                        "list view edit failed; sending replacement message instead: {:#}",
-                        err
-                    );
-                    let (fallback_text, fallback_kb) =
-                        render_list_view(&session.id, &session, &peeked_snapshot, &state.config);
-                    let sent = bot
-                        .send_message(message.chat.id, fallback_text)
-                        .reply_markup(fallback_kb)
-                        .await?;
-                    session.message_id = Some(sent.id);
-                }
+                session.view = ListView::FinishAllConfirm {
+                    step: 1,
+                    expires_at: now_ts() + FINISH_ALL_CONFIRM_TTL_SECS,
+                };
             }
-            if let Err(err) = refresh_embedded_media_for_view(
+            "finish_all1" => {
+                if let ListView::FinishAllConfirm { expires_at, .. } = session.view.clone() {
+                    if now_ts() > expires_at {
+                        session.view = ListView::Menu;
+                        send_error(
+                            &bot,
+                            &state.config,
+                            message.chat.id,
+                            "Finish confirmation expired.",
+                        )
+                        .await?;
+                    } else {
+                        session.view = ListView::FinishAllConfirm {
+                            step: 2,
+                            expires_at,
+                        };
+                    }
+                }
+            }
+            "finish_all2" => {
+                if let ListView::FinishAllConfirm { expires_at, .. } = session.view.clone() {
+                    if now_ts() > expires_at {
+                        session.view = ListView::Menu;
+                        send_error(
+                            &bot,
+                            &state.config,
+                            message.chat.id,
+                            "Finish confirmation expired.",
+                        )
+                        .await?;
+                    } else {
+                        let blocks: Vec<String> =
+                            session.entries.iter().map(|e| e.block_string()).collect();
+                        let result = {
+                            let _guard = state.write_lock.lock().await;
+                            move_many_to_finished_sync(
+                                &state.config.read_later_path,
+                                &state.config.finished_path,
+                                &blocks,
+                                finished_stamp_date(&state.config),
+                                state.config.finished_order,
+                            )
+                        };
+                        match result {
+                            Ok(moved) => {
+                                session.view = ListView::Menu;
+                                session
+                                    .entries
+                                    .retain(|e| !moved.contains(&e.block_string()));
+                                for block in &moved {
+                                    let _ = add_undo(&state, UndoKind::MoveToFinished, block.clone())
+                                        .await?;
+                                }
+                                send_ephemeral(
+                                    &bot,
+                                    &state.config,
+                                    message.chat.id,
+                                    &format!("Moved {}.", moved.len()),
+                                    ACK_TTL_SECS,
+                                )
+                                .await?;
+                            }
+                            Err(err) => {
+                                // Keep the final confirm up so the batch can be retried
+                                // as one write, with an undo record per moved entry.
+                                error!("finish all failed: {:#}", err);
+                                send_error(
+                                    &bot,
+                                    &state.config,
+                                    message.chat.id,
+                                    "Write failed; tap Confirm to retry.",
+                                )
+                                .await?;
+                            }
+                        }
+                    }
+                }
+            }
+            "cancel_finish_all" if matches!(session.view, ListView::FinishAllConfirm { .. }) => {
+                session.view = ListView::Menu;
+            }
+            _ => {}
+        }
+
+        if close_session {
+            return Ok(());
+        }
+
+        if refresh_list_view
+            && !just_peeked
+            && matches!(session.kind, SessionKind::List)
+            && matches!(session.view, ListView::Peek { .. })
+            && should_auto_reset_peeked(&state.config, &session.entries, &peeked_snapshot)
+        {
+            reset_peeked(&state).await?;
+            peeked_snapshot.clear();
+            session.seen_random.clear();
+            normalize_peek_view(&mut session, &peeked_snapshot, state.config.page_size);
+            send_ephemeral(
                 &bot,
+                &state.config,
                 message.chat.id,
-                &state,
-                &mut session,
-                &peeked_snapshot,
+                PEEKED_AUTO_RESET_TEXT,
+                ACK_TTL_SECS,
             )
-            .await
-            {
-                error!("send embedded media failed: {:#}", err);
+            .await?;
+        }
+
+        if refresh_list_view {
+            session.message_id = Some(message.id);
+            for step in list_update_order(&state.config) {
+                match step {
+                    ListUpdateStep::Text => {
+                        let (text, kb) = render_list_view(
+                            &session.id,
+                            &session,
+                            &peeked_snapshot,
+                            &state.config,
+                        );
+                        match edit(&bot, &state.config, message.chat.id, message.id, text)
+                            .reply_markup(kb)
+                            .await
+                        {
+                            Ok(_) => {}
+                            Err(err) if is_message_not_modified_error(&err) => {}
+                            Err(err) => {
+                                error!(
+                                    "list view edit failed; sending replacement message instead: {:#}",
+                                    err
+                                );
+                                let (fallback_text, fallback_kb) = render_list_view(
+                                    &session.id,
+                                    &session,
+                                    &peeked_snapshot,
+                                    &state.config,
+                                );
+                                let sent =
+                                    send(&bot, &state.config, message.chat.id, fallback_text)
+                                        .reply_markup(fallback_kb)
+                                        .await?;
+                                session.message_id = Some(sent.id);
+                            }
+                        }
+                    }
+                    ListUpdateStep::Media => {
+                        if let Err(err) = refresh_embedded_media_for_view(
+                            &bot,
+                            message.chat.id,
+                            &state,
+                            &mut session,
+                            &peeked_snapshot,
+                        )
+                        .await
+                        {
+                            error!("send embedded media failed: {:#}", err);
+                        }
+                    }
+                }
             }
         }
 
@@ -969,7 +1924,7 @@ async fn handle_picker_callback(
             }
             let text = build_picker_text(&picker.items, &picker.selected);
             let kb = build_picker_keyboard(&picker.id, &picker.selected);
-            bot.edit_message_text(message.chat.id, message.id, text)
+            edit(&bot, &state.config, message.chat.id, message.id, text)
                 .reply_markup(kb)
                 .await?;
             reinsert = true;
@@ -992,23 +1947,35 @@ async fn handle_picker_callback(
             let mut duplicates = 0usize;
             let mut queued = false;
             for item in selected_items {
-                let entry = EntryBlock::from_text(&item);
+                let entry = if state.config.track_added_dates {
+                    EntryBlock::from_text(&stamp_added_date(&item, Local::now().date_naive()))
+                } else {
+                    EntryBlock::from_text(&item)
+                };
                 let op = QueuedOp {
                     kind: QueuedOpKind::Add,
                     entry: entry.block_string(),
                     resource_path: None,
                     updated_entry: None,
+                    position: None,
                 };
                 match apply_user_op(&state, &op).await? {
                     UserOpOutcome::Applied(ApplyOutcome::Applied) => added += 1,
-                    UserOpOutcome::Applied(ApplyOutcome::Duplicate) => duplicates += 1,
+                    UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+                    | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => duplicates += 1,
                     UserOpOutcome::Applied(ApplyOutcome::NotFound) => {}
                     UserOpOutcome::Queued => queued = true,
                 }
             }
 
             if queued {
-                send_error(&bot, message.chat.id, "Write failed; queued for retry.").await?;
+                send_error(
+                    &bot,
+                    &state.config,
+                    message.chat.id,
+                    "Write failed; queued for retry.",
+                )
+                .await?;
             }
 
             let summary = if duplicates > 0 {
@@ -1019,11 +1986,15 @@ async fn handle_picker_callback(
             } else {
                 format!("Saved {} item(s).", added)
             };
-            send_ephemeral(&bot, message.chat.id, &summary, ACK_TTL_SECS).await?;
+            send_ephemeral(&bot, &state.config, message.chat.id, &summary, ACK_TTL_SECS).await?;
             if !queued {
-                let _ = bot
-                    .delete_message(ChatId(picker.chat_id), picker.source_message_id)
-                    .await;
+                delete_source_message(
+                    &bot,
+                    &state.config,
+                    ChatId(picker.chat_id),
+                    picker.source_message_id,
+                )
+                .await;
             }
             bot.delete_message(message.chat.id, message.id).await?;
         }
@@ -1097,20 +2068,7 @@ async fn handle_undos_callback(
                 bot.answer_callback_query(q.id).await?;
                 return Ok(());
             };
-            let op = match record.kind {
-                UndoKind::MoveToFinished => QueuedOp {
-                    kind: QueuedOpKind::MoveToReadLater,
-                    entry: record.entry,
-                    resource_path: None,
-                    updated_entry: None,
-                },
-                UndoKind::Delete => QueuedOp {
-                    kind: QueuedOpKind::Add,
-                    entry: record.entry,
-                    resource_path: None,
-                    updated_entry: None,
-                },
-            };
+            let op = undo_op_for_record(&record, &state.config);
 
             let mut undo = state.undo.lock().await;
             prune_undo(&mut undo);
@@ -1120,11 +2078,25 @@ async fn handle_undos_callback(
             match apply_user_op(&state, &op).await? {
                 UserOpOutcome::Applied(ApplyOutcome::Applied)
                 | UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+                | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished)
                 | UserOpOutcome::Applied(ApplyOutcome::NotFound) => {
-                    send_ephemeral(&bot, message.chat.id, "Undone.", ACK_TTL_SECS).await?;
+                    send_ephemeral(
+                        &bot,
+                        &state.config,
+                        message.chat.id,
+                        "Undone.",
+                        ACK_TTL_SECS,
+                    )
+                    .await?;
                 }
                 UserOpOutcome::Queued => {
-                    send_error(&bot, message.chat.id, "Write failed; queued for retry.").await?;
+                    send_error(
+                        &bot,
+                        &state.config,
+                        message.chat.id,
+                        "Write failed; queued for retry.",
+                    )
+                    .await?;
                 }
             }
         }
@@ -1149,7 +2121,536 @@ async fn handle_undos_callback(
         }
     }
 
+    let remaining = {
+        let undo = state.undo.lock().await;
+        undos_refresh_records(state.config.undos_refresh_in_place, &session.records, &undo)
+    };
+    if let Some(records) = remaining {
+        let (text, kb) = build_undos_view(&session_id, &records);
+        edit(&bot, &state.config, message.chat.id, message.id, text)
+            .reply_markup(kb)
+            .await?;
+        state
+            .undo_sessions
+            .lock()
+            .await
+            .insert(session_id, UndoSession { records, ..session });
+    } else {
+        let _ = bot.delete_message(message.chat.id, message.id).await;
+    }
+    bot.answer_callback_query(q.id).await?;
+    Ok(())
+}
+
+async fn handle_trash_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let Some(message) = q.message.clone() else {
+        return Ok(());
+    };
+    let Some(data) = q.data.as_deref() else {
+        return Ok(());
+    };
+
+    let mut parts = data.split(':');
+    let _ = parts.next();
+    let session_id = match parts.next() {
+        Some(id) => id.to_string(),
+        None => return Ok(()),
+    };
+    let action = match parts.next() {
+        Some(action) => action,
+        None => return Ok(()),
+    };
+
+    let mut session = {
+        let mut sessions = state.trash_sessions.lock().await;
+        let session = match sessions.remove(&session_id) {
+            Some(session) => session,
+            None => {
+                bot.answer_callback_query(q.id).await?;
+                return Ok(());
+            }
+        };
+        if session.chat_id != message.chat.id.0 || session.message_id != message.id {
+            sessions.insert(session_id, session);
+            bot.answer_callback_query(q.id).await?;
+            return Ok(());
+        }
+        session
+    };
+
+    match action {
+        "close" => {
+            let _ = bot.delete_message(message.chat.id, message.id).await;
+            bot.answer_callback_query(q.id).await?;
+            return Ok(());
+        }
+        "restore" => {
+            let entry = parts
+                .next()
+                .and_then(|p| p.parse::<usize>().ok())
+                .and_then(|index| session.entries.get(index).cloned());
+            if let Some(entry) = entry {
+                let op = QueuedOp {
+                    kind: QueuedOpKind::RestoreFromTrash,
+                    entry: entry.block_string(),
+                    resource_path: None,
+                    updated_entry: None,
+                    position: None,
+                };
+                match apply_user_op(&state, &op).await? {
+                    UserOpOutcome::Applied(ApplyOutcome::Applied) => {
+                        send_ephemeral(
+                            &bot,
+                            &state.config,
+                            message.chat.id,
+                            "Restored.",
+                            ACK_TTL_SECS,
+                        )
+                        .await?;
+                    }
+                    UserOpOutcome::Applied(ApplyOutcome::NotFound)
+                    | UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+                    | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {
+                        send_ephemeral(
+                            &bot,
+                            &state.config,
+                            message.chat.id,
+                            "Already restored.",
+                            ACK_TTL_SECS,
+                        )
+                        .await?;
+                    }
+                    UserOpOutcome::Queued => {
+                        send_error(
+                            &bot,
+                            &state.config,
+                            message.chat.id,
+                            "Write failed; queued for retry.",
+                        )
+                        .await?;
+                    }
+                }
+                if let Some(trash_path) = state.config.trash_path.as_ref() {
+                    session.entries = read_entries(trash_path)?.1;
+                }
+                let (text, kb) = build_trash_view(&session_id, &session.entries);
+                match edit(&bot, &state.config, message.chat.id, message.id, text)
+                    .reply_markup(kb)
+                    .await
+                {
+                    Ok(_) => {}
+                    Err(err) if is_message_not_modified_error(&err) => {}
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        }
+        _ => {}
+    }
+
+    state.trash_sessions.lock().await.insert(session_id, session);
+    bot.answer_callback_query(q.id).await?;
+    Ok(())
+}
+
+async fn handle_duplicates_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let Some(message) = q.message.clone() else {
+        return Ok(());
+    };
+    let Some(data) = q.data.as_deref() else {
+        return Ok(());
+    };
+
+    let mut parts = data.split(':');
+    let _ = parts.next();
+    let session_id = match parts.next() {
+        Some(id) => id.to_string(),
+        None => return Ok(()),
+    };
+    let action = match parts.next() {
+        Some(action) => action,
+        None => return Ok(()),
+    };
+
+    let mut session = {
+        let mut sessions = state.duplicate_sessions.lock().await;
+        let session = match sessions.remove(&session_id) {
+            Some(session) => session,
+            None => {
+                bot.answer_callback_query(q.id).await?;
+                return Ok(());
+            }
+        };
+        if session.chat_id != message.chat.id.0 || session.message_id != message.id {
+            sessions.insert(session_id, session);
+            bot.answer_callback_query(q.id).await?;
+            return Ok(());
+        }
+        session
+    };
+    session.last_activity = now_ts();
+
+    match action {
+        "close" => {
+            let _ = bot.delete_message(message.chat.id, message.id).await;
+            bot.answer_callback_query(q.id).await?;
+            return Ok(());
+        }
+        "page" => {
+            if let Some(page) = parts.next().and_then(|p| p.parse::<usize>().ok()) {
+                session.page = page.min(session.groups.len().saturating_sub(1));
+            }
+        }
+        "del" => {
+            let member = parts.next().and_then(|p| p.parse::<usize>().ok());
+            let entry = member.and_then(|member| {
+                session
+                    .groups
+                    .get(session.page)
+                    .and_then(|members| members.get(member))
+                    .cloned()
+            });
+            if let (Some(member), Some(entry)) = (member, entry) {
+                let op = QueuedOp {
+                    kind: QueuedOpKind::Delete,
+                    entry: entry.block_string(),
+                    resource_path: None,
+                    updated_entry: None,
+                    position: None,
+                };
+                match apply_user_op(&state, &op).await? {
+                    UserOpOutcome::Applied(ApplyOutcome::Applied) => {
+                        add_undo(&state, UndoKind::Delete, op.entry.clone()).await?;
+                        send_ephemeral(
+                            &bot,
+                            &state.config,
+                            message.chat.id,
+                            "Deleted.",
+                            ACK_TTL_SECS,
+                        )
+                        .await?;
+                        session.page =
+                            remove_duplicate_member(&mut session.groups, session.page, member);
+                    }
+                    UserOpOutcome::Applied(ApplyOutcome::NotFound)
+                    | UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+                    | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {
+                        send_error(&bot, &state.config, message.chat.id, "Item not found.").await?;
+                        session.page =
+                            remove_duplicate_member(&mut session.groups, session.page, member);
+                    }
+                    UserOpOutcome::Queued => {
+                        send_error(
+                            &bot,
+                            &state.config,
+                            message.chat.id,
+                            "Write failed; queued for retry.",
+                        )
+                        .await?;
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+
+    let (text, kb) = build_duplicates_view(&session_id, &session.groups, session.page);
+    match edit(&bot, &state.config, message.chat.id, message.id, text)
+        .reply_markup(kb)
+        .await
+    {
+        Ok(_) => {}
+        Err(err) if is_message_not_modified_error(&err) => {}
+        Err(err) => return Err(err.into()),
+    }
+    state
+        .duplicate_sessions
+        .lock()
+        .await
+        .insert(session_id, session);
+    bot.answer_callback_query(q.id).await?;
+    Ok(())
+}
+
+async fn handle_finished_pick_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let Some(message) = q.message.clone() else {
+        return Ok(());
+    };
+    let Some(data) = q.data.as_deref() else {
+        return Ok(());
+    };
+    let mut parts = data.split(':');
+    let _ = parts.next();
+    let pick_id = match parts.next() {
+        Some(id) => id.to_string(),
+        None => return Ok(()),
+    };
+    let action = match parts.next() {
+        Some(action) => action,
+        None => return Ok(()),
+    };
+
+    let mut pick = {
+        let mut picks = state.finished_picks.lock().await;
+        let pick = match picks.remove(&pick_id) {
+            Some(pick) => pick,
+            None => {
+                bot.answer_callback_query(q.id).await?;
+                return Ok(());
+            }
+        };
+        if pick.chat_id != message.chat.id.0 || pick.message_id != message.id {
+            picks.insert(pick_id, pick);
+            bot.answer_callback_query(q.id).await?;
+            return Ok(());
+        }
+        pick
+    };
+
+    match action {
+        "close" => {
+            let _ = bot.delete_message(message.chat.id, message.id).await;
+            bot.answer_callback_query(q.id).await?;
+            return Ok(());
+        }
+        "back" => {
+            let op = QueuedOp {
+                kind: QueuedOpKind::MoveToReadLater,
+                entry: pick.entry.block_string(),
+                resource_path: None,
+                updated_entry: None,
+                position: None,
+            };
+            match apply_user_op(&state, &op).await? {
+                UserOpOutcome::Applied(ApplyOutcome::Applied) => {
+                    let _ = bot.delete_message(message.chat.id, message.id).await;
+                    send_ephemeral(
+                        &bot,
+                        &state.config,
+                        message.chat.id,
+                        "Moved back to Read Later.",
+                        ACK_TTL_SECS,
+                    )
+                    .await?;
+                    bot.answer_callback_query(q.id).await?;
+                    return Ok(());
+                }
+                UserOpOutcome::Applied(_) => {
+                    send_error(&bot, &state.config, message.chat.id, "Item not found.").await?;
+                }
+                UserOpOutcome::Queued => {
+                    send_error(
+                        &bot,
+                        &state.config,
+                        message.chat.id,
+                        "Write failed; queued for retry.",
+                    )
+                    .await?;
+                }
+            }
+        }
+        "another" => {
+            let entries = read_entries(&state.config.finished_path)?.1;
+            if let Some(index) = quick_select_index(entries.len(), QuickSelectMode::Random) {
+                pick.entry = entries[index].clone();
+                let (text, kb) = build_finished_pick_view(&pick_id, &pick.entry, &state.config);
+                match edit(&bot, &state.config, message.chat.id, message.id, text)
+                    .reply_markup(kb)
+                    .await
+                {
+                    Ok(_) => {}
+                    Err(err) if is_message_not_modified_error(&err) => {}
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        }
+        _ => {}
+    }
+
+    state.finished_picks.lock().await.insert(pick_id, pick);
+    bot.answer_callback_query(q.id).await?;
+    Ok(())
+}
+
+async fn handle_queue_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let Some(message) = q.message.clone() else {
+        return Ok(());
+    };
+    let Some(data) = q.data.as_deref() else {
+        return Ok(());
+    };
+    let action = data.trim_start_matches("queue:");
+
+    // Any button ends a pending confirm; "clear" and "clear1" start the next step.
+    let pending = state
+        .queue_clear_confirms
+        .lock()
+        .await
+        .remove(&message.chat.id.0);
+    let confirm_valid =
+        |step| queue_clear_confirm_valid(pending.as_ref(), message.id, step, now_ts());
+    let confirm_step = match action {
+        "close" => {
+            let _ = bot.delete_message(message.chat.id, message.id).await;
+            bot.answer_callback_query(q.id).await?;
+            return Ok(());
+        }
+        "retry" => {
+            process_queue(state.clone()).await?;
+            0
+        }
+        "clear" => 1,
+        "clear1" if confirm_valid(1) => 2,
+        "clear2" if confirm_valid(2) => {
+            replace_queue(&state, Vec::new()).await?;
+            0
+        }
+        "clear1" | "clear2" => {
+            send_error(
+                &bot,
+                &state.config,
+                message.chat.id,
+                "Clear confirmation expired.",
+            )
+            .await?;
+            0
+        }
+        _ => 0,
+    };
+    if confirm_step > 0 {
+        // Like the delete confirm, both steps share the expiry set by "clear".
+        let expires_at = match (confirm_step, &pending) {
+            (2, Some(pending)) => pending.expires_at,
+            _ => now_ts() + QUEUE_CLEAR_CONFIRM_TTL_SECS,
+        };
+        let confirm = QueueClearConfirm {
+            message_id: message.id,
+            step: confirm_step,
+            expires_at,
+        };
+        state
+            .queue_clear_confirms
+            .lock()
+            .await
+            .insert(message.chat.id.0, confirm);
+    }
+
+    let queue = state.queue.lock().await.clone();
+    let (text, kb) = build_queue_view(&queue, confirm_step);
+    match edit(&bot, &state.config, message.chat.id, message.id, text)
+        .reply_markup(kb)
+        .await
+    {
+        Ok(_) => {}
+        Err(err) if is_message_not_modified_error(&err) => {}
+        Err(err) => return Err(err.into()),
+    }
+    bot.answer_callback_query(q.id).await?;
+    Ok(())
+}
+
+async fn handle_snapshot_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let Some(message) = q.message.clone() else {
+        return Ok(());
+    };
+    let Some(data) = q.data.as_deref() else {
+        return Ok(());
+    };
+
+    let mut parts = data.split(':');
+    let _ = parts.next();
+    let prompt_id = match parts.next() {
+        Some(id) => id.to_string(),
+        None => return Ok(()),
+    };
+    let action = parts.next().unwrap_or("");
+
+    let prompt = {
+        let mut prompts = state.snapshot_restores.lock().await;
+        match prompts.remove(&prompt_id) {
+            Some(prompt)
+                if prompt.chat_id == message.chat.id.0 && prompt.message_id == message.id =>
+            {
+                Some(prompt)
+            }
+            Some(prompt) => {
+                prompts.insert(prompt_id, prompt);
+                None
+            }
+            None => None,
+        }
+    };
+    let Some(prompt) = prompt else {
+        bot.answer_callback_query(q.id).await?;
+        return Ok(());
+    };
+
     let _ = bot.delete_message(message.chat.id, message.id).await;
+    if action != "confirm" {
+        bot.answer_callback_query(q.id).await?;
+        return Ok(());
+    }
+    if prompt.expires_at <= now_ts() {
+        send_error(&bot, &state.config, message.chat.id, "Restore expired.").await?;
+        bot.answer_callback_query(q.id).await?;
+        return Ok(());
+    }
+
+    let result = {
+        let _guard = state.write_lock.lock().await;
+        let result = restore_snapshot_sync(
+            &state.config,
+            &state.queue_path,
+            &state.undo_path,
+            &prompt.snapshot,
+        );
+        if result.is_ok() {
+            *state.undo.lock().await = prompt.snapshot.undo.clone();
+            replace_queue(&state, prompt.snapshot.queue.clone()).await
+        } else {
+            result
+        }
+    };
+    match result {
+        Ok(()) => {
+            send_ephemeral(
+                &bot,
+                &state.config,
+                message.chat.id,
+                "Snapshot restored.",
+                ACK_TTL_SECS,
+            )
+            .await?;
+        }
+        Err(err) => {
+            send_error(
+                &bot,
+                &state.config,
+                message.chat.id,
+                &format!("Restore failed: {}", err),
+            )
+            .await?;
+        }
+    }
     bot.answer_callback_query(q.id).await?;
     Ok(())
 }
@@ -1166,18 +2667,7 @@ async fn handle_undo_callback(
     let undo_id = parts.next().unwrap_or("");
     let action = parts.next().unwrap_or("undo");
 
-    let (record, undo_snapshot) = {
-        let mut undo = state.undo.lock().await;
-        prune_undo(&mut undo);
-        let pos = undo.iter().position(|r| r.id == undo_id);
-        let record = if let Some(pos) = pos {
-            Some(undo.remove(pos))
-        } else {
-            None
-        };
-        (record, undo.clone())
-    };
-    save_undo(&state.undo_path, &undo_snapshot)?;
+    let record = take_undo_record(&state, undo_id).await?;
 
     if action == "delete" {
         if let Some(message) = q.message.clone() {
@@ -1190,41 +2680,41 @@ async fn handle_undo_callback(
     if let Some(record) = record {
         let chat_id = chat_id_from_user_id(q.from.id.0);
         if record.expires_at < now_ts() {
-            send_error(&bot, chat_id, "Undo expired.").await?;
+            send_error(&bot, &state.config, chat_id, "Undo expired.").await?;
             bot.answer_callback_query(q.id).await?;
             return Ok(());
         }
 
-        let op = match record.kind {
-            UndoKind::MoveToFinished => QueuedOp {
-                kind: QueuedOpKind::MoveToReadLater,
-                entry: record.entry,
-                resource_path: None,
-                updated_entry: None,
-            },
-            UndoKind::Delete => QueuedOp {
-                kind: QueuedOpKind::Add,
-                entry: record.entry,
-                resource_path: None,
-                updated_entry: None,
-            },
-        };
+        let op = undo_op_for_record(&record, &state.config);
 
         match apply_user_op(&state, &op).await? {
             UserOpOutcome::Applied(ApplyOutcome::Applied)
             | UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+            | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished)
             | UserOpOutcome::Applied(ApplyOutcome::NotFound) => {
-                send_ephemeral(&bot, chat_id, "Undone.", ACK_TTL_SECS).await?;
+                send_ephemeral(&bot, &state.config, chat_id, "Undone.", ACK_TTL_SECS).await?;
             }
             UserOpOutcome::Queued => {
-                send_error(&bot, chat_id, "Write failed; queued for retry.").await?;
+                send_error(
+                    &bot,
+                    &state.config,
+                    chat_id,
+                    "Write failed; queued for retry.",
+                )
+                .await?;
             }
         }
         if let Some(message) = q.message.clone() {
             let _ = bot.delete_message(message.chat.id, message.id).await;
         }
     } else {
-        send_error(&bot, chat_id_from_user_id(q.from.id.0), "Undo not found.").await?;
+        send_error(
+            &bot,
+            &state.config,
+            chat_id_from_user_id(q.from.id.0),
+            "Undo not found.",
+        )
+        .await?;
     }
 
     bot.answer_callback_query(q.id).await?;
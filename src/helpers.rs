@@ -1,4 +1,6 @@
 use super::*;
+use teloxide::payloads::{EditMessageText, SendMessage};
+use teloxide::requests::JsonRequest;
 
 pub(super) fn search_entries(entries: &[EntryBlock], query: &str) -> Vec<EntryBlock> {
     entries
@@ -8,21 +10,96 @@ pub(super) fn search_entries(entries: &[EntryBlock], query: &str) -> Vec<EntryBl
         .collect()
 }
 
+/// Entries whose display text matches `pattern`, used by `/search re:...`.
+pub(super) fn regex_search_entries(
+    entries: &[EntryBlock],
+    pattern: &str,
+) -> std::result::Result<Vec<EntryBlock>, regex::Error> {
+    let re = regex::Regex::new(pattern)?;
+    Ok(entries
+        .iter()
+        .filter(|entry| re.is_match(&entry.display_lines().join("\n")))
+        .cloned()
+        .collect())
+}
+
+/// Every term in a group must match; a bare `OR` starts a new group, and any
+/// matching group is enough. Quoted text is kept together as one phrase.
 pub(super) fn matches_query(entry: &EntryBlock, query: &str) -> bool {
-    let needle = query.trim().to_lowercase();
-    if needle.is_empty() {
+    let groups = parse_query_groups(query);
+    if groups.is_empty() {
         return false;
     }
     let haystack = entry.display_lines().join("\n").to_lowercase();
-    needle
-        .split_whitespace()
-        .all(|term| haystack.contains(term))
+    let tags = extract_tags(entry);
+    groups.iter().any(|group| {
+        group.iter().all(|term| match term.strip_prefix('#') {
+            // `#rust` matches the whole tag only, so it skips `#rustlang`.
+            Some(tag) if !tag.is_empty() => tags.iter().any(|t| t == tag),
+            _ => haystack.contains(term.as_str()),
+        })
+    })
+}
+
+/// Splits a search query into lowercase AND-groups separated by `OR`.
+pub(super) fn parse_query_groups(query: &str) -> Vec<Vec<String>> {
+    let mut groups = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut rest = query.trim_start();
+    while !rest.is_empty() {
+        let (term, quoted, after) = if let Some(quoted) = rest.strip_prefix('"') {
+            match quoted.split_once('"') {
+                Some((phrase, after)) => (phrase, true, after),
+                None => (quoted, true, ""),
+            }
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            (&rest[..end], false, &rest[end..])
+        };
+        if !quoted && term == "OR" {
+            if !current.is_empty() {
+                groups.push(std::mem::take(&mut current));
+            }
+        } else if !term.trim().is_empty() {
+            current.push(term.to_lowercase());
+        }
+        rest = after.trim_start();
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+    groups
+}
+
+/// Line count ignoring trailing blank lines, which separate entries in the file.
+pub(super) fn entry_line_count(entry: &EntryBlock) -> usize {
+    entry.lines.len()
+        - entry
+            .lines
+            .iter()
+            .rev()
+            .take_while(|line| line.trim().is_empty())
+            .count()
+}
+
+pub(super) fn is_short_entry(entry: &EntryBlock, max_lines: usize) -> bool {
+    entry_line_count(entry) <= max_lines
+}
+
+pub(super) fn is_long_entry(entry: &EntryBlock, min_lines: usize) -> bool {
+    entry_line_count(entry) >= min_lines
 }
 
 #[cfg(test)]
-pub(super) fn displayed_indices_for_view(session: &ListSession, peeked: &HashSet<String>) -> Vec<usize> {
+pub(super) fn displayed_indices_for_view(
+    session: &ListSession,
+    peeked: &HashSet<String>,
+    page_size: usize,
+) -> Vec<usize> {
     match session.view {
-        ListView::Peek { mode, page } => peek_indices_for_session(session, peeked, mode, page),
+        ListView::Peek { mode, page } => {
+            peek_indices_for_session(session, peeked, mode, page, page_size)
+        }
         ListView::Selected { index, .. } => vec![index],
         ListView::FinishConfirm { index, .. } => vec![index],
         ListView::DeleteConfirm { index, .. } => vec![index],
@@ -30,13 +107,19 @@ pub(super) fn displayed_indices_for_view(session: &ListSession, peeked: &HashSet
     }
 }
 
-pub(super) fn embedded_lines_for_view(session: &ListSession, peeked: &HashSet<String>) -> Vec<String> {
+pub(super) fn embedded_lines_for_view(
+    session: &ListSession,
+    peeked: &HashSet<String>,
+    page_size: usize,
+) -> Vec<String> {
     match session.view {
-        ListView::Peek { mode, page } => peek_indices_for_session(session, peeked, mode, page)
-            .into_iter()
-            .filter_map(|index| session.entries.get(index))
-            .flat_map(|entry| entry.preview_lines())
-            .collect(),
+        ListView::Peek { mode, page } => {
+            peek_indices_for_session(session, peeked, mode, page, page_size)
+                .into_iter()
+                .filter_map(|index| session.entries.get(index))
+                .flat_map(|entry| entry.preview_lines())
+                .collect()
+        }
         ListView::Selected { index, .. } => session
             .entries
             .get(index)
@@ -51,12 +134,47 @@ pub(super) fn embedded_lines_for_view(session: &ListSession, peeked: &HashSet<St
     }
 }
 
-pub(super) fn norm_target_index(session: &ListSession, peeked: &HashSet<String>) -> Option<usize> {
+/// Text of the item the view is focused on. A peek page only counts when it
+/// shows a single item, unless `whole_page` is set, in which case every item
+/// on the page is included.
+pub(super) fn view_entry_text(
+    session: &ListSession,
+    peeked: &HashSet<String>,
+    whole_page: bool,
+    page_size: usize,
+) -> Option<String> {
+    match &session.view {
+        ListView::Selected { index, .. } => session
+            .entries
+            .get(*index)
+            .map(|entry| entry.display_lines().join("\n")),
+        ListView::Peek { mode, page } => {
+            let indices = peek_indices_for_session(session, peeked, *mode, *page, page_size);
+            if indices.len() == 1 || (whole_page && !indices.is_empty()) {
+                let texts: Vec<String> = indices
+                    .iter()
+                    .filter_map(|index| session.entries.get(*index))
+                    .map(|entry| entry.display_lines().join("\n"))
+                    .collect();
+                Some(texts.join("\n"))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+pub(super) fn norm_target_index(
+    session: &ListSession,
+    peeked: &HashSet<String>,
+    page_size: usize,
+) -> Option<usize> {
     match &session.view {
         ListView::Selected { index, .. } => Some(*index),
         ListView::FinishConfirm { index, .. } => Some(*index),
         ListView::Peek { mode, page } => {
-            let indices = peek_indices_for_session(session, peeked, *mode, *page);
+            let indices = peek_indices_for_session(session, peeked, *mode, *page, page_size);
             if indices.len() == 1 {
                 indices.first().copied()
             } else {
@@ -84,6 +202,23 @@ pub(super) fn normalize_entry_markdown_links(entry: &EntryBlock) -> Option<Entry
     }
 }
 
+/// Normalizes links in every entry of `path` with one read and one write.
+/// Returns how many entries changed.
+pub(super) fn normalize_all_entries_sync(path: &Path) -> Result<usize> {
+    let (preamble, mut entries) = read_entries(path)?;
+    let mut changed = 0;
+    for entry in entries.iter_mut() {
+        if let Some(normalized) = normalize_entry_markdown_links(entry) {
+            *entry = normalized;
+            changed += 1;
+        }
+    }
+    if changed > 0 {
+        write_entries(path, &preamble, &entries)?;
+    }
+    Ok(changed)
+}
+
 pub(super) fn normalize_markdown_links(text: &str) -> (String, bool) {
     if !text.contains('[') {
         return (text.to_string(), false);
@@ -125,7 +260,32 @@ pub(super) fn normalize_markdown_links(text: &str) -> (String, bool) {
     (out, changed)
 }
 
-pub(super) fn extract_links(text: &str) -> Vec<String> {
+/// URL buttons for the first few links in `entry`; Telegram opens them itself.
+pub(super) fn build_open_link_row(entry: &EntryBlock, min_len: usize) -> Vec<InlineKeyboardButton> {
+    let links: Vec<reqwest::Url> = extract_links(&entry.block_string(), min_len)
+        .into_iter()
+        .filter(|link| is_http_link(link))
+        .filter_map(|link| reqwest::Url::parse(&link).ok())
+        .take(OPEN_LINK_BUTTONS)
+        .collect();
+    let numbered = links.len() > 1;
+    links
+        .into_iter()
+        .enumerate()
+        .map(|(idx, url)| {
+            let label = if numbered {
+                format!("Open {}", idx + 1)
+            } else {
+                "Open link".to_string()
+            };
+            InlineKeyboardButton::url(label, url)
+        })
+        .collect()
+}
+
+/// Finds http(s) links in markdown link syntax and bare text, skipping any
+/// shorter than `min_len` characters.
+pub(super) fn extract_links(text: &str, min_len: usize) -> Vec<String> {
     let mut links = Vec::new();
     let mut seen: HashSet<String> = HashSet::new();
 
@@ -149,7 +309,7 @@ pub(super) fn extract_links(text: &str) -> Vec<String> {
         let url_end = url_start + url_end_rel;
         let url = text[url_start..url_end].trim();
         if is_http_link(url) {
-            push_link(&mut links, &mut seen, url.to_string());
+            push_link(&mut links, &mut seen, url.to_string(), min_len);
         }
         index = url_end + 1;
     }
@@ -175,7 +335,7 @@ pub(super) fn extract_links(text: &str) -> Vec<String> {
         let mut url = text[start..end].to_string();
         url = trim_link(&url);
         if is_http_link(&url) {
-            push_link(&mut links, &mut seen, url);
+            push_link(&mut links, &mut seen, url, min_len);
         }
         scan = end;
     }
@@ -187,7 +347,15 @@ pub(super) fn is_http_link(link: &str) -> bool {
     link.starts_with("http://") || link.starts_with("https://")
 }
 
-pub(super) fn push_link(links: &mut Vec<String>, seen: &mut HashSet<String>, link: String) {
+pub(super) fn push_link(
+    links: &mut Vec<String>,
+    seen: &mut HashSet<String>,
+    link: String,
+    min_len: usize,
+) {
+    if link.chars().count() < min_len {
+        return;
+    }
     if seen.insert(link.clone()) {
         links.push(link);
     }
@@ -199,6 +367,130 @@ pub(super) fn trim_link(link: &str) -> String {
         .to_string()
 }
 
+/// True when the first line is only a URL, optionally after an added date.
+pub(super) fn is_bare_link_entry(entry: &EntryBlock) -> bool {
+    let Some(first) = entry.display_lines().into_iter().next() else {
+        return false;
+    };
+    let first = if entry_added_date(entry).is_some() {
+        &first[10..]
+    } else {
+        first.as_str()
+    };
+    let first = first.trim();
+    is_http_link(first) && !first.contains(char::is_whitespace)
+}
+
+/// True when any line embeds media with `![[...]]`, whether or not the file
+/// still exists in `media_dir`.
+pub(super) fn has_embedded_media(entry: &EntryBlock) -> bool {
+    entry.lines.iter().any(|line| {
+        line.split("![[").skip(1).any(|rest| {
+            rest.find("]]")
+                .is_some_and(|end| !rest[..end].trim().is_empty())
+        })
+    })
+}
+
+/// Link to title for the selected item, if it is a bare link.
+pub(super) fn selected_title_link(session: &ListSession) -> Option<String> {
+    let ListView::Selected { index, .. } = session.view else {
+        return None;
+    };
+    let entry = session.entries.get(index)?;
+    if !is_bare_link_entry(entry) {
+        return None;
+    }
+    extract_links(&entry.block_string(), 0).into_iter().next()
+}
+
+/// Rewrites the `[category]` prefix on the first line, after any added date,
+/// replacing an existing one. An empty category removes the prefix.
+pub(super) fn set_entry_category(entry: &str, category: &str) -> String {
+    let mut entry = EntryBlock::from_block(entry);
+    let Some(first) = entry.lines.first() else {
+        return entry.block_string();
+    };
+    let body = first.strip_prefix("- ").unwrap_or(first);
+    let (date, rest) = match NaiveDate::parse_from_str(body.get(..10).unwrap_or(""), "%Y-%m-%d") {
+        Ok(_) if body.len() > 10 && body[10..].starts_with(' ') => {
+            (Some(&body[..10]), body[11..].trim_start())
+        }
+        _ => (None, body),
+    };
+    let rest = match rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+        Some((_, after)) if !after.starts_with('(') => after.trim_start(),
+        _ => rest,
+    };
+    let category = category.trim();
+    let mut line = String::from("- ");
+    if let Some(date) = date {
+        line.push_str(date);
+        line.push(' ');
+    }
+    if !category.is_empty() {
+        line.push_str(&format!("[{}] ", category));
+    }
+    line.push_str(rest);
+    entry.lines[0] = line.trim_end().to_string();
+    entry.block_string()
+}
+
+/// Due date from a `(due: YYYY-MM-DD)` marker on the first line.
+pub(super) fn due_of(entry: &EntryBlock) -> Option<NaiveDate> {
+    let first = entry.lines.first()?;
+    let start = first.find("(due: ")? + "(due: ".len();
+    let date = first.get(start..start + 10)?;
+    if !first[start + 10..].starts_with(')') {
+        return None;
+    }
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// Replaces the due marker on the first line, or removes it when `due` is
+/// `None`.
+pub(super) fn set_entry_due(entry: &str, due: Option<NaiveDate>) -> String {
+    let mut block = EntryBlock::from_block(entry);
+    let existing = due_of(&block);
+    let Some(first) = block.lines.first_mut() else {
+        return entry.to_string();
+    };
+    if let Some(existing) = existing {
+        let marker = format!("(due: {})", existing.format("%Y-%m-%d"));
+        let spaced = format!(" {}", marker);
+        let marker = if first.contains(&spaced) {
+            spaced
+        } else {
+            marker
+        };
+        *first = first.replacen(&marker, "", 1).trim_end().to_string();
+    }
+    if let Some(due) = due {
+        first.push_str(&format!(" (due: {})", due.format("%Y-%m-%d")));
+    }
+    block.block_string()
+}
+
+/// Parses a reply to the Due prompt: a date, or `-` to clear it.
+pub(super) fn parse_due_input(text: &str) -> Option<Option<NaiveDate>> {
+    let input = text.lines().next().unwrap_or("").trim();
+    if input == "-" {
+        return Some(None);
+    }
+    NaiveDate::parse_from_str(input, "%Y-%m-%d").ok().map(Some)
+}
+
+/// Entries due on or before `today`, earliest due date first.
+pub(super) fn due_entries(entries: Vec<EntryBlock>, today: NaiveDate) -> Vec<EntryBlock> {
+    let mut due: Vec<(NaiveDate, EntryBlock)> = entries
+        .into_iter()
+        .filter_map(|entry| due_of(&entry).map(|date| (date, entry)))
+        .filter(|(date, _)| *date <= today)
+        .collect();
+    due.sort_by_key(|(date, _)| *date);
+    due.into_iter().map(|(_, entry)| entry).collect()
+}
+
 pub(super) fn entry_with_title(entry: &str, title: &str, link: &str) -> String {
     let mut entry = EntryBlock::from_block(entry);
     let line = format!("- [{}]({})", title.trim(), link);
@@ -210,6 +502,25 @@ pub(super) fn entry_with_title(entry: &str, title: &str, link: &str) -> String {
     entry.block_string()
 }
 
+pub(super) fn entry_with_note(entry: &str, note: &str) -> String {
+    let note = note
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut entry = EntryBlock::from_block(entry);
+    while entry
+        .lines
+        .last()
+        .is_some_and(|line| line.trim().is_empty())
+    {
+        entry.lines.pop();
+    }
+    entry.lines.push(format!("  - Note: {}", note));
+    entry.block_string()
+}
+
 pub(super) fn build_picker_text(items: &[String], selected: &[bool]) -> String {
     let mut text = String::from("Select items to save:\n\n");
     for (idx, item) in items.iter().enumerate() {
@@ -220,7 +531,7 @@ pub(super) fn build_picker_text(items: &[String], selected: &[bool]) -> String {
         };
         let preview = preview_text(item);
         text.push_str(&format!("{} {}\n", idx + 1, marker));
-        if let Some(first) = preview.get(0) {
+        if let Some(first) = preview.first() {
             text.push_str(&format!("{}\n", first));
         }
         if let Some(second) = preview.get(1) {
@@ -262,17 +573,51 @@ pub(super) fn build_add_prompt_keyboard(prompt_id: &str) -> InlineKeyboardMarkup
     ])
 }
 
+pub(super) fn build_title_prompt_keyboard(prompt_id: &str) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback("Use title", format!("title:{}:use", prompt_id)),
+        InlineKeyboardButton::callback("Keep raw", format!("title:{}:raw", prompt_id)),
+    ]])
+}
+
+/// Returns the link when `text` is nothing but one bare http(s) URL.
+pub(super) fn single_bare_link(text: &str) -> Option<&str> {
+    let text = text.trim();
+    let is_link = text.starts_with("http://") || text.starts_with("https://");
+    (is_link && !text.contains(char::is_whitespace)).then_some(text)
+}
+
+/// Whether a `Content-Type` header value is an HTML page.
+pub(super) fn is_html_content_type(value: &str) -> bool {
+    value
+        .split(';')
+        .next()
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("text/html"))
+}
+
+/// Pulls the text of the first `<title>` element out of an HTML page.
+pub(super) fn extract_html_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = html[start..end]
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace(['[', ']'], "");
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
 pub(super) fn build_resource_picker_keyboard(picker_id: &str, files: &[PathBuf]) -> InlineKeyboardMarkup {
     let mut rows: Vec<Vec<InlineKeyboardButton>> = Vec::new();
     let mut current_row = Vec::new();
     for (idx, path) in files.iter().enumerate() {
-        let label = path
-            .file_name()
-            .and_then(|name| name.to_str())
-            .map(|name| name.to_string())
-            .unwrap_or_else(|| path.to_string_lossy().to_string());
         current_row.push(InlineKeyboardButton::callback(
-            label,
+            resource_file_label(path),
             format!("res:{}:file:{}", picker_id, idx),
         ));
         if current_row.len() == 2 {
@@ -293,6 +638,83 @@ pub(super) fn build_resource_picker_keyboard(picker_id: &str, files: &[PathBuf])
     InlineKeyboardMarkup::new(rows)
 }
 
+/// Resource file that `/add` writes to without asking, relative names being
+/// resolved inside `resources_path`.
+pub(super) fn default_resource_path(config: &Config) -> Option<PathBuf> {
+    let file = config.default_resource_file.as_ref()?;
+    if file.is_absolute() {
+        Some(file.clone())
+    } else {
+        Some(config.resources_path.join(file))
+    }
+}
+
+/// File the resource picker should add to without asking, which is only the
+/// case when no resource files exist yet and auto-creation is enabled.
+pub(super) fn auto_create_resource_path(config: &Config, files: &[PathBuf]) -> Option<PathBuf> {
+    if !config.auto_create_default_resource || !files.is_empty() {
+        return None;
+    }
+    default_resource_path(config)
+}
+
+pub(super) fn build_resource_browser_keyboard(browser_id: &str, files: &[PathBuf]) -> InlineKeyboardMarkup {
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = files
+        .chunks(2)
+        .enumerate()
+        .map(|(row, chunk)| {
+            chunk
+                .iter()
+                .enumerate()
+                .map(|(col, path)| {
+                    InlineKeyboardButton::callback(
+                        resource_file_label(path),
+                        format!("rb:{}:file:{}", browser_id, row * 2 + col),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    rows.push(vec![InlineKeyboardButton::callback(
+        "Cancel",
+        format!("rb:{}:cancel", browser_id),
+    )]);
+    InlineKeyboardMarkup::new(rows)
+}
+
+pub(super) fn resource_file_label(path: &Path) -> String {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+/// Joins entries into one markdown document. Sections get a `##` heading
+/// when there is more than one.
+pub(super) fn build_export_markdown(title: &str, sections: &[(String, Vec<EntryBlock>)]) -> String {
+    let mut text = format!("# {}\n", title);
+    for (name, entries) in sections {
+        if entries.is_empty() {
+            continue;
+        }
+        if sections.len() > 1 {
+            text.push_str(&format!("\n## {}\n", name));
+        }
+        for entry in entries {
+            text.push_str(&entry.block_string());
+            text.push('\n');
+        }
+    }
+    text
+}
+
+pub(super) fn session_target_path(session: &ListSession) -> Option<PathBuf> {
+    match &session.kind {
+        SessionKind::Resource { path } => Some(path.clone()),
+        SessionKind::List | SessionKind::Search { .. } => None,
+    }
+}
+
 pub(super) fn build_download_picker_text(links: &[String]) -> String {
     if links.is_empty() {
         return "No links found. Add one?".to_string();
@@ -312,6 +734,7 @@ pub(super) fn build_download_quality_text(
     let action_label = match action {
         DownloadAction::Send => "send",
         DownloadAction::Save => "save",
+        DownloadAction::SendAndSave => "send and save",
     };
     let mut text = format!("Choose quality to {}:\n{}\n\n", action_label, link);
     for (idx, option) in options.iter().enumerate() {
@@ -320,19 +743,51 @@ pub(super) fn build_download_quality_text(
     text.trim_end().to_string()
 }
 
-pub(super) fn build_download_picker_keyboard(picker_id: &str, links: &[String]) -> InlineKeyboardMarkup {
+pub(super) fn download_action_shortcut(setting: DefaultDownloadAction) -> Option<DownloadAction> {
+    match setting {
+        DefaultDownloadAction::Ask => None,
+        DefaultDownloadAction::Send => Some(DownloadAction::Send),
+        DefaultDownloadAction::Save => Some(DownloadAction::Save),
+    }
+}
+
+pub(super) fn build_download_picker_keyboard(
+    picker_id: &str,
+    links: &[String],
+    default_action: DefaultDownloadAction,
+) -> InlineKeyboardMarkup {
     let mut rows = Vec::new();
-    for (idx, _) in links.iter().enumerate() {
-        rows.push(vec![
-            InlineKeyboardButton::callback(
-                format!("Send {}", idx + 1),
-                format!("dl:{}:send:{}", picker_id, idx),
-            ),
-            InlineKeyboardButton::callback(
-                format!("Save {}", idx + 1),
-                format!("dl:{}:save:{}", picker_id, idx),
-            ),
-        ]);
+    if download_action_shortcut(default_action).is_some() {
+        for chunk in (0..links.len()).collect::<Vec<_>>().chunks(5) {
+            rows.push(
+                chunk
+                    .iter()
+                    .map(|idx| {
+                        InlineKeyboardButton::callback(
+                            format!("{}", idx + 1),
+                            format!("dl:{}:link:{}", picker_id, idx),
+                        )
+                    })
+                    .collect(),
+            );
+        }
+    } else {
+        for (idx, _) in links.iter().enumerate() {
+            rows.push(vec![
+                InlineKeyboardButton::callback(
+                    format!("Send {}", idx + 1),
+                    format!("dl:{}:send:{}", picker_id, idx),
+                ),
+                InlineKeyboardButton::callback(
+                    format!("Save {}", idx + 1),
+                    format!("dl:{}:save:{}", picker_id, idx),
+                ),
+                InlineKeyboardButton::callback(
+                    format!("Send + Save {}", idx + 1),
+                    format!("dl:{}:sendsave:{}", picker_id, idx),
+                ),
+            ]);
+        }
     }
     rows.push(vec![InlineKeyboardButton::callback(
         "Add link",
@@ -373,13 +828,13 @@ pub(super) fn render_list_view(
     peeked: &HashSet<String>,
     config: &Config,
 ) -> (String, InlineKeyboardMarkup) {
-    match &session.view {
+    let (text, mut kb) = match &session.view {
         ListView::Menu => build_menu_view(session_id, session),
         ListView::Peek { mode, page } => {
             build_peek_view(session_id, session, *mode, *page, peeked, config)
         }
         ListView::Selected { index, .. } => {
-            build_selected_view(session_id, session, *index, config)
+            build_selected_view(session_id, session, *index, peeked, config)
         }
         ListView::FinishConfirm { index, .. } => {
             build_finish_confirm_view(session_id, session, *index, config)
@@ -387,7 +842,39 @@ pub(super) fn render_list_view(
         ListView::DeleteConfirm { step, index, .. } => {
             build_delete_confirm_view(session_id, session, *index, *step, config)
         }
+        ListView::FinishAllConfirm { step, .. } => {
+            build_finish_all_confirm_view(session_id, session, *step)
+        }
+        ListView::FinishLinkPick { links, .. } => build_finish_link_view(session_id, links),
+        ListView::FinishResourcePick { files, .. } => build_finish_resource_view(session_id, files),
+    };
+
+    let toggles_media = matches!(
+        session.view,
+        ListView::Peek { .. } | ListView::Selected { .. }
+    ) && embedded_lines_for_view(session, peeked, config.page_size)
+        .iter()
+        .any(|line| line.contains("![["));
+    if toggles_media {
+        let label = if session.hide_media {
+            "Show media"
+        } else {
+            "Hide media"
+        };
+        kb = kb.append_row(vec![InlineKeyboardButton::callback(
+            label,
+            format!("ls:{}:media", session_id),
+        )]);
     }
+    if !session.session_undo.is_empty()
+        && matches!(session.view, ListView::Menu | ListView::Peek { .. })
+    {
+        kb = kb.append_row(vec![InlineKeyboardButton::callback(
+            "Undo last action",
+            format!("ls:{}:undo_last", session_id),
+        )]);
+    }
+    (text, kb)
 }
 
 pub(super) fn build_menu_view(session_id: &str, session: &ListSession) -> (String, InlineKeyboardMarkup) {
@@ -412,10 +899,10 @@ pub(super) fn build_menu_view(session_id: &str, session: &ListSession) -> (Strin
                         format!("ls:{}:bottom:0", session_id),
                     ),
                 ]);
-                rows.push(vec![InlineKeyboardButton::callback(
-                    "Random",
-                    format!("ls:{}:random", session_id),
-                )]);
+                rows.push(vec![
+                    InlineKeyboardButton::callback("Random", format!("ls:{}:random", session_id)),
+                    InlineKeyboardButton::callback("Export", format!("ls:{}:export", session_id)),
+                ]);
             }
 
             (text, InlineKeyboardMarkup::new(rows))
@@ -427,6 +914,35 @@ pub(super) fn build_menu_view(session_id: &str, session: &ListSession) -> (Strin
                 format!("Matches for \"{}\" ({}).", query, count)
             };
 
+            let mut rows = Vec::new();
+            if count > 0 {
+                rows.push(vec![InlineKeyboardButton::callback(
+                    "Show",
+                    format!("ls:{}:top:0", session_id),
+                )]);
+                rows.push(vec![
+                    InlineKeyboardButton::callback(
+                        "Finish all",
+                        format!("ls:{}:finish_all", session_id),
+                    ),
+                    InlineKeyboardButton::callback("Export", format!("ls:{}:export", session_id)),
+                ]);
+            }
+            rows.push(vec![InlineKeyboardButton::callback(
+                "Close",
+                format!("ls:{}:close", session_id),
+            )]);
+
+            (text, InlineKeyboardMarkup::new(rows))
+        }
+        SessionKind::Resource { path } => {
+            let name = resource_file_label(path);
+            let text = if count == 0 {
+                format!("{} is empty.", name)
+            } else {
+                format!("{} ({}).", name, count)
+            };
+
             let mut rows = Vec::new();
             if count > 0 {
                 rows.push(vec![InlineKeyboardButton::callback(
@@ -453,11 +969,11 @@ pub(super) fn build_peek_view(
     config: &Config,
 ) -> (String, InlineKeyboardMarkup) {
     let total_unpeeked = count_visible_entries(session, peeked);
-    let indices = peek_indices_for_session(session, peeked, mode, page);
+    let indices = peek_indices_for_session(session, peeked, mode, page, config.page_size);
     let total_pages = if total_unpeeked == 0 {
         0
     } else {
-        (total_unpeeked + PAGE_SIZE - 1) / PAGE_SIZE
+        total_unpeeked.div_ceil(config.page_size)
     };
     let mut text = match &session.kind {
         SessionKind::List => {
@@ -480,6 +996,14 @@ pub(super) fn build_peek_view(
                 format!("Matches for \"{}\"\n", query)
             }
         }
+        SessionKind::Resource { path } => {
+            let name = resource_file_label(path);
+            if total_pages > 0 {
+                format!("{} (page {}/{})\n", name, page + 1, total_pages)
+            } else {
+                format!("{}\n", name)
+            }
+        }
     };
     if total_unpeeked == 0 {
         text.push_str("Everything's been peeked already.");
@@ -488,9 +1012,9 @@ pub(super) fn build_peek_view(
     } else {
         for (display_index, entry_index) in indices.iter().enumerate() {
             if let Some(entry) = session.entries.get(*entry_index) {
-                let preview = format_embedded_references_for_lines(&entry.preview_lines(), config);
+                let preview = format_display_lines(&entry.preview_lines(), config);
                 text.push_str(&format!("{}) ", display_index + 1));
-                if let Some(first) = preview.get(0) {
+                if let Some(first) = preview.first() {
                     text.push_str(first);
                 }
                 text.push('\n');
@@ -504,15 +1028,40 @@ pub(super) fn build_peek_view(
     }
 
     let mut rows = Vec::new();
-    if !indices.is_empty() {
-        let mut pick_row = Vec::new();
-        for i in 0..indices.len() {
-            pick_row.push(InlineKeyboardButton::callback(
-                format!("{}", i + 1),
-                format!("ls:{}:pick:{}", session_id, i + 1),
-            ));
-        }
-        rows.push(pick_row);
+    for chunk in (1..=indices.len())
+        .collect::<Vec<_>>()
+        .chunks(PICK_ROW_WIDTH)
+    {
+        rows.push(
+            chunk
+                .iter()
+                .map(|i| {
+                    InlineKeyboardButton::callback(
+                        format!("{}", i),
+                        format!("ls:{}:pick:{}", session_id, i),
+                    )
+                })
+                .collect(),
+        );
+    }
+
+    let media_row: Vec<InlineKeyboardButton> = indices
+        .iter()
+        .enumerate()
+        .filter(|(_, index)| {
+            session.entries.get(**index).is_some_and(|entry| {
+                !extract_embedded_paths(&entry.display_lines(), config).is_empty()
+            })
+        })
+        .map(|(position, _)| {
+            InlineKeyboardButton::callback(
+                format!("Media {}", position + 1),
+                format!("ls:{}:peekmedia:{}", session_id, position + 1),
+            )
+        })
+        .collect();
+    if !media_row.is_empty() {
+        rows.push(media_row);
     }
 
     rows.push(vec![
@@ -521,12 +1070,24 @@ pub(super) fn build_peek_view(
     ]);
     match &session.kind {
         SessionKind::List => {
+            if !indices.is_empty() {
+                rows.push(vec![
+                    InlineKeyboardButton::callback(
+                        "Peek all shown",
+                        format!("ls:{}:peekall", session_id),
+                    ),
+                    InlineKeyboardButton::callback(
+                        "Finish page",
+                        format!("ls:{}:finishpage", session_id),
+                    ),
+                ]);
+            }
             rows.push(vec![
                 InlineKeyboardButton::callback("Back", format!("ls:{}:back", session_id)),
                 InlineKeyboardButton::callback("Random", format!("ls:{}:random", session_id)),
             ]);
         }
-        SessionKind::Search { .. } => {
+        SessionKind::Search { .. } | SessionKind::Resource { .. } => {
             rows.push(vec![InlineKeyboardButton::callback(
                 "Close",
                 format!("ls:{}:close", session_id),
@@ -541,17 +1102,26 @@ pub(super) fn build_selected_view(
     session_id: &str,
     session: &ListSession,
     index: usize,
+    peeked: &HashSet<String>,
     config: &Config,
 ) -> (String, InlineKeyboardMarkup) {
     let entry = session.entries.get(index);
+    let peek_label = match entry {
+        Some(entry) if !peeked.contains(&entry.block_string()) => "Mark peeked",
+        _ => "Unpeek",
+    };
+    let (pin_label, pin_action) = match entry {
+        Some(entry) if is_pinned(entry) => ("Unpin", "unpin"),
+        _ => ("Pin", "pin"),
+    };
     let text = if let Some(entry) = entry {
-        let lines = format_embedded_references_for_lines(&entry.display_lines(), config);
+        let lines = format_display_lines(&entry.display_lines(), config);
         format!("Selected item:\n\n{}", lines.join("\n"))
     } else {
         "Selected item not found.".to_string()
     };
 
-    let rows = match &session.kind {
+    let mut rows = match &session.kind {
         SessionKind::List => vec![
             vec![
                 InlineKeyboardButton::callback(
@@ -562,10 +1132,16 @@ pub(super) fn build_selected_view(
                     "Add Resource",
                     format!("ls:{}:resource", session_id),
                 ),
+                InlineKeyboardButton::callback("Edit", format!("ls:{}:edit", session_id)),
             ],
             vec![
                 InlineKeyboardButton::callback("Delete", format!("ls:{}:delete", session_id)),
                 InlineKeyboardButton::callback("Random", format!("ls:{}:random", session_id)),
+                InlineKeyboardButton::callback(peek_label, format!("ls:{}:togglepeek", session_id)),
+                InlineKeyboardButton::callback(
+                    pin_label,
+                    format!("ls:{}:{}", session_id, pin_action),
+                ),
             ],
             vec![InlineKeyboardButton::callback(
                 "Back",
@@ -573,10 +1149,13 @@ pub(super) fn build_selected_view(
             )],
         ],
         SessionKind::Search { .. } => vec![
-            vec![InlineKeyboardButton::callback(
-                "Add Resource",
-                format!("ls:{}:resource", session_id),
-            )],
+            vec![
+                InlineKeyboardButton::callback(
+                    "Add Resource",
+                    format!("ls:{}:resource", session_id),
+                ),
+                InlineKeyboardButton::callback("Edit", format!("ls:{}:edit", session_id)),
+            ],
             vec![InlineKeyboardButton::callback(
                 "Delete",
                 format!("ls:{}:delete", session_id),
@@ -586,21 +1165,97 @@ pub(super) fn build_selected_view(
                 format!("ls:{}:back", session_id),
             )],
         ],
-    };
-
-    (text, InlineKeyboardMarkup::new(rows))
-}
-
+        SessionKind::Resource { .. } => vec![
+            vec![InlineKeyboardButton::callback(
+                "Edit",
+                format!("ls:{}:edit", session_id),
+            )],
+            vec![InlineKeyboardButton::callback(
+                "Back",
+                format!("ls:{}:back", session_id),
+            )],
+        ],
+    };
+    if let Some(entry) = entry {
+        let is_resource = matches!(session.kind, SessionKind::Resource { .. });
+        let mut extra_row = Vec::new();
+        if !is_resource {
+            extra_row.push(InlineKeyboardButton::callback(
+                "Recategorize",
+                format!("ls:{}:category", session_id),
+            ));
+            extra_row.push(InlineKeyboardButton::callback(
+                "Due",
+                format!("ls:{}:due", session_id),
+            ));
+            extra_row.push(InlineKeyboardButton::callback(
+                "Move to",
+                format!("ls:{}:moveto", session_id),
+            ));
+            if !is_archived(entry) {
+                extra_row.push(InlineKeyboardButton::callback(
+                    "Mark archived",
+                    format!("ls:{}:archived", session_id),
+                ));
+            }
+        }
+        extra_row.push(InlineKeyboardButton::callback(
+            "Debug",
+            format!("ls:{}:debug", session_id),
+        ));
+        let back_row = rows.len() - 1;
+        rows.insert(back_row, extra_row);
+        let open_row = build_open_link_row(entry, config.min_link_length);
+        if !open_row.is_empty() {
+            let back_row = rows.len() - 1;
+            rows.insert(back_row, open_row);
+        }
+        if is_bare_link_entry(entry) && !is_resource {
+            rows.insert(
+                0,
+                vec![InlineKeyboardButton::callback(
+                    "Add title",
+                    format!("ls:{}:title", session_id),
+                )],
+            );
+        }
+    }
+
+    (text, InlineKeyboardMarkup::new(rows))
+}
+
+/// Records to re-render the `/undos` view with after an action, or `None`
+/// when the message should be deleted instead.
+pub(super) fn undos_refresh_records(
+    refresh_in_place: bool,
+    shown: &[UndoRecord],
+    current: &[UndoRecord],
+) -> Option<Vec<UndoRecord>> {
+    if !refresh_in_place {
+        return None;
+    }
+    let live: HashSet<&str> = current.iter().map(|record| record.id.as_str()).collect();
+    let remaining: Vec<UndoRecord> = shown
+        .iter()
+        .filter(|record| live.contains(record.id.as_str()))
+        .cloned()
+        .collect();
+    (!remaining.is_empty()).then_some(remaining)
+}
+
 pub(super) fn build_undos_view(session_id: &str, records: &[UndoRecord]) -> (String, InlineKeyboardMarkup) {
     let mut text = format!("Undos ({})\n\n", records.len());
     for (idx, record) in records.iter().enumerate() {
         let label = match record.kind {
             UndoKind::MoveToFinished => "Moved to finished",
             UndoKind::Delete => "Deleted",
+            UndoKind::FinishToResource { .. } => "Finished + resource",
+            UndoKind::Edit { .. } => "Edited",
+            UndoKind::AddResource { .. } => "Added to resources",
         };
         text.push_str(&format!("{}) {}\n", idx + 1, label));
         let preview = undo_preview(&record.entry);
-        if let Some(first) = preview.get(0) {
+        if let Some(first) = preview.first() {
             text.push_str("   ");
             text.push_str(first);
             text.push('\n');
@@ -634,6 +1289,96 @@ pub(super) fn build_undos_view(session_id: &str, records: &[UndoRecord]) -> (Str
     (text.trim_end().to_string(), InlineKeyboardMarkup::new(rows))
 }
 
+pub(super) fn undo_op_for_record(record: &UndoRecord, config: &Config) -> QueuedOp {
+    let restore = |kind, resource_path| QueuedOp {
+        kind,
+        entry: record.entry.clone(),
+        resource_path,
+        updated_entry: None,
+        position: None,
+    };
+    match &record.kind {
+        UndoKind::MoveToFinished => restore(QueuedOpKind::MoveToReadLater, None),
+        UndoKind::Delete if config.trash_path.is_some() => {
+            restore(QueuedOpKind::RestoreFromTrash, None)
+        }
+        UndoKind::Delete => restore(QueuedOpKind::Add, None),
+        UndoKind::FinishToResource {
+            resource_path,
+            added: true,
+        } => restore(
+            QueuedOpKind::UndoFinishToResource,
+            Some(resource_path.clone()),
+        ),
+        // The resource already held the block, so only the finish is undone.
+        UndoKind::FinishToResource { added: false, .. } => {
+            restore(QueuedOpKind::MoveToReadLater, None)
+        }
+        UndoKind::AddResource { resource_path } => {
+            restore(QueuedOpKind::DeleteResource, Some(resource_path.clone()))
+        }
+        // An edit is undone by editing the updated block back to the original.
+        UndoKind::Edit {
+            updated_entry,
+            resource_path,
+        } => QueuedOp {
+            kind: QueuedOpKind::UpdateEntry,
+            entry: updated_entry.clone(),
+            resource_path: resource_path.clone(),
+            updated_entry: Some(record.entry.clone()),
+            position: None,
+        },
+    }
+}
+
+pub(super) fn build_trash_view(session_id: &str, entries: &[EntryBlock]) -> (String, InlineKeyboardMarkup) {
+    if entries.is_empty() {
+        return (
+            "Trash is empty.".to_string(),
+            InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
+                "Close",
+                format!("trash:{}:close", session_id),
+            )]]),
+        );
+    }
+
+    // Newest deletes are appended last; list them first.
+    let shown: Vec<usize> = (0..entries.len()).rev().take(TRASH_VIEW_LIMIT).collect();
+    let mut text = format!("Trash ({})\n\n", entries.len());
+    for (pos, idx) in shown.iter().enumerate() {
+        text.push_str(&format!("{})\n", pos + 1));
+        for line in entries[*idx].preview_lines() {
+            text.push_str("   ");
+            text.push_str(&line);
+            text.push('\n');
+        }
+        text.push('\n');
+    }
+
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = shown
+        .chunks(3)
+        .enumerate()
+        .map(|(row, chunk)| {
+            chunk
+                .iter()
+                .enumerate()
+                .map(|(col, idx)| {
+                    InlineKeyboardButton::callback(
+                        format!("Restore {}", row * 3 + col + 1),
+                        format!("trash:{}:restore:{}", session_id, idx),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    rows.push(vec![InlineKeyboardButton::callback(
+        "Close",
+        format!("trash:{}:close", session_id),
+    )]);
+
+    (text.trim_end().to_string(), InlineKeyboardMarkup::new(rows))
+}
+
 pub(super) fn build_finish_confirm_view(
     session_id: &str,
     session: &ListSession,
@@ -645,7 +1390,7 @@ pub(super) fn build_finish_confirm_view(
         .map(|e| format_embedded_references_for_lines(&e.preview_lines(), config))
         .unwrap_or_default();
     let mut text = String::from("Finish this item?\n\n");
-    if let Some(first) = preview.get(0) {
+    if let Some(first) = preview.first() {
         text.push_str(first);
         text.push('\n');
     }
@@ -663,6 +1408,14 @@ pub(super) fn build_finish_confirm_view(
             "Finish + Title",
             format!("ls:{}:finish_title", session_id),
         )],
+        vec![InlineKeyboardButton::callback(
+            "Finish + Note",
+            format!("ls:{}:finish_note", session_id),
+        )],
+        vec![InlineKeyboardButton::callback(
+            "Finish + Resource",
+            format!("ls:{}:finish_resource", session_id),
+        )],
         vec![InlineKeyboardButton::callback(
             "Cancel",
             format!("ls:{}:finish_cancel", session_id),
@@ -672,6 +1425,60 @@ pub(super) fn build_finish_confirm_view(
     (text.trim_end().to_string(), InlineKeyboardMarkup::new(rows))
 }
 
+/// Whether "Finish + Title" can prompt right away or needs the user to pick
+/// which of the item's links the title is for.
+pub(super) fn finish_title_step(mut links: Vec<String>) -> FinishTitleStep {
+    match links.len() {
+        0 => FinishTitleStep::NoLink,
+        1 => FinishTitleStep::Prompt(links.remove(0)),
+        _ => FinishTitleStep::PickLink(links),
+    }
+}
+
+pub(super) fn build_finish_link_view(
+    session_id: &str,
+    links: &[String],
+) -> (String, InlineKeyboardMarkup) {
+    let mut text = String::from("Which link is the title for?\n\n");
+    let mut rows = Vec::new();
+    for (idx, link) in links.iter().enumerate() {
+        text.push_str(&format!("{}) {}\n", idx + 1, link));
+        rows.push(vec![InlineKeyboardButton::callback(
+            format!("{}", idx + 1),
+            format!("ls:{}:finish_link:{}", session_id, idx),
+        )]);
+    }
+    rows.push(vec![InlineKeyboardButton::callback(
+        "Cancel",
+        format!("ls:{}:finish_cancel", session_id),
+    )]);
+    (text.trim_end().to_string(), InlineKeyboardMarkup::new(rows))
+}
+
+pub(super) fn build_finish_resource_view(
+    session_id: &str,
+    files: &[PathBuf],
+) -> (String, InlineKeyboardMarkup) {
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = files
+        .iter()
+        .enumerate()
+        .map(|(idx, path)| {
+            vec![InlineKeyboardButton::callback(
+                resource_file_label(path),
+                format!("ls:{}:finish_res:{}", session_id, idx),
+            )]
+        })
+        .collect();
+    rows.push(vec![InlineKeyboardButton::callback(
+        "Cancel",
+        format!("ls:{}:finish_cancel", session_id),
+    )]);
+    (
+        "Finish and add to which resource file?".to_string(),
+        InlineKeyboardMarkup::new(rows),
+    )
+}
+
 pub(super) fn build_delete_confirm_view(
     session_id: &str,
     session: &ListSession,
@@ -684,7 +1491,7 @@ pub(super) fn build_delete_confirm_view(
         .map(|e| format_embedded_references_for_lines(&e.preview_lines(), config))
         .unwrap_or_default();
     let mut text = format!("Confirm delete ({}/2)?\n\n", step);
-    if let Some(first) = preview.get(0) {
+    if let Some(first) = preview.first() {
         text.push_str(first);
         text.push('\n');
     }
@@ -708,6 +1515,103 @@ pub(super) fn build_delete_confirm_view(
     (text.trim_end().to_string(), InlineKeyboardMarkup::new(rows))
 }
 
+pub(super) fn build_finish_all_confirm_view(
+    session_id: &str,
+    session: &ListSession,
+    step: u8,
+) -> (String, InlineKeyboardMarkup) {
+    let text = format!(
+        "Move all {} matches to Finished ({}/2)?",
+        session.entries.len(),
+        step
+    );
+    let confirm_action = if step == 1 {
+        "finish_all1"
+    } else {
+        "finish_all2"
+    };
+    let rows = vec![
+        vec![InlineKeyboardButton::callback(
+            "Confirm",
+            format!("ls:{}:{}", session_id, confirm_action),
+        )],
+        vec![InlineKeyboardButton::callback(
+            "Cancel",
+            format!("ls:{}:cancel_finish_all", session_id),
+        )],
+    ];
+    (text, InlineKeyboardMarkup::new(rows))
+}
+
+/// Whether a queue-clear button on `message_id` may take confirm `step`: the
+/// pending confirm must be for that message and step, and not expired.
+pub(super) fn queue_clear_confirm_valid(
+    confirm: Option<&QueueClearConfirm>,
+    message_id: MessageId,
+    step: u8,
+    now: u64,
+) -> bool {
+    confirm.is_some_and(|confirm| {
+        confirm.message_id == message_id && confirm.step == step && now <= confirm.expires_at
+    })
+}
+
+/// Renders `/queue`. `confirm_step` is 1 or 2 while clearing is being
+/// confirmed, and 0 otherwise.
+pub(super) fn build_queue_view(
+    queue: &[QueuedOp],
+    confirm_step: u8,
+) -> (String, InlineKeyboardMarkup) {
+    if queue.is_empty() {
+        let rows = vec![vec![InlineKeyboardButton::callback("Close", "queue:close")]];
+        return (
+            "Queue is empty.".to_string(),
+            InlineKeyboardMarkup::new(rows),
+        );
+    }
+    if confirm_step > 0 {
+        let text = format!(
+            "Drop all {} queued writes ({}/2)?",
+            queue.len(),
+            confirm_step
+        );
+        let confirm_action = if confirm_step == 1 {
+            "clear1"
+        } else {
+            "clear2"
+        };
+        let rows = vec![
+            vec![InlineKeyboardButton::callback(
+                "Confirm",
+                format!("queue:{}", confirm_action),
+            )],
+            vec![InlineKeyboardButton::callback("Cancel", "queue:cancel")],
+        ];
+        return (text, InlineKeyboardMarkup::new(rows));
+    }
+
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for op in queue {
+        let kind = format!("{:?}", op.kind);
+        match counts.iter_mut().find(|(name, _)| *name == kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((kind, 1)),
+        }
+    }
+    let mut text = format!("{} queued writes pending:", queue.len());
+    for (kind, count) in counts {
+        text.push_str(&format!("\n{} x{}", kind, count));
+    }
+    let rows = vec![
+        vec![
+            InlineKeyboardButton::callback("Retry now", "queue:retry"),
+            InlineKeyboardButton::callback("Clear queue", "queue:clear"),
+        ],
+        vec![InlineKeyboardButton::callback("Close", "queue:close")],
+    ];
+    (text, InlineKeyboardMarkup::new(rows))
+}
+
 pub(super) fn count_unpeeked_entries(entries: &[EntryBlock], peeked: &HashSet<String>) -> usize {
     entries
         .iter()
@@ -715,197 +1619,1569 @@ pub(super) fn count_unpeeked_entries(entries: &[EntryBlock], peeked: &HashSet<St
         .count()
 }
 
-pub(super) fn count_visible_entries(session: &ListSession, peeked: &HashSet<String>) -> usize {
-    match session.kind {
-        SessionKind::Search { .. } => session.entries.len(),
-        SessionKind::List => count_unpeeked_entries(&session.entries, peeked),
+pub(super) fn count_reviewed_entries(entries: &[EntryBlock], peeked: &HashSet<String>) -> usize {
+    entries.len() - count_unpeeked_entries(entries, peeked)
+}
+
+pub(super) fn build_progress_text(reviewed: usize, total: usize) -> String {
+    if total == 0 {
+        return "Read Later is empty.".to_string();
     }
+    let reviewed = reviewed.min(total);
+    format!(
+        "{} of {} reviewed ({}%)\n{}",
+        reviewed,
+        total,
+        reviewed * 100 / total,
+        render_progress_bar(reviewed, total, PROGRESS_BAR_WIDTH)
+    )
 }
 
-pub(super) fn ordered_unpeeked_indices(
-    entries: &[EntryBlock],
-    peeked: &HashSet<String>,
-    mode: ListMode,
-) -> Vec<usize> {
-    let mut indices: Vec<usize> = entries
+/// Summary for `/stats`. `order` says which end of Finished Reading holds the
+/// newest finished item.
+pub(super) fn build_stats_text(
+    read_later: &[EntryBlock],
+    finished: &[EntryBlock],
+    resource_count: usize,
+    min_link_length: usize,
+    order: FinishedOrder,
+) -> String {
+    let linked = read_later
         .iter()
-        .enumerate()
-        .filter(|(_, entry)| !peeked.contains(&entry.block_string()))
-        .map(|(idx, _)| idx)
-        .collect();
-    if matches!(mode, ListMode::Bottom) {
-        indices.reverse();
+        .filter(|entry| !extract_links(&entry.block_string(), min_link_length).is_empty())
+        .count();
+    let mut text = format!(
+        "Read Later: {}\nFinished: {}\nResources: {}\nRead Later items with links: {}",
+        read_later.len(),
+        finished.len(),
+        resource_count,
+        linked
+    );
+    let first_line =
+        |entry: &EntryBlock| entry.display_lines().into_iter().next().unwrap_or_default();
+    let (newest, oldest) = match order {
+        FinishedOrder::Prepend => (finished.first(), finished.last()),
+        FinishedOrder::Append => (finished.last(), finished.first()),
+    };
+    if let (Some(newest), Some(oldest)) = (newest, oldest) {
+        text.push_str(&format!("\n\nNewest finished: {}", first_line(newest)));
+        if finished.len() > 1 {
+            text.push_str(&format!("\nOldest finished: {}", first_line(oldest)));
+        }
     }
-    indices
+    text
 }
 
-pub(super) fn ordered_indices(entries: &[EntryBlock], mode: ListMode) -> Vec<usize> {
-    let mut indices: Vec<usize> = (0..entries.len()).collect();
-    if matches!(mode, ListMode::Bottom) {
-        indices.reverse();
+/// Prefixes item text with the date it was added, e.g. `2026-10-16 text`.
+pub(super) fn stamp_added_date(text: &str, today: NaiveDate) -> String {
+    format!("{} {}", today.format("%Y-%m-%d"), text)
+}
+
+/// Splits a leading pin marker off item text, so the added-date stamp after
+/// it can still be read.
+fn split_pin_marker(text: &str) -> (bool, &str) {
+    match text.strip_prefix(PINNED_MARKER) {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, text),
     }
-    indices
 }
 
-pub(super) fn peek_indices(
+/// Block with the added-date stamp taken off its first line, so the same text
+/// saved on different days still counts as a duplicate. A pin marker in front
+/// of the stamp is kept.
+pub(super) fn strip_added_date(block: &str) -> String {
+    let Some(rest) = block.strip_prefix("- ") else {
+        return block.to_string();
+    };
+    let (pinned, rest) = split_pin_marker(rest);
+    match (rest.get(..10), rest.get(10..)) {
+        (Some(date), Some(tail))
+            if tail.starts_with(' ') && NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok() =>
+        {
+            if pinned {
+                format!("- {} {}", PINNED_MARKER, &tail[1..])
+            } else {
+                format!("- {}", &tail[1..])
+            }
+        }
+        _ => block.to_string(),
+    }
+}
+
+pub(super) fn entry_added_date(entry: &EntryBlock) -> Option<NaiveDate> {
+    let first = entry.display_lines().into_iter().next()?;
+    let (_, first) = split_pin_marker(&first);
+    let candidate = first.get(..10)?;
+    if first.len() > 10 && !first[10..].starts_with(' ') {
+        return None;
+    }
+    NaiveDate::parse_from_str(candidate, "%Y-%m-%d").ok()
+}
+
+pub(super) fn finished_stamp_date(config: &Config) -> Option<NaiveDate> {
+    config.finished_timestamp.then(|| Local::now().date_naive())
+}
+
+fn finished_stamp_line_date(line: &str) -> Option<NaiveDate> {
+    let date = line.strip_prefix(FINISHED_STAMP_PREFIX)?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+pub(super) fn entry_finished_date(entry: &EntryBlock) -> Option<NaiveDate> {
+    entry
+        .lines
+        .iter()
+        .skip(1)
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| finished_stamp_line_date(line))
+}
+
+/// True for a stamp exactly as [`stamp_finished`] writes it. A line the user
+/// typed, such as "Finished: soon" or an unpadded date, doesn't count.
+fn is_bot_finished_stamp(line: &str) -> bool {
+    line.strip_prefix(FINISHED_STAMP_PREFIX)
+        .and_then(|date| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .ok()
+                .filter(|parsed| parsed.format("%Y-%m-%d").to_string() == date)
+        })
+        .is_some()
+}
+
+/// Drops the trailing "Finished: <date>" line the bot added, if any, so the
+/// entry matches what it was in Read Later.
+pub(super) fn strip_finished_stamp(entry: &EntryBlock) -> EntryBlock {
+    let mut entry = entry.clone();
+    let stamped = entry
+        .lines
+        .iter()
+        .skip(1)
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .is_some_and(|line| is_bot_finished_stamp(line));
+    if stamped {
+        while entry
+            .lines
+            .last()
+            .is_some_and(|line| line.trim().is_empty())
+        {
+            entry.lines.pop();
+        }
+        entry.lines.pop();
+    }
+    entry
+}
+
+/// Appends the finished date as the entry's last line. Any earlier stamp is
+/// replaced, so an entry never carries more than one.
+pub(super) fn stamp_finished(entry: &EntryBlock, date: NaiveDate) -> EntryBlock {
+    let mut entry = strip_finished_stamp(entry);
+    while entry
+        .lines
+        .last()
+        .is_some_and(|line| line.trim().is_empty())
+    {
+        entry.lines.pop();
+    }
+    entry.lines.push(format!(
+        "{}{}",
+        FINISHED_STAMP_PREFIX,
+        date.format("%Y-%m-%d")
+    ));
+    entry
+}
+
+/// Counts dated entries per day over the trailing `days` days, newest first.
+pub(super) fn entries_per_day(
+    entries: &[EntryBlock],
+    today: NaiveDate,
+    days: i64,
+) -> Vec<(NaiveDate, usize)> {
+    let start = today - chrono::Duration::days(days - 1);
+    let mut counts: HashMap<NaiveDate, usize> = HashMap::new();
+    for date in entries.iter().filter_map(entry_added_date) {
+        if date >= start && date <= today {
+            *counts.entry(date).or_insert(0) += 1;
+        }
+    }
+    let mut grouped: Vec<(NaiveDate, usize)> = counts.into_iter().collect();
+    grouped.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
+    grouped
+}
+
+pub(super) fn build_week_text(entries: &[EntryBlock], today: NaiveDate) -> String {
+    if !entries
+        .iter()
+        .any(|entry| entry_added_date(entry).is_some())
+    {
+        return "No dated entries. Enable track_added_dates to record when items are added."
+            .to_string();
+    }
+    let grouped = entries_per_day(entries, today, WEEK_DIGEST_DAYS);
+    if grouped.is_empty() {
+        return "Nothing added in the last 7 days.".to_string();
+    }
+    let total: usize = grouped.iter().map(|(_, count)| count).sum();
+    let mut text = format!("Added in the last 7 days: {}\n\n", total);
+    for (date, count) in grouped {
+        text.push_str(&format!("{}: {}\n", date.format("%a %Y-%m-%d"), count));
+    }
+    text.trim_end().to_string()
+}
+
+/// Summarizes Read Later items that have not been peeked yet, or `None` when
+/// there are none.
+pub(super) fn build_digest_text(
     entries: &[EntryBlock],
     peeked: &HashSet<String>,
-    mode: ListMode,
-    page: usize,
-) -> Vec<usize> {
-    let ordered = ordered_unpeeked_indices(entries, peeked, mode);
-    if ordered.is_empty() {
-        return Vec::new();
+) -> Option<String> {
+    let unpeeked: Vec<&EntryBlock> = entries
+        .iter()
+        .filter(|entry| !peeked.contains(&entry.block_string()))
+        .collect();
+    if unpeeked.is_empty() {
+        return None;
     }
-    let start = page * PAGE_SIZE;
-    if start >= ordered.len() {
-        return Vec::new();
+    let mut text = format!("Digest: {} unpeeked\n\n", unpeeked.len());
+    for entry in unpeeked.iter().take(DIGEST_PREVIEW_COUNT) {
+        text.push_str(&format!("- {}\n", entry.preview_lines()[0]));
     }
-    let end = (start + PAGE_SIZE).min(ordered.len());
-    ordered[start..end].to_vec()
+    if unpeeked.len() > DIGEST_PREVIEW_COUNT {
+        text.push_str(&format!(
+            "...and {} more\n",
+            unpeeked.len() - DIGEST_PREVIEW_COUNT
+        ));
+    }
+    Some(text.trim_end().to_string())
 }
 
-pub(super) fn peek_indices_all(entries: &[EntryBlock], mode: ListMode, page: usize) -> Vec<usize> {
-    let ordered = ordered_indices(entries, mode);
-    if ordered.is_empty() {
-        return Vec::new();
+/// The first time at `at` strictly after `now`: today if it is still ahead,
+/// otherwise tomorrow.
+pub(super) fn next_digest_at(now: NaiveDateTime, at: NaiveTime) -> NaiveDateTime {
+    let today = now.date().and_time(at);
+    if today > now {
+        today
+    } else {
+        today + chrono::Duration::days(1)
     }
-    let start = page * PAGE_SIZE;
-    if start >= ordered.len() {
-        return Vec::new();
+}
+
+/// "This week" and "this month" are the trailing 7 and 30 days, not calendar
+/// periods.
+pub(super) fn age_bucket(added: NaiveDate, today: NaiveDate) -> AgeBucket {
+    match (today - added).num_days() {
+        days if days <= 0 => AgeBucket::Today,
+        days if days < 7 => AgeBucket::ThisWeek,
+        days if days < 30 => AgeBucket::ThisMonth,
+        _ => AgeBucket::Older,
     }
-    let end = (start + PAGE_SIZE).min(ordered.len());
-    ordered[start..end].to_vec()
 }
 
-pub(super) fn peek_indices_for_session(
-    session: &ListSession,
-    peeked: &HashSet<String>,
-    mode: ListMode,
-    page: usize,
-) -> Vec<usize> {
-    match session.kind {
-        SessionKind::Search { .. } => peek_indices_all(&session.entries, mode, page),
-        SessionKind::List => peek_indices(&session.entries, peeked, mode, page),
+pub(super) fn build_age_text(entries: &[EntryBlock], today: NaiveDate) -> String {
+    let dates: Vec<NaiveDate> = entries.iter().filter_map(entry_added_date).collect();
+    if dates.is_empty() {
+        return "No dated entries. Enable track_added_dates to record when items are added."
+            .to_string();
+    }
+    let count = |bucket: AgeBucket| {
+        dates
+            .iter()
+            .filter(|date| age_bucket(**date, today) == bucket)
+            .count()
+    };
+    let mut text = format!(
+        "Read Later by age\n\nToday: {}\nThis week: {}\nThis month: {}\nOlder: {}",
+        count(AgeBucket::Today),
+        count(AgeBucket::ThisWeek),
+        count(AgeBucket::ThisMonth),
+        count(AgeBucket::Older)
+    );
+    let undated = entries.len() - dates.len();
+    if undated > 0 {
+        text.push_str(&format!("\nUndated: {}", undated));
     }
+    text
 }
 
-pub(super) fn normalize_peek_view(session: &mut ListSession, peeked: &HashSet<String>) {
-    if let ListView::Peek { mode, page } = session.view.clone() {
-        let indices = peek_indices_for_session(session, peeked, mode, page);
-        if indices.is_empty() && page > 0 {
-            session.view = ListView::Peek {
-                mode,
-                page: page.saturating_sub(1),
-            };
+/// Counts dated entries for every day from `start` to `end` inclusive, oldest
+/// first, with zero for days nothing was added.
+pub(super) fn daily_counts(
+    entries: &[EntryBlock],
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Vec<(NaiveDate, usize)> {
+    let mut counts: Vec<(NaiveDate, usize)> = start
+        .iter_days()
+        .take_while(|date| *date <= end)
+        .map(|date| (date, 0))
+        .collect();
+    for date in entries.iter().filter_map(entry_added_date) {
+        if date >= start && date <= end {
+            let offset = (date - start).num_days() as usize;
+            counts[offset].1 += 1;
         }
     }
+    counts
+}
+
+fn heatmap_cell(count: usize) -> char {
+    match count {
+        0 => '·',
+        1 => '░',
+        2..=3 => '▒',
+        4..=5 => '▓',
+        _ => '█',
+    }
+}
+
+pub(super) fn build_heatmap_text(entries: &[EntryBlock], today: NaiveDate) -> String {
+    if !entries
+        .iter()
+        .any(|entry| entry_added_date(entry).is_some())
+    {
+        return "No dated entries. Enable track_added_dates to record when items are added."
+            .to_string();
+    }
+    let start = today - chrono::Duration::days(HEATMAP_DAYS - 1);
+    let counts = daily_counts(entries, start, today);
+    let total: usize = counts.iter().map(|(_, count)| count).sum();
+
+    let mut text = format!(
+        "Added in the last {} days: {}\n\n      M T W T F S S\n",
+        HEATMAP_DAYS, total
+    );
+    let leading = start.weekday().num_days_from_monday() as usize;
+    let mut cells: Vec<Option<usize>> = vec![None; leading];
+    cells.extend(counts.iter().map(|(_, count)| Some(*count)));
+    let mut week_start = start - chrono::Duration::days(leading as i64);
+    for week in cells.chunks(7) {
+        let row: Vec<String> = week
+            .iter()
+            .map(|cell| cell.map(heatmap_cell).unwrap_or(' ').to_string())
+            .collect();
+        text.push_str(&format!(
+            "{} {}\n",
+            week_start.format("%m-%d"),
+            row.join(" ")
+        ));
+        week_start += chrono::Duration::days(7);
+    }
+    text.push_str("\n· 0  ░ 1  ▒ 2-3  ▓ 4-5  █ 6+");
+    text
+}
+
+/// Additions per day over the last `CHART_DAYS` days, oldest first.
+pub(super) fn chart_series(entries: &[EntryBlock], today: NaiveDate) -> Vec<(NaiveDate, usize)> {
+    daily_counts(
+        entries,
+        today - chrono::Duration::days(CHART_DAYS - 1),
+        today,
+    )
+}
+
+pub(super) fn render_chart_png(series: &[(NaiveDate, usize)]) -> Result<Vec<u8>> {
+    use plotters::prelude::*;
+
+    let width = CHART_BAR_WIDTH * (series.len() as u32 + 1);
+    let max = series
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let mut pixels = vec![0u8; (width * CHART_HEIGHT * 3) as usize];
+    {
+        let root =
+            BitMapBackend::with_buffer(&mut pixels, (width, CHART_HEIGHT)).into_drawing_area();
+        root.fill(&WHITE).context("draw chart")?;
+        let mut chart = ChartBuilder::on(&root)
+            .margin(CHART_BAR_WIDTH / 2)
+            .build_cartesian_2d(0..series.len(), 0..max)
+            .context("draw chart")?;
+        chart
+            .draw_series(series.iter().enumerate().map(|(idx, (_, count))| {
+                let mut bar = Rectangle::new(
+                    [(idx, 0), (idx + 1, *count)],
+                    RGBColor(66, 133, 244).filled(),
+                );
+                bar.set_margin(0, 0, 3, 3);
+                bar
+            }))
+            .context("draw chart")?;
+        chart
+            .draw_series(std::iter::once(PathElement::new(
+                vec![(0, 0), (series.len(), 0)],
+                RGBColor(80, 80, 80),
+            )))
+            .context("draw chart")?;
+        root.present().context("draw chart")?;
+    }
+    let img = image::RgbImage::from_raw(width, CHART_HEIGHT, pixels)
+        .ok_or_else(|| anyhow!("chart buffer has the wrong size"))?;
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::ImageRgb8(img)
+        .write_to(&mut bytes, image::ImageFormat::Png)
+        .context("encode chart")?;
+    Ok(bytes.into_inner())
+}
+
+pub(super) fn build_chart_text(series: &[(NaiveDate, usize)]) -> String {
+    let max = series
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let mut text = String::new();
+    for (date, count) in series {
+        let bar = "█".repeat((count * 20).div_ceil(max));
+        text.push_str(&format!("{} {:>3} {}\n", date.format("%m-%d"), count, bar));
+    }
+    text.trim_end().to_string()
+}
+
+/// Poll option text for an entry: its first line, cut to Telegram's limit.
+pub(super) fn poll_option_label(entry: &EntryBlock) -> String {
+    let first = entry
+        .display_lines()
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    if first.is_empty() {
+        return "(empty)".to_string();
+    }
+    if first.chars().count() <= POLL_OPTION_MAX_CHARS {
+        return first;
+    }
+    let cut: String = first.chars().take(POLL_OPTION_MAX_CHARS - 3).collect();
+    format!("{}...", cut)
+}
+
+/// Picks up to `count` random unpeeked entries (never more than Telegram's
+/// option limit) and labels them for a poll.
+pub(super) fn pick_poll_options(
+    entries: &[EntryBlock],
+    peeked: &HashSet<String>,
+    count: usize,
+) -> Vec<String> {
+    let mut candidates: Vec<&EntryBlock> = entries
+        .iter()
+        .filter(|entry| !peeked.contains(&entry.block_string()))
+        .collect();
+    candidates.shuffle(&mut rand::thread_rng());
+    candidates
+        .into_iter()
+        .take(count.min(POLL_MAX_OPTIONS))
+        .map(poll_option_label)
+        .collect()
+}
+
+pub(super) fn host_of(url: &str) -> Option<String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let host = authority.rsplit('@').next().unwrap_or("");
+    let host = host.split(':').next().unwrap_or("").to_ascii_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+    if host.is_empty() {
+        None
+    } else {
+        Some(host)
+    }
+}
+
+/// Appends the `#tag` configured for the first link's host to the first line,
+/// unless the text already has it. The most specific matching domain wins.
+pub(super) fn apply_auto_tag(text: &str, tags: &HashMap<String, String>) -> String {
+    if tags.is_empty() {
+        return text.to_string();
+    }
+    let Some(host) = extract_links(text, 0)
+        .first()
+        .and_then(|link| host_of(link))
+    else {
+        return text.to_string();
+    };
+    let tag = tags
+        .iter()
+        .filter(|(domain, _)| host == **domain || host.ends_with(&format!(".{}", domain)))
+        .max_by_key(|(domain, _)| domain.len())
+        .map(|(_, tag)| tag);
+    let Some(tag) = tag else {
+        return text.to_string();
+    };
+    let tag = format!("#{}", tag);
+    if text.split_whitespace().any(|word| word == tag) {
+        return text.to_string();
+    }
+    let (first, rest) = match text.split_once('\n') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (text, None),
+    };
+    let mut tagged = format!("{} {}", first.trim_end(), tag);
+    if let Some(rest) = rest {
+        tagged.push('\n');
+        tagged.push_str(rest);
+    }
+    tagged
+}
+
+/// Lowercased `#tags` in an entry, each listed once in order of appearance.
+pub(super) fn extract_tags(entry: &EntryBlock) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for line in entry.display_lines() {
+        for word in line.split_whitespace() {
+            let Some(rest) = word.strip_prefix('#') else {
+                continue;
+            };
+            let tag: String = rest
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'))
+                .collect::<String>()
+                .to_lowercase();
+            if !tag.is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+    tags
+}
+
+/// How many entries carry each tag, most used first. Ties sort by name.
+pub(super) fn tag_counts(entries: &[EntryBlock]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for entry in entries {
+        for tag in extract_tags(entry) {
+            *counts.entry(tag).or_insert(0) += 1;
+        }
+    }
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+pub(super) fn build_tags_text(counts: &[(String, usize)]) -> String {
+    if counts.is_empty() {
+        return "No tags found.".to_string();
+    }
+    let mut text = format!("Tags ({})\n\n", counts.len());
+    for (tag, count) in counts {
+        text.push_str(&format!("#{} ({})\n", tag, count));
+    }
+    text.trim_end().to_string()
+}
+
+pub(super) fn top_domains(entries: &[EntryBlock], limit: usize) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for entry in entries {
+        for link in extract_links(&entry.block_string(), 0) {
+            if let Some(host) = host_of(&link) {
+                *counts.entry(host).or_insert(0) += 1;
+            }
+        }
+    }
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(limit);
+    ranked
+}
+
+pub(super) fn build_top_domains_text(ranked: &[(String, usize)]) -> String {
+    if ranked.is_empty() {
+        return "No links found.".to_string();
+    }
+    let mut text = format!("Top {} domains\n\n", ranked.len());
+    for (idx, (host, count)) in ranked.iter().enumerate() {
+        text.push_str(&format!("{}) {} ({})\n", idx + 1, host, count));
+    }
+    text.trim_end().to_string()
+}
+
+/// Entries with at least one link, most links first. Ties keep file order.
+pub(super) fn entries_by_link_count(entries: &[EntryBlock]) -> Vec<(usize, &EntryBlock)> {
+    let mut counted: Vec<(usize, &EntryBlock)> = entries
+        .iter()
+        .map(|entry| (extract_links(&entry.block_string(), 0).len(), entry))
+        .filter(|(count, _)| *count > 0)
+        .collect();
+    counted.sort_by_key(|(count, _)| std::cmp::Reverse(*count));
+    counted
+}
+
+pub(super) fn build_link_heavy_text(entries: &[EntryBlock]) -> String {
+    let ranked = entries_by_link_count(entries);
+    if ranked.is_empty() {
+        return "No links found.".to_string();
+    }
+    let mut text = String::from("Most links per item\n\n");
+    for (idx, (count, entry)) in ranked.iter().take(LINK_HEAVY_LIMIT).enumerate() {
+        let first = entry.display_lines().into_iter().next().unwrap_or_default();
+        text.push_str(&format!("{}) {} links: {}\n", idx + 1, count, first));
+    }
+    text.trim_end().to_string()
+}
+
+/// The `limit` entries with the most characters, largest first.
+pub(super) fn largest_entries(entries: &[EntryBlock], limit: usize) -> Vec<(usize, &EntryBlock)> {
+    let mut sized: Vec<(usize, &EntryBlock)> = entries
+        .iter()
+        .map(|entry| (entry.block_string().chars().count(), entry))
+        .collect();
+    sized.sort_by_key(|(size, _)| std::cmp::Reverse(*size));
+    sized.truncate(limit);
+    sized
+}
+
+pub(super) fn build_biggest_text(ranked: &[(usize, &EntryBlock)]) -> String {
+    if ranked.is_empty() {
+        return "Read Later is empty.".to_string();
+    }
+    let mut text = String::from("Largest items\n\n");
+    for (idx, (size, entry)) in ranked.iter().enumerate() {
+        let first = entry.display_lines().into_iter().next().unwrap_or_default();
+        text.push_str(&format!("{}) {} chars: {}\n", idx + 1, size, first));
+    }
+    text.trim_end().to_string()
+}
+
+/// Groups entries by `key`, in file order, keeping only groups with more than
+/// one member. Entries without a key are left out.
+fn group_entries_by(
+    entries: &[EntryBlock],
+    key: impl Fn(&EntryBlock) -> Option<String>,
+) -> Vec<(String, Vec<&EntryBlock>)> {
+    let mut groups: Vec<(String, Vec<&EntryBlock>)> = Vec::new();
+    for entry in entries {
+        let Some(key) = key(entry) else {
+            continue;
+        };
+        match groups.iter_mut().find(|(existing, _)| *existing == key) {
+            Some((_, members)) => members.push(entry),
+            None => groups.push((key, vec![entry])),
+        }
+    }
+    groups.retain(|(_, members)| members.len() > 1);
+    groups
+}
+
+/// Groups entries that share a first link, in file order, keeping only groups
+/// with more than one member.
+pub(super) fn near_duplicate_groups(entries: &[EntryBlock]) -> Vec<(String, Vec<&EntryBlock>)> {
+    group_entries_by(entries, |entry| {
+        extract_links(&entry.block_string(), 0).into_iter().next()
+    })
+}
+
+pub(super) fn build_near_duplicates_text(entries: &[EntryBlock]) -> String {
+    let groups = near_duplicate_groups(entries);
+    if groups.is_empty() {
+        return "No near-duplicates found.".to_string();
+    }
+    let mut text = String::from("Items sharing a first link\n");
+    for (link, members) in &groups {
+        text.push_str(&format!("\n{} ({} items)\n", link, members.len()));
+        for entry in members {
+            let first = entry.display_lines().into_iter().next().unwrap_or_default();
+            text.push_str(&format!("- {}\n", first));
+        }
+    }
+    text.trim_end().to_string()
+}
+
+/// Groups entries that adding would treat as the same item (same
+/// [`dedupe_key`]), in file order, keeping only groups with more than one
+/// member.
+pub(super) fn duplicate_groups(
+    entries: &[EntryBlock],
+    dedupe_by: DedupeBy,
+) -> Vec<Vec<EntryBlock>> {
+    group_entries_by(entries, |entry| {
+        Some(dedupe_key(&entry.block_string(), dedupe_by))
+    })
+    .into_iter()
+    .map(|(_, members)| members.into_iter().cloned().collect())
+    .collect()
+}
+
+/// One duplicate group per page, with a Delete button for each member.
+pub(super) fn build_duplicates_view(
+    session_id: &str,
+    groups: &[Vec<EntryBlock>],
+    page: usize,
+) -> (String, InlineKeyboardMarkup) {
+    let close = vec![InlineKeyboardButton::callback(
+        "Close",
+        format!("dup:{}:close", session_id),
+    )];
+    let Some(members) = groups.get(page) else {
+        return (
+            "No duplicates left.".to_string(),
+            InlineKeyboardMarkup::new(vec![close]),
+        );
+    };
+    let mut text = format!("Duplicates (group {}/{})\n\n", page + 1, groups.len());
+    for (idx, entry) in members.iter().enumerate() {
+        text.push_str(&format!("{})\n", idx + 1));
+        for line in entry.preview_lines() {
+            text.push_str("   ");
+            text.push_str(&line);
+            text.push('\n');
+        }
+        text.push('\n');
+    }
+
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = (0..members.len())
+        .collect::<Vec<_>>()
+        .chunks(3)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .map(|idx| {
+                    InlineKeyboardButton::callback(
+                        format!("Delete {}", idx + 1),
+                        format!("dup:{}:del:{}", session_id, idx),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    let mut nav = Vec::new();
+    if page > 0 {
+        nav.push(InlineKeyboardButton::callback(
+            "Prev",
+            format!("dup:{}:page:{}", session_id, page - 1),
+        ));
+    }
+    if page + 1 < groups.len() {
+        nav.push(InlineKeyboardButton::callback(
+            "Next",
+            format!("dup:{}:page:{}", session_id, page + 1),
+        ));
+    }
+    if !nav.is_empty() {
+        rows.push(nav);
+    }
+    rows.push(close);
+
+    (text.trim_end().to_string(), InlineKeyboardMarkup::new(rows))
+}
+
+/// Drops `member` from the group on `page`, removing the group once it no
+/// longer holds duplicates. Returns the page to show next.
+pub(super) fn remove_duplicate_member(
+    groups: &mut Vec<Vec<EntryBlock>>,
+    page: usize,
+    member: usize,
+) -> usize {
+    if let Some(members) = groups.get_mut(page) {
+        if member < members.len() {
+            members.remove(member);
+        }
+        if members.len() < 2 {
+            groups.remove(page);
+        }
+    }
+    page.min(groups.len().saturating_sub(1))
+}
+
+/// Runs `check` over `links` with at most `concurrency` checks in flight,
+/// returning results in input order.
+pub(super) async fn check_links_bounded<F, Fut>(
+    links: Vec<String>,
+    concurrency: usize,
+    check: F,
+) -> Vec<(String, LinkHealth)>
+where
+    F: Fn(String) -> Fut,
+    Fut: std::future::Future<Output = LinkHealth> + Send + 'static,
+{
+    let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(concurrency.max(1)));
+    let mut handles = Vec::new();
+    for link in links {
+        let semaphore = semaphore.clone();
+        let future = check(link.clone());
+        handles.push((
+            link,
+            tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                future.await
+            }),
+        ));
+    }
+    let mut results = Vec::new();
+    for (link, handle) in handles {
+        let health = handle
+            .await
+            .unwrap_or_else(|err| LinkHealth::Failed(err.to_string()));
+        results.push((link, health));
+    }
+    results
+}
+
+pub(super) fn build_broken_links_text(results: &[(String, LinkHealth)]) -> String {
+    let broken: Vec<&(String, LinkHealth)> = results
+        .iter()
+        .filter(|(_, health)| *health != LinkHealth::Ok)
+        .collect();
+    if broken.is_empty() {
+        return format!("All {} links look fine.", results.len());
+    }
+    let mut text = format!("Broken links ({} of {})\n\n", broken.len(), results.len());
+    for (link, health) in broken.iter().take(BROKEN_LINKS_LIMIT) {
+        let reason = match health {
+            LinkHealth::Ok => String::new(),
+            LinkHealth::Status(code) => code.to_string(),
+            LinkHealth::Failed(err) => err.clone(),
+        };
+        text.push_str(&format!("- {} ({})\n", link, reason));
+    }
+    if broken.len() > BROKEN_LINKS_LIMIT {
+        text.push_str(&format!(
+            "...and {} more\n",
+            broken.len() - BROKEN_LINKS_LIMIT
+        ));
+    }
+    text.trim_end().to_string()
+}
+
+pub(super) fn render_progress_bar(done: usize, total: usize, width: usize) -> String {
+    let filled = (done.min(total) * width).checked_div(total).unwrap_or(0);
+    format!("[{}{}]", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+pub(super) fn count_visible_entries(session: &ListSession, peeked: &HashSet<String>) -> usize {
+    match session.kind {
+        SessionKind::Search { .. } | SessionKind::Resource { .. } => session.entries.len(),
+        SessionKind::List => count_unpeeked_entries(&session.entries, peeked),
+    }
+}
+
+/// Unpeeked entries in file order (reversed for `Bottom`). Pinned items are
+/// not moved up here; the list views do that in [`peek_indices`].
+pub(super) fn ordered_unpeeked_indices(
+    entries: &[EntryBlock],
+    peeked: &HashSet<String>,
+    mode: ListMode,
+) -> Vec<usize> {
+    let mut indices: Vec<usize> = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| !peeked.contains(&entry.block_string()))
+        .map(|(idx, _)| idx)
+        .collect();
+    if matches!(mode, ListMode::Bottom) {
+        indices.reverse();
+    }
+    indices
+}
+
+pub(super) fn ordered_indices(entries: &[EntryBlock], mode: ListMode) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..entries.len()).collect();
+    if matches!(mode, ListMode::Bottom) {
+        indices.reverse();
+    }
+    pinned_first(entries, indices)
+}
+
+pub(super) fn peek_indices(
+    entries: &[EntryBlock],
+    peeked: &HashSet<String>,
+    mode: ListMode,
+    page: usize,
+    page_size: usize,
+) -> Vec<usize> {
+    let ordered = pinned_first(entries, ordered_unpeeked_indices(entries, peeked, mode));
+    if ordered.is_empty() {
+        return Vec::new();
+    }
+    let start = page * page_size;
+    if start >= ordered.len() {
+        return Vec::new();
+    }
+    let end = (start + page_size).min(ordered.len());
+    ordered[start..end].to_vec()
+}
+
+pub(super) fn peek_indices_all(
+    entries: &[EntryBlock],
+    mode: ListMode,
+    page: usize,
+    page_size: usize,
+) -> Vec<usize> {
+    let ordered = ordered_indices(entries, mode);
+    if ordered.is_empty() {
+        return Vec::new();
+    }
+    let start = page * page_size;
+    if start >= ordered.len() {
+        return Vec::new();
+    }
+    let end = (start + page_size).min(ordered.len());
+    ordered[start..end].to_vec()
+}
+
+pub(super) fn peek_indices_for_session(
+    session: &ListSession,
+    peeked: &HashSet<String>,
+    mode: ListMode,
+    page: usize,
+    page_size: usize,
+) -> Vec<usize> {
+    match session.kind {
+        SessionKind::Search { .. } | SessionKind::Resource { .. } => {
+            peek_indices_all(&session.entries, mode, page, page_size)
+        }
+        SessionKind::List => peek_indices(&session.entries, peeked, mode, page, page_size),
+    }
+}
+
+pub(super) fn normalize_peek_view(
+    session: &mut ListSession,
+    peeked: &HashSet<String>,
+    page_size: usize,
+) {
+    if let ListView::Peek { mode, page } = session.view.clone() {
+        let indices = peek_indices_for_session(session, peeked, mode, page, page_size);
+        if indices.is_empty() && page > 0 {
+            session.view = ListView::Peek {
+                mode,
+                page: page.saturating_sub(1),
+            };
+        }
+    }
+}
+
+pub(super) fn preview_text(text: &str) -> Vec<String> {
+    let normalized = normalize_line_endings(text);
+    let lines: Vec<&str> = normalized.lines().collect();
+    let mut out = Vec::new();
+    if let Some(first) = lines.first() {
+        out.push(first.to_string());
+    }
+    if let Some(second) = lines.get(1) {
+        out.push(second.to_string());
+    }
+    if lines.len() > 2 {
+        if let Some(last) = out.last_mut() {
+            last.push_str("...");
+        }
+    }
+    out
+}
+
+pub(super) fn undo_preview(entry: &str) -> Vec<String> {
+    let entry = EntryBlock::from_block(entry);
+    entry.preview_lines()
+}
+
+pub(super) fn delete_message_keyboard() -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
+        "Delete message",
+        "msgdel",
+    )]])
+}
+
+pub(super) async fn send_message_with_delete_button(
+    bot: &Bot,
+    config: &Config,
+    chat_id: ChatId,
+    text: impl Into<String>,
+) -> Result<Message> {
+    let text = text.into();
+    let sent = tg_retry(config.send_retry_attempts, || {
+        send(bot, config, chat_id, text.clone()).reply_markup(delete_message_keyboard())
+    })
+    .await?;
+    Ok(sent)
+}
+
+/// How long to wait before retrying a failed Telegram request, or `None` when
+/// the error will not go away by trying again (a missing message, bad
+/// request, and so on).
+pub(super) fn tg_retry_delay(err: &teloxide::RequestError, attempt: u32) -> Option<Duration> {
+    match err {
+        teloxide::RequestError::Network(_) => Some(Duration::from_millis(
+            SEND_RETRY_BACKOFF_MS * u64::from(attempt + 1),
+        )),
+        teloxide::RequestError::RetryAfter(wait)
+            if *wait <= Duration::from_secs(SEND_RETRY_MAX_WAIT_SECS) =>
+        {
+            Some(*wait)
+        }
+        _ => None,
+    }
+}
+
+/// Runs a Telegram request up to `attempts` times, backing off between
+/// retryable failures.
+pub(super) async fn tg_retry<F, Fut, T>(
+    attempts: u32,
+    mut request: F,
+) -> Result<T, teloxide::RequestError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::IntoFuture<Output = Result<T, teloxide::RequestError>>,
+{
+    let mut attempt = 0;
+    loop {
+        match request().await {
+            Ok(value) => return Ok(value),
+            Err(err) => {
+                attempt += 1;
+                match tg_retry_delay(&err, attempt - 1) {
+                    Some(delay) if attempt < attempts => tokio::time::sleep(delay).await,
+                    _ => return Err(err),
+                }
+            }
+        }
+    }
+}
+
+pub(super) async fn send_ephemeral(
+    bot: &Bot,
+    config: &Config,
+    chat_id: ChatId,
+    text: &str,
+    ttl_secs: u64,
+) -> Result<()> {
+    let sent = tg_retry(config.send_retry_attempts, || {
+        send(bot, config, chat_id, text)
+    })
+    .await?;
+    let bot = bot.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(ttl_secs)).await;
+        let _ = bot.delete_message(chat_id, sent.id).await;
+    });
+    Ok(())
+}
+
+/// Deletes a message the user sent, after `source_delete_delay_ms` when set.
+pub(super) async fn delete_source_message(
+    bot: &Bot,
+    config: &Config,
+    chat_id: ChatId,
+    message_id: MessageId,
+) {
+    let Some(delay) = source_delete_delay(config) else {
+        let _ = bot.delete_message(chat_id, message_id).await;
+        return;
+    };
+    let bot = bot.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        let _ = bot.delete_message(chat_id, message_id).await;
+    });
+}
+
+pub(super) fn source_delete_delay(config: &Config) -> Option<Duration> {
+    (config.source_delete_delay_ms > 0)
+        .then(|| Duration::from_millis(config.source_delete_delay_ms))
+}
+
+pub(super) async fn send_error(
+    bot: &Bot,
+    config: &Config,
+    chat_id: ChatId,
+    text: &str,
+) -> Result<()> {
+    send_message_with_delete_button(bot, config, chat_id, text).await?;
+    Ok(())
+}
+
+/// All outgoing text goes through here so `parse_mode` is applied consistently.
+pub(super) fn send(
+    bot: &Bot,
+    config: &Config,
+    chat_id: ChatId,
+    text: impl Into<String>,
+) -> JsonRequest<SendMessage> {
+    let text = format_outgoing(&text.into(), config.parse_mode);
+    let request = bot.send_message(chat_id, text);
+    match config.parse_mode.telegram_mode() {
+        Some(mode) => request.parse_mode(mode),
+        None => request,
+    }
+}
+
+pub(super) fn edit(
+    bot: &Bot,
+    config: &Config,
+    chat_id: ChatId,
+    message_id: MessageId,
+    text: impl Into<String>,
+) -> JsonRequest<EditMessageText> {
+    let text = format_outgoing(&text.into(), config.parse_mode);
+    let request = bot.edit_message_text(chat_id, message_id, text);
+    match config.parse_mode.telegram_mode() {
+        Some(mode) => request.parse_mode(mode),
+        None => request,
+    }
+}
+
+pub(super) fn format_outgoing(text: &str, mode: OutgoingParseMode) -> String {
+    match mode {
+        OutgoingParseMode::Plain => text.to_string(),
+        OutgoingParseMode::MarkdownV2 => escape_markdown_v2(text),
+        OutgoingParseMode::Html => escape_html(text),
+    }
+}
+
+pub(super) fn escape_markdown_v2(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(
+            ch,
+            '_' | '*'
+                | '['
+                | ']'
+                | '('
+                | ')'
+                | '~'
+                | '`'
+                | '>'
+                | '#'
+                | '+'
+                | '-'
+                | '='
+                | '|'
+                | '{'
+                | '}'
+                | '.'
+                | '!'
+                | '\\'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+pub(super) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Makes invisible or easily-confused characters visible, so two entries that
+/// look identical can be told apart.
+pub(super) fn escape_nonprintable(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\n' | ' ' => out.push(ch),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control()
+                || c.is_whitespace()
+                || matches!(
+                    c,
+                    '\u{00AD}' | '\u{200B}'..='\u{200F}' | '\u{2060}' | '\u{FEFF}'
+                ) =>
+            {
+                out.push_str(&format!("\\u{{{:04X}}}", c as u32))
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub(super) async fn send_embedded_media_for_view(
+    bot: &Bot,
+    chat_id: ChatId,
+    state: &std::sync::Arc<AppState>,
+    session: &ListSession,
+    peeked: &HashSet<String>,
+) -> Result<Vec<MessageId>> {
+    let embeds = embedded_media_paths_for_view(session, peeked, &state.config);
+    send_media_paths(bot, chat_id, embeds).await
+}
+
+pub(super) async fn send_media_paths(
+    bot: &Bot,
+    chat_id: ChatId,
+    paths: Vec<PathBuf>,
+) -> Result<Vec<MessageId>> {
+    let mut sent_message_ids = Vec::new();
+    for path in paths {
+        if is_image_path(&path) {
+            let sent = bot.send_photo(chat_id, InputFile::file(path)).await?;
+            sent_message_ids.push(sent.id);
+        } else if is_video_path(&path) {
+            let sent = bot.send_video(chat_id, InputFile::file(path)).await?;
+            sent_message_ids.push(sent.id);
+        } else {
+            let sent = bot.send_document(chat_id, InputFile::file(path)).await?;
+            sent_message_ids.push(sent.id);
+        }
+    }
+    Ok(sent_message_ids)
+}
+
+/// Media embedded in the item at `position` (1-based) on the current peek
+/// page. Looking does not mark the item as peeked.
+pub(super) fn peek_item_media_paths(
+    session: &ListSession,
+    peeked: &HashSet<String>,
+    position: usize,
+    config: &Config,
+) -> Vec<PathBuf> {
+    let ListView::Peek { mode, page } = session.view else {
+        return Vec::new();
+    };
+    peek_indices_for_session(session, peeked, mode, page, config.page_size)
+        .get(position.saturating_sub(1))
+        .and_then(|index| session.entries.get(*index))
+        .map(|entry| extract_embedded_paths(&entry.display_lines(), config))
+        .unwrap_or_default()
+}
+
+pub(super) fn embedded_media_paths_for_view(
+    session: &ListSession,
+    peeked: &HashSet<String>,
+    config: &Config,
+) -> Vec<PathBuf> {
+    if session.hide_media {
+        return Vec::new();
+    }
+    extract_embedded_paths(
+        &embedded_lines_for_view(session, peeked, config.page_size),
+        config,
+    )
+}
+
+/// Asks for new text, a title, or a category for the selected item. Returns
+/// false when nothing is selected.
+pub(super) async fn open_edit_prompt(
+    bot: &Bot,
+    state: &std::sync::Arc<AppState>,
+    session: &ListSession,
+    mode: EditMode,
+) -> Result<bool> {
+    let ListView::Selected { return_to, index } = &session.view else {
+        return Ok(false);
+    };
+    let Some(entry) = session.entries.get(*index) else {
+        return Ok(false);
+    };
+    let chat_id = ChatId(session.chat_id);
+    let prompt_text = match &mode {
+        EditMode::Text => format!(
+            "Send the updated text for this item.\n\n{}",
+            entry.display_lines().join("\n")
+        ),
+        EditMode::Title { link } => format!("Send a title for {}", link),
+        EditMode::Category => "Send the new category for this item, or - to remove it.".to_string(),
+        EditMode::Due => "Send a due date as YYYY-MM-DD, or - to remove it.".to_string(),
+        EditMode::Position => format!(
+            "Send the new position for this item (1-{}).",
+            session.entries.len()
+        ),
+    };
+    let sent = send(bot, &state.config, chat_id, prompt_text).await?;
+    let prompt = EditPrompt {
+        session_id: session.id.clone(),
+        chat_id: session.chat_id,
+        entry: entry.block_string(),
+        target_path: session_target_path(session),
+        return_to: (**return_to).clone(),
+        prompt_message_id: sent.id,
+        expires_at: now_ts() + EDIT_PROMPT_TTL_SECS,
+        mode,
+    };
+    let previous = state
+        .edit_prompts
+        .lock()
+        .await
+        .insert(session.chat_id, prompt);
+    if let Some(previous) = previous {
+        let _ = bot
+            .delete_message(chat_id, previous.prompt_message_id)
+            .await;
+    }
+    Ok(true)
+}
+
+/// Asks for the title or note to finish `session.entries[index]` with. The
+/// list returns to `selected` once the reply arrives.
+pub(super) async fn open_finish_prompt(
+    bot: &Bot,
+    state: &std::sync::Arc<AppState>,
+    session: &ListSession,
+    index: usize,
+    selected: &ListView,
+    mode: FinishMode,
+) -> Result<bool> {
+    let Some(entry) = session.entries.get(index) else {
+        return Ok(false);
+    };
+    let chat_id = ChatId(session.chat_id);
+    let prompt_text = match &mode {
+        FinishMode::Title { .. } => "Send a title for the finished item.",
+        FinishMode::Note => "Send a note for the finished item.",
+    };
+    let sent = send(bot, &state.config, chat_id, prompt_text).await?;
+    let return_to = match selected {
+        ListView::Selected { return_to, .. } => (**return_to).clone(),
+        _ => ListView::Menu,
+    };
+    let prompt = FinishTitlePrompt {
+        session_id: session.id.clone(),
+        chat_id: session.chat_id,
+        entry: entry.block_string(),
+        mode,
+        return_to,
+        prompt_message_id: sent.id,
+        expires_at: now_ts() + FINISH_TITLE_PROMPT_TTL_SECS,
+    };
+    let previous = state
+        .finish_title_prompts
+        .lock()
+        .await
+        .insert(session.chat_id, prompt);
+    if let Some(previous) = previous {
+        let _ = bot
+            .delete_message(chat_id, previous.prompt_message_id)
+            .await;
+    }
+    Ok(true)
+}
+
+pub(super) async fn delete_embedded_media_messages(bot: &Bot, chat_id: ChatId, message_ids: &[MessageId]) {
+    for message_id in message_ids {
+        let _ = bot.delete_message(chat_id, *message_id).await;
+    }
+}
+
+pub(super) async fn refresh_embedded_media_for_view(
+    bot: &Bot,
+    chat_id: ChatId,
+    state: &std::sync::Arc<AppState>,
+    session: &mut ListSession,
+    peeked: &HashSet<String>,
+) -> Result<()> {
+    delete_embedded_media_messages(bot, chat_id, &session.sent_media_message_ids).await;
+    session.sent_media_message_ids =
+        send_embedded_media_for_view(bot, chat_id, state, session, peeked).await?;
+    Ok(())
+}
+
+pub(super) fn list_update_order(config: &Config) -> [ListUpdateStep; 2] {
+    if config.media_before_text {
+        [ListUpdateStep::Media, ListUpdateStep::Text]
+    } else {
+        [ListUpdateStep::Text, ListUpdateStep::Media]
+    }
+}
+
+/// Edits the list message (or sends it if there is none yet) and refreshes its
+/// embedded media, in the order given by `media_before_text`.
+pub(super) async fn show_list_view(
+    bot: &Bot,
+    chat_id: ChatId,
+    state: &std::sync::Arc<AppState>,
+    session: &mut ListSession,
+    peeked: &HashSet<String>,
+) -> Result<()> {
+    for step in list_update_order(&state.config) {
+        match step {
+            ListUpdateStep::Text => {
+                let (text, kb) = render_list_view(&session.id, session, peeked, &state.config);
+                let attempts = state.config.send_retry_attempts;
+                if let Some(message_id) = session.message_id {
+                    tg_retry(attempts, || {
+                        edit(bot, &state.config, chat_id, message_id, text.clone())
+                            .reply_markup(kb.clone())
+                    })
+                    .await?;
+                } else {
+                    let sent = tg_retry(attempts, || {
+                        send(bot, &state.config, chat_id, text.clone()).reply_markup(kb.clone())
+                    })
+                    .await?;
+                    session.message_id = Some(sent.id);
+                }
+            }
+            ListUpdateStep::Media => {
+                if let Err(err) =
+                    refresh_embedded_media_for_view(bot, chat_id, state, session, peeked).await
+                {
+                    error!("send embedded media failed: {:#}", err);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(super) async fn reset_peeked(state: &std::sync::Arc<AppState>) -> Result<()> {
+    let mut peeked = state.peeked.lock().await;
+    peeked.clear();
+    save_peeked(&state.peeked_path, &peeked)
+}
+
+/// Whether every Read Later item is peeked and the config asks to start over
+/// instead of showing the "Everything's been peeked" dead end.
+pub(super) fn should_auto_reset_peeked(
+    config: &Config,
+    entries: &[EntryBlock],
+    peeked: &HashSet<String>,
+) -> bool {
+    config.auto_reset_when_all_peeked
+        && !entries.is_empty()
+        && entries
+            .iter()
+            .all(|entry| peeked.contains(&entry.block_string()))
 }
 
-pub(super) fn preview_text(text: &str) -> Vec<String> {
-    let normalized = normalize_line_endings(text);
-    let lines: Vec<&str> = normalized.lines().collect();
-    let mut out = Vec::new();
-    if let Some(first) = lines.get(0) {
-        out.push(first.to_string());
+pub(super) async fn mark_peeked(
+    state: &std::sync::Arc<AppState>,
+    entry: &EntryBlock,
+) -> Result<()> {
+    let mut peeked = state.peeked.lock().await;
+    if peeked.insert(entry.block_string()) {
+        save_peeked(&state.peeked_path, &peeked)?;
     }
-    if let Some(second) = lines.get(1) {
-        out.push(second.to_string());
+    Ok(())
+}
+
+/// Whether the entry's first line carries the archived-elsewhere marker.
+pub(super) fn is_archived(entry: &EntryBlock) -> bool {
+    entry
+        .lines
+        .first()
+        .is_some_and(|first| first.trim_end().ends_with(ARCHIVED_MARKER))
+}
+
+/// Appends the archived marker to the first line, or `None` if it is already there.
+pub(super) fn mark_archived(entry: &EntryBlock) -> Option<EntryBlock> {
+    if is_archived(entry) {
+        return None;
     }
-    if lines.len() > 2 {
-        if let Some(last) = out.last_mut() {
-            last.push_str("...");
-        }
+    let mut marked = entry.clone();
+    if let Some(first) = marked.lines.first_mut() {
+        *first = format!("{} {}", first.trim_end(), ARCHIVED_MARKER);
     }
-    out
+    Some(marked)
 }
 
-pub(super) fn undo_preview(entry: &str) -> Vec<String> {
-    let entry = EntryBlock::from_block(entry);
-    entry.preview_lines()
+pub(super) fn is_pinned(entry: &EntryBlock) -> bool {
+    entry.lines.first().is_some_and(|first| {
+        first
+            .trim_start_matches('-')
+            .trim_start()
+            .starts_with(PINNED_MARKER)
+    })
 }
 
-pub(super) fn delete_message_keyboard() -> InlineKeyboardMarkup {
-    InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
-        "Delete message",
-        "msgdel",
-    )]])
+/// Puts the pin marker at the start of the first line, or `None` if it is
+/// already pinned.
+pub(super) fn pin_entry(entry: &EntryBlock) -> Option<EntryBlock> {
+    if is_pinned(entry) {
+        return None;
+    }
+    let mut pinned = entry.clone();
+    if let Some(first) = pinned.lines.first_mut() {
+        let text = first.trim_start_matches('-').trim_start();
+        *first = format!("- {} {}", PINNED_MARKER, text);
+    }
+    Some(pinned)
 }
 
-pub(super) async fn send_message_with_delete_button(
-    bot: &Bot,
-    chat_id: ChatId,
-    text: impl Into<String>,
-) -> Result<Message> {
-    let sent = bot
-        .send_message(chat_id, text.into())
-        .reply_markup(delete_message_keyboard())
-        .await?;
-    Ok(sent)
+/// Removes the pin marker, or `None` if the entry is not pinned.
+pub(super) fn unpin_entry(entry: &EntryBlock) -> Option<EntryBlock> {
+    if !is_pinned(entry) {
+        return None;
+    }
+    let mut unpinned = entry.clone();
+    if let Some(first) = unpinned.lines.first_mut() {
+        let text = first.trim_start_matches('-').trim_start();
+        let text = text.trim_start_matches(PINNED_MARKER).trim_start();
+        *first = format!("- {}", text);
+    }
+    Some(unpinned)
 }
 
-pub(super) async fn send_ephemeral(bot: &Bot, chat_id: ChatId, text: &str, ttl_secs: u64) -> Result<()> {
-    let sent = bot.send_message(chat_id, text).await?;
-    let bot = bot.clone();
-    tokio::spawn(async move {
-        tokio::time::sleep(Duration::from_secs(ttl_secs)).await;
-        let _ = bot.delete_message(chat_id, sent.id).await;
-    });
-    Ok(())
+/// `entry` without its pin marker, as it is written to Finished.
+pub(super) fn without_pin(entry: &EntryBlock) -> EntryBlock {
+    unpin_entry(entry).unwrap_or_else(|| entry.clone())
 }
 
-pub(super) async fn send_error(bot: &Bot, chat_id: ChatId, text: &str) -> Result<()> {
-    send_message_with_delete_button(bot, chat_id, text).await?;
-    Ok(())
+/// Moves the indices of pinned entries ahead of the rest, keeping the order
+/// within each group.
+pub(super) fn pinned_first(entries: &[EntryBlock], indices: Vec<usize>) -> Vec<usize> {
+    let (mut pinned, rest): (Vec<usize>, Vec<usize>) = indices
+        .into_iter()
+        .partition(|idx| entries.get(*idx).is_some_and(is_pinned));
+    pinned.extend(rest);
+    pinned
 }
 
-pub(super) async fn send_embedded_media_for_view(
-    bot: &Bot,
-    chat_id: ChatId,
-    state: &std::sync::Arc<AppState>,
-    session: &ListSession,
+/// Indices Random may land on: not yet shown this session, not peeked, and
+/// not archived elsewhere when `skip_archived` is set.
+pub(super) fn random_candidates(
+    entries: &[EntryBlock],
+    seen: &HashSet<usize>,
     peeked: &HashSet<String>,
-) -> Result<Vec<MessageId>> {
-    let lines = embedded_lines_for_view(session, peeked);
-    let embeds = extract_embedded_paths(&lines, &state.config);
-    let mut sent_message_ids = Vec::new();
-    for path in embeds {
-        if is_image_path(&path) {
-            let sent = bot.send_photo(chat_id, InputFile::file(path)).await?;
-            sent_message_ids.push(sent.id);
-        } else if is_video_path(&path) {
-            let sent = bot.send_video(chat_id, InputFile::file(path)).await?;
-            sent_message_ids.push(sent.id);
-        } else {
-            let sent = bot.send_document(chat_id, InputFile::file(path)).await?;
-            sent_message_ids.push(sent.id);
-        }
-    }
-    Ok(sent_message_ids)
+    skip_archived: bool,
+) -> Vec<usize> {
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !seen.contains(i))
+        .filter(|(_, entry)| !peeked.contains(&entry.block_string()))
+        .filter(|(_, entry)| !(skip_archived && is_archived(entry)))
+        .map(|(i, _)| i)
+        .collect()
 }
 
-pub(super) async fn delete_embedded_media_messages(bot: &Bot, chat_id: ChatId, message_ids: &[MessageId]) {
-    for message_id in message_ids {
-        let _ = bot.delete_message(chat_id, *message_id).await;
+/// Flips whether `entry` counts as peeked. Returns true when it is now peeked.
+pub(super) fn toggle_peeked(peeked: &mut HashSet<String>, entry: &EntryBlock) -> bool {
+    let block = entry.block_string();
+    if peeked.remove(&block) {
+        false
+    } else {
+        peeked.insert(block)
     }
 }
 
-pub(super) async fn refresh_embedded_media_for_view(
-    bot: &Bot,
-    chat_id: ChatId,
-    state: &std::sync::Arc<AppState>,
-    session: &mut ListSession,
-    peeked: &HashSet<String>,
-) -> Result<()> {
-    delete_embedded_media_messages(bot, chat_id, &session.sent_media_message_ids).await;
-    session.sent_media_message_ids =
-        send_embedded_media_for_view(bot, chat_id, state, session, peeked).await?;
-    Ok(())
+/// Removes the single Read Later entry matching `query` from `peeked`. Returns
+/// whether it had been peeked, or a message when zero or several entries match.
+pub(super) fn unpeek_entry(
+    entries: &[EntryBlock],
+    peeked: &mut HashSet<String>,
+    query: &str,
+) -> std::result::Result<bool, String> {
+    let matches = search_entries(entries, query);
+    match matches.as_slice() {
+        [] => Err("No matching item.".to_string()),
+        [entry] => Ok(peeked.remove(&entry.block_string())),
+        _ => Err(format!(
+            "{} items match. Use a more specific query.",
+            matches.len()
+        )),
+    }
 }
 
-pub(super) async fn reset_peeked(state: &std::sync::Arc<AppState>) {
-    let mut peeked = state.peeked.lock().await;
+/// Forgets everything peeked and every random pick, and returns the session to
+/// its menu.
+pub(super) fn start_fresh(session: &mut ListSession, peeked: &mut HashSet<String>) {
     peeked.clear();
+    session.seen_random.clear();
+    session.view = ListView::Menu;
+}
+
+/// Records keep the expiry they were created with, so changing
+/// `undo_ttl_seconds` only affects new undos.
+pub(super) fn undo_expires_at(config: &Config, now: u64) -> u64 {
+    now + config.undo_ttl_seconds
 }
 
 pub(super) async fn add_undo(
@@ -920,12 +3196,87 @@ pub(super) async fn add_undo(
         id: id.clone(),
         kind,
         entry,
-        expires_at: now_ts() + UNDO_TTL_SECS,
+        expires_at: undo_expires_at(&state.config, now_ts()),
     });
     save_undo(&state.undo_path, &undo)?;
     Ok(id)
 }
 
+pub(super) async fn take_undo_record(
+    state: &std::sync::Arc<AppState>,
+    undo_id: &str,
+) -> Result<Option<UndoRecord>> {
+    let (record, undo_snapshot) = {
+        let mut undo = state.undo.lock().await;
+        prune_undo(&mut undo);
+        let pos = undo.iter().position(|r| r.id == undo_id);
+        let record = pos.map(|pos| undo.remove(pos));
+        (record, undo.clone())
+    };
+    save_undo(&state.undo_path, &undo_snapshot)?;
+    Ok(record)
+}
+
+/// Puts a reverted item back at the index it was removed from, or at the end
+/// if the list has since shrunk. Returns the index used.
+pub(super) fn reinsert_session_entry(
+    entries: &mut Vec<EntryBlock>,
+    entry: EntryBlock,
+    index: usize,
+) -> usize {
+    let index = index.min(entries.len());
+    entries.insert(index, entry);
+    index
+}
+
+/// Inserts a restored item at `position` (clamped to the list), or at the top
+/// when there is none.
+pub(super) fn insert_at_position(
+    entries: &mut Vec<EntryBlock>,
+    entry: EntryBlock,
+    position: Option<usize>,
+) {
+    reinsert_session_entry(entries, entry, position.unwrap_or(0));
+}
+
+/// Read Later index for an item undone from a session: in front of the item
+/// that now sits at its old session index (or after the one before it), so
+/// the file keeps the session's order even when the session is a filtered
+/// view. Falls back to the session index when neither neighbour is found.
+pub(super) fn undo_file_position(
+    file_entries: &[EntryBlock],
+    session_entries: &[EntryBlock],
+    index: usize,
+) -> usize {
+    let find = |entry: &EntryBlock| {
+        let block = entry.block_string();
+        file_entries.iter().position(|e| e.block_string() == block)
+    };
+    if let Some(pos) = session_entries.get(index).and_then(find) {
+        return pos;
+    }
+    index
+        .checked_sub(1)
+        .and_then(|prev| session_entries.get(prev))
+        .and_then(find)
+        .map_or(index, |pos| pos + 1)
+}
+
+/// Warning to show above the list opened by `/refresh`, given how the pull
+/// went. `None` means sync is not configured, so there was nothing to pull.
+pub(super) fn refresh_pull_warning(outcome: Option<&Result<PullOutcome>>) -> Option<String> {
+    match outcome {
+        None | Some(Ok(_)) => None,
+        Some(Err(err)) => Some(format!("Pull failed, showing local list: {}", err)),
+    }
+}
+
+pub(super) fn try_acquire_sync(flag: &AtomicBool) -> Option<SyncGuard<'_>> {
+    flag.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .ok()
+        .map(|_| SyncGuard { flag })
+}
+
 pub(super) async fn with_retries<F, T>(mut f: F) -> Result<T>
 where
     F: FnMut() -> Result<T>,
@@ -943,6 +3294,28 @@ where
     Err(last_err.unwrap_or_else(|| anyhow!("retry failed")))
 }
 
+/// Resolves a `${VAR}` token reference from the environment; anything else is
+/// used as the literal token.
+pub(super) fn resolve_token(raw: &str) -> Result<String> {
+    let trimmed = raw.trim();
+    let Some(name) = trimmed
+        .strip_prefix("${")
+        .and_then(|rest| rest.strip_suffix('}'))
+    else {
+        return Ok(raw.to_string());
+    };
+    let value = std::env::var(name)
+        .map_err(|_| anyhow!("token refers to ${{{}}}, but {} is not set", name, name))?;
+    if value.trim().is_empty() {
+        return Err(anyhow!(
+            "token refers to ${{{}}}, but {} is empty",
+            name,
+            name
+        ));
+    }
+    Ok(value.trim().to_string())
+}
+
 pub(super) fn resolve_user_id(input: UserIdInput, config_dir: &Path) -> Result<u64> {
     match input {
         UserIdInput::Number(value) => Ok(value),
@@ -1012,20 +3385,143 @@ pub(super) fn load_config(path: &Path) -> Result<Config> {
             .as_ref()
             .map(|p| resolve_user_id_path(p, config_dir)),
     });
+    ensure_parent_dir(&config_file.read_later_path, "read_later_path")?;
+    ensure_parent_dir(&config_file.finished_path, "finished_path")?;
+    if let Some(trash_path) = config_file.trash_path.as_deref() {
+        ensure_parent_dir(trash_path, "trash_path")?;
+    }
     Ok(Config {
-        token: config_file.token,
+        token: resolve_token(&config_file.token)?,
         user_id,
         read_later_path: config_file.read_later_path,
         finished_path: config_file.finished_path,
         resources_path: config_file.resources_path,
-        media_dir,
+        media_dir: media_dir.clone(),
         data_dir: config_file.data_dir,
         retry_interval_seconds: config_file.retry_interval_seconds,
         sync: config_file.sync,
         sync_x,
+        default_download_action: config_file.default_download_action,
+        playlist_item_limit: positive_or_default(
+            config_file.playlist_item_limit,
+            DEFAULT_PLAYLIST_ITEM_LIMIT,
+            "playlist_item_limit",
+        )?,
+        parse_mode: config_file.parse_mode,
+        trash_path: config_file.trash_path,
+        prune_media: config_file.prune_media,
+        max_photo_dimension: config_file.max_photo_dimension,
+        block_refinished_links: config_file.block_refinished_links,
+        track_added_dates: config_file.track_added_dates,
+        media_max_age_days: config_file.media_max_age_days,
+        command_prefix: config_file
+            .command_prefix
+            .filter(|prefix| !prefix.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_COMMAND_PREFIX.to_string()),
+        default_resource_file: config_file.default_resource_file,
+        media_before_text: config_file.media_before_text,
+        dedupe_by: config_file.dedupe_by,
+        min_link_length: config_file.min_link_length,
+        download_scan_active_page: config_file.download_scan_active_page,
+        auto_create_default_resource: config_file.auto_create_default_resource,
+        undos_refresh_in_place: config_file.undos_refresh_in_place,
+        link_check_concurrency: positive_or_default(
+            config_file.link_check_concurrency,
+            DEFAULT_LINK_CHECK_CONCURRENCY,
+            "link_check_concurrency",
+        )?,
+        link_check_timeout_ms: positive_or_default(
+            config_file.link_check_timeout_ms,
+            DEFAULT_LINK_CHECK_TIMEOUT_MS,
+            "link_check_timeout_ms",
+        )?,
+        display_normalize_links: config_file.display_normalize_links,
+        auto_tag_domains: config_file
+            .auto_tag_domains
+            .into_iter()
+            .map(|(host, tag)| {
+                let host = host.trim().to_ascii_lowercase();
+                let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+                let tag = tag.trim().trim_start_matches('#').to_string();
+                (host, tag)
+            })
+            .filter(|(host, tag)| !host.is_empty() && !tag.is_empty())
+            .collect(),
+        finished_timestamp: config_file.finished_timestamp,
+        page_size: page_size_or_default(config_file.page_size)?,
+        source_delete_delay_ms: config_file.source_delete_delay_ms,
+        send_retry_attempts: positive_or_default(
+            config_file.send_retry_attempts,
+            DEFAULT_SEND_RETRY_ATTEMPTS,
+            "send_retry_attempts",
+        )?,
+        random_skip_archived: config_file.random_skip_archived,
+        max_send_bytes: positive_or_default(
+            config_file.max_send_bytes,
+            DEFAULT_MAX_SEND_BYTES,
+            "max_send_bytes",
+        )?,
+        digest_time: parse_digest_time(config_file.digest_time.as_deref())?,
+        auto_reset_when_all_peeked: config_file.auto_reset_when_all_peeked,
+        undo_ttl_seconds: positive_or_default(
+            config_file.undo_ttl_seconds,
+            DEFAULT_UNDO_TTL_SECS,
+            "undo_ttl_seconds",
+        )?,
+        fetch_titles: config_file.fetch_titles,
+        finished_order: config_file.finished_order,
+        session_ttl_seconds: config_file
+            .session_ttl_seconds
+            .map(|ttl| positive_or_default(Some(ttl), ttl, "session_ttl_seconds"))
+            .transpose()?,
+        image_dir: config_file.image_dir.unwrap_or_else(|| media_dir.clone()),
+        video_dir: config_file.video_dir.unwrap_or_else(|| media_dir.clone()),
+        document_dir: config_file
+            .document_dir
+            .unwrap_or_else(|| media_dir.clone()),
     })
 }
 
+/// Creates the directory `path` lives in, so a missing vault folder fails at
+/// startup instead of sending every write to the retry queue.
+pub(super) fn ensure_parent_dir(path: &Path, name: &str) -> Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent)
+            .with_context(|| format!("{}: cannot create directory {}", name, parent.display())),
+        _ => Ok(()),
+    }
+}
+
+pub(super) fn positive_or_default<T>(value: Option<T>, default: T, name: &str) -> Result<T>
+where
+    T: PartialEq + Default,
+{
+    match value {
+        Some(value) if value == T::default() => Err(anyhow!("{} must be positive", name)),
+        Some(value) => Ok(value),
+        None => Ok(default),
+    }
+}
+
+pub(super) fn page_size_or_default(value: Option<usize>) -> Result<usize> {
+    match value {
+        Some(size) if !(1..=MAX_PAGE_SIZE).contains(&size) => {
+            Err(anyhow!("page_size must be between 1 and {}", MAX_PAGE_SIZE))
+        }
+        Some(size) => Ok(size),
+        None => Ok(DEFAULT_PAGE_SIZE),
+    }
+}
+
+pub(super) fn parse_digest_time(value: Option<&str>) -> Result<Option<NaiveTime>> {
+    value
+        .map(|value| {
+            NaiveTime::parse_from_str(value.trim(), "%H:%M")
+                .with_context(|| format!("digest_time must be HH:MM, got {:?}", value))
+        })
+        .transpose()
+}
+
 pub(super) fn list_resource_files(dir: &Path) -> Result<Vec<PathBuf>> {
     let mut files = Vec::new();
     if !dir.exists() {
@@ -1103,6 +3599,10 @@ pub(super) fn parse_entries(contents: &str) -> (Vec<String>, Vec<EntryBlock>) {
 }
 
 pub(super) fn write_entries(path: &Path, preamble: &[String], entries: &[EntryBlock]) -> Result<()> {
+    atomic_write(path, entries_content(preamble, entries).as_bytes())
+}
+
+pub(super) fn entries_content(preamble: &[String], entries: &[EntryBlock]) -> String {
     let mut lines: Vec<String> = Vec::new();
     lines.extend_from_slice(preamble);
     for entry in entries {
@@ -1112,7 +3612,18 @@ pub(super) fn write_entries(path: &Path, preamble: &[String], entries: &[EntryBl
     if !content.is_empty() {
         content.push('\n');
     }
-    atomic_write(path, content.as_bytes())
+    content
+}
+
+/// File name and markdown for the entries a session currently holds, so an
+/// export reflects the search or list that produced them.
+pub(super) fn session_export(session: &ListSession) -> (String, String) {
+    let name = match &session.kind {
+        SessionKind::List => "read-later.md".to_string(),
+        SessionKind::Search { .. } => "search-results.md".to_string(),
+        SessionKind::Resource { path } => resource_file_label(path),
+    };
+    (name, entries_content(&[], &session.entries))
 }
 
 pub(super) fn atomic_write(path: &Path, data: &[u8]) -> Result<()> {
@@ -1130,13 +3641,47 @@ pub(super) fn atomic_write(path: &Path, data: &[u8]) -> Result<()> {
     Ok(())
 }
 
-pub(super) fn add_entry_sync(path: &Path, entry: &EntryBlock) -> Result<AddOutcome> {
+/// Key two entries are compared by when checking for duplicates. The added
+/// date is ignored; with `first_link`, `[Title](url)` and a bare `url`
+/// collapse to the same key.
+pub(super) fn dedupe_key(block: &str, dedupe_by: DedupeBy) -> String {
+    match dedupe_by {
+        DedupeBy::Exact => strip_added_date(block),
+        DedupeBy::FirstLink => extract_links(block, 0)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| strip_added_date(block)),
+    }
+}
+
+pub(super) fn add_entry_sync(
+    path: &Path,
+    entry: &EntryBlock,
+    finished: Option<&Path>,
+    dedupe_by: DedupeBy,
+    position: Option<usize>,
+) -> Result<AddOutcome> {
     let (preamble, mut entries) = read_entries(path)?;
     let block = entry.block_string();
-    if entries.iter().any(|e| e.block_string() == block) {
+    let key = dedupe_key(&block, dedupe_by);
+    if entries
+        .iter()
+        .any(|e| dedupe_key(&e.block_string(), dedupe_by) == key)
+    {
         return Ok(AddOutcome::Duplicate);
     }
-    entries.insert(0, entry.clone());
+    if let Some(finished) = finished {
+        if let Some(link) = extract_links(&block, 0).into_iter().next() {
+            let (_, finished_entries) = read_entries(finished)?;
+            if finished_entries
+                .iter()
+                .any(|e| extract_links(&e.block_string(), 0).first() == Some(&link))
+            {
+                return Ok(AddOutcome::AlreadyFinished);
+            }
+        }
+    }
+    insert_at_position(&mut entries, entry.clone(), position);
     write_entries(path, &preamble, &entries)?;
     Ok(AddOutcome::Added)
 }
@@ -1169,17 +3714,61 @@ pub(super) fn add_resource_entry_sync(path: &Path, entry_block: &str) -> Result<
     Ok(AddOutcome::Added)
 }
 
-pub(super) fn delete_entry_sync(path: &Path, entry_block: &str) -> Result<ModifyOutcome> {
+pub(super) fn delete_entry_sync(
+    path: &Path,
+    entry_block: &str,
+    trash: Option<&Path>,
+) -> Result<ModifyOutcome> {
     let (preamble, mut entries) = read_entries(path)?;
     let pos = entries.iter().position(|e| e.block_string() == entry_block);
     let Some(pos) = pos else {
         return Ok(ModifyOutcome::NotFound);
     };
-    entries.remove(pos);
+    let entry = entries.remove(pos);
+
+    if let Some(trash) = trash {
+        let (preamble_trash, mut entries_trash) = read_entries(trash)?;
+        if !entries_trash.iter().any(|e| e.block_string() == entry_block) {
+            entries_trash.push(entry);
+            write_entries(trash, &preamble_trash, &entries_trash)?;
+        }
+    }
     write_entries(path, &preamble, &entries)?;
     Ok(ModifyOutcome::Applied)
 }
 
+/// Moves an entry from the trash back to the top of Read Later. Entries missing
+/// from the trash are still restored so undo keeps working for older deletes.
+pub(super) fn restore_from_trash_sync(
+    read_later: &Path,
+    trash: &Path,
+    entry_block: &str,
+    position: Option<usize>,
+) -> Result<ModifyOutcome> {
+    let (preamble_trash, mut entries_trash) = read_entries(trash)?;
+    let pos = entries_trash
+        .iter()
+        .position(|e| e.block_string() == entry_block);
+    let entry = match pos {
+        Some(pos) => entries_trash.remove(pos),
+        None => EntryBlock::from_block(entry_block),
+    };
+
+    let (preamble_rl, mut entries_rl) = read_entries(read_later)?;
+    let in_read_later = entries_rl.iter().any(|e| e.block_string() == entry_block);
+    if pos.is_none() && in_read_later {
+        return Ok(ModifyOutcome::NotFound);
+    }
+    if !in_read_later {
+        insert_at_position(&mut entries_rl, entry, position);
+        write_entries(read_later, &preamble_rl, &entries_rl)?;
+    }
+    if pos.is_some() {
+        write_entries(trash, &preamble_trash, &entries_trash)?;
+    }
+    Ok(ModifyOutcome::Applied)
+}
+
 pub(super) fn update_entry_sync(
     path: &Path,
     entry_block: &str,
@@ -1195,10 +3784,181 @@ pub(super) fn update_entry_sync(
     Ok(ModifyOutcome::Applied)
 }
 
+/// Moves `entries[from]` to the 1-based `position`, clamped to the list, and
+/// returns its new index.
+pub(super) fn move_entry_to_position(
+    entries: &mut Vec<EntryBlock>,
+    from: usize,
+    position: usize,
+) -> usize {
+    let to = position.clamp(1, entries.len()) - 1;
+    let entry = entries.remove(from);
+    entries.insert(to, entry);
+    to
+}
+
+/// Reorders the file so `entry_block` sits at `position`, returning its new
+/// index, or `None` if it is not in the file.
+pub(super) fn move_to_position_sync(
+    path: &Path,
+    entry_block: &str,
+    position: usize,
+) -> Result<Option<usize>> {
+    let (preamble, mut entries) = read_entries(path)?;
+    let Some(pos) = entries.iter().position(|e| e.block_string() == entry_block) else {
+        return Ok(None);
+    };
+    let index = move_entry_to_position(&mut entries, pos, position);
+    write_entries(path, &preamble, &entries)?;
+    Ok(Some(index))
+}
+
+pub(super) fn parse_position(text: &str) -> Option<usize> {
+    text.trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|position| *position > 0)
+}
+
+/// Yearly Finished archives live next to the Finished file.
+pub(super) fn archive_dir(config: &Config) -> &Path {
+    config
+        .finished_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+}
+
+pub(super) fn archive_file_path(archive_dir: &Path, year: i32) -> PathBuf {
+    archive_dir.join(format!("finished-archive-{}.md", year))
+}
+
+pub(super) fn list_archive_files(archive_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = list_resource_files(archive_dir)?
+        .into_iter()
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("finished-archive-"))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Collapses runs of blank lines after the header line down to one. Returns
+/// `None` when there is nothing to collapse.
+pub(super) fn collapse_blank_lines(entry: &EntryBlock) -> Option<EntryBlock> {
+    let mut lines: Vec<String> = Vec::with_capacity(entry.lines.len());
+    for (idx, line) in entry.lines.iter().enumerate() {
+        let blank = line.trim().is_empty();
+        let prev_blank = lines
+            .last()
+            .is_some_and(|prev: &String| prev.trim().is_empty());
+        if idx > 1 && blank && prev_blank {
+            continue;
+        }
+        lines.push(line.clone());
+    }
+    if lines.len() == entry.lines.len() {
+        None
+    } else {
+        Some(EntryBlock { lines })
+    }
+}
+
+pub(super) fn tidy_entries_sync(path: &Path) -> Result<usize> {
+    let (preamble, mut entries) = read_entries(path)?;
+    let mut tidied = 0;
+    for entry in entries.iter_mut() {
+        if let Some(collapsed) = collapse_blank_lines(entry) {
+            *entry = collapsed;
+            tidied += 1;
+        }
+    }
+    if tidied > 0 {
+        write_entries(path, &preamble, &entries)?;
+    }
+    Ok(tidied)
+}
+
+/// Splits finished entries into those dated before `cutoff`, those to keep,
+/// and how many were kept only because they have no date. The finished stamp
+/// wins over the added date when an entry has both.
+pub(super) fn split_archivable(
+    entries: Vec<EntryBlock>,
+    cutoff: NaiveDate,
+) -> (Vec<(NaiveDate, EntryBlock)>, Vec<EntryBlock>, usize) {
+    let mut archived = Vec::new();
+    let mut kept = Vec::new();
+    let mut undated = 0;
+    for entry in entries {
+        match entry_finished_date(&entry).or_else(|| entry_added_date(&entry)) {
+            Some(date) if date < cutoff => archived.push((date, entry)),
+            Some(_) => kept.push(entry),
+            None => {
+                undated += 1;
+                kept.push(entry);
+            }
+        }
+    }
+    (archived, kept, undated)
+}
+
+/// Moves finished entries dated before `cutoff` into per-year archive files
+/// in `archive_dir`, returning how many moved and how many were skipped for
+/// lacking a date. Archives are written before Finished Reading, so a failure
+/// part way leaves entries duplicated rather than lost.
+pub(super) fn archive_finished_sync(
+    finished: &Path,
+    archive_dir: &Path,
+    cutoff: NaiveDate,
+) -> Result<(usize, usize)> {
+    let (preamble, entries) = read_entries(finished)?;
+    let (archived, kept, undated) = split_archivable(entries, cutoff);
+    if archived.is_empty() {
+        return Ok((0, undated));
+    }
+
+    let mut by_year: Vec<(i32, Vec<EntryBlock>)> = Vec::new();
+    for (date, entry) in archived.iter().cloned() {
+        match by_year.iter_mut().find(|(year, _)| *year == date.year()) {
+            Some((_, entries)) => entries.push(entry),
+            None => by_year.push((date.year(), vec![entry])),
+        }
+    }
+    for (year, moving) in by_year {
+        let path = archive_file_path(archive_dir, year);
+        let (archive_preamble, existing) = read_entries(&path)?;
+        let existing_blocks: HashSet<String> = existing.iter().map(|e| e.block_string()).collect();
+        let mut combined: Vec<EntryBlock> = moving
+            .into_iter()
+            .filter(|entry| !existing_blocks.contains(&entry.block_string()))
+            .collect();
+        combined.extend(existing);
+        write_entries(&path, &archive_preamble, &combined)?;
+    }
+    write_entries(finished, &preamble, &kept)?;
+    Ok((archived.len(), undated))
+}
+
+/// Places a newly finished entry according to `order`.
+pub(super) fn insert_finished(
+    entries: &mut Vec<EntryBlock>,
+    entry: EntryBlock,
+    order: FinishedOrder,
+) {
+    match order {
+        FinishedOrder::Prepend => entries.insert(0, entry),
+        FinishedOrder::Append => entries.push(entry),
+    }
+}
+
 pub(super) fn move_to_finished_sync(
     read_later: &Path,
     finished: &Path,
     entry_block: &str,
+    finished_on: Option<NaiveDate>,
+    order: FinishedOrder,
 ) -> Result<ModifyOutcome> {
     let (preamble_rl, mut entries_rl) = read_entries(read_later)?;
     let pos = entries_rl
@@ -1207,20 +3967,62 @@ pub(super) fn move_to_finished_sync(
     let Some(pos) = pos else {
         return Ok(ModifyOutcome::NotFound);
     };
-    let entry = entries_rl.remove(pos);
+    let mut entry = without_pin(&entries_rl.remove(pos));
+    if let Some(date) = finished_on {
+        entry = stamp_finished(&entry, date);
+    }
 
     let (preamble_fin, mut entries_fin) = read_entries(finished)?;
-    entries_fin.insert(0, entry);
+    insert_finished(&mut entries_fin, entry, order);
     write_entries(finished, &preamble_fin, &entries_fin)?;
     write_entries(read_later, &preamble_rl, &entries_rl)?;
     Ok(ModifyOutcome::Applied)
 }
 
+/// Moves every listed entry still in Read Later to Finished with a single read
+/// and write of each file. Returns the blocks that were moved.
+pub(super) fn move_many_to_finished_sync(
+    read_later: &Path,
+    finished: &Path,
+    entry_blocks: &[String],
+    finished_on: Option<NaiveDate>,
+    order: FinishedOrder,
+) -> Result<Vec<String>> {
+    let wanted: HashSet<&str> = entry_blocks.iter().map(|block| block.as_str()).collect();
+    let (preamble_rl, entries_rl) = read_entries(read_later)?;
+    let (kept, moving): (Vec<EntryBlock>, Vec<EntryBlock>) = entries_rl
+        .into_iter()
+        .partition(|e| !wanted.contains(e.block_string().as_str()));
+    if moving.is_empty() {
+        return Ok(Vec::new());
+    }
+    let moved: Vec<String> = moving.iter().map(|e| e.block_string()).collect();
+
+    let (preamble_fin, mut entries_fin) = read_entries(finished)?;
+    let moving: Vec<EntryBlock> = moving.iter().map(without_pin).collect();
+    let moving: Vec<EntryBlock> = match finished_on {
+        Some(date) => moving.iter().map(|e| stamp_finished(e, date)).collect(),
+        None => moving,
+    };
+    let combined = match order {
+        FinishedOrder::Prepend => moving.into_iter().chain(entries_fin).collect(),
+        FinishedOrder::Append => {
+            entries_fin.extend(moving);
+            entries_fin
+        }
+    };
+    write_entries(finished, &preamble_fin, &combined)?;
+    write_entries(read_later, &preamble_rl, &kept)?;
+    Ok(moved)
+}
+
 pub(super) fn move_to_finished_updated_sync(
     read_later: &Path,
     finished: &Path,
     entry_block: &str,
     updated_entry: &str,
+    finished_on: Option<NaiveDate>,
+    order: FinishedOrder,
 ) -> Result<ModifyOutcome> {
     let (preamble_rl, mut entries_rl) = read_entries(read_later)?;
     let pos = entries_rl
@@ -1229,32 +4031,92 @@ pub(super) fn move_to_finished_updated_sync(
     let Some(pos) = pos else {
         return Ok(ModifyOutcome::NotFound);
     };
-    entries_rl.remove(pos);
+    entries_rl.remove(pos);
+
+    let (preamble_fin, mut entries_fin) = read_entries(finished)?;
+    let mut updated_entry = without_pin(&EntryBlock::from_block(updated_entry));
+    if let Some(date) = finished_on {
+        updated_entry = stamp_finished(&updated_entry, date);
+    }
+    insert_finished(&mut entries_fin, updated_entry, order);
+    write_entries(finished, &preamble_fin, &entries_fin)?;
+    write_entries(read_later, &preamble_rl, &entries_rl)?;
+    Ok(ModifyOutcome::Applied)
+}
+
+/// The block an entry gets when it is added to a resource file.
+pub(super) fn resource_block_for_entry(entry: &EntryBlock) -> String {
+    resource_block_from_text(&entry.display_lines().join("\n"))
+}
+
+/// Adds the entry to `resource`, moves it to Finished, and removes it from
+/// Read Later. An entry already in the resource file is not added twice; the
+/// returned flag says whether the resource block was added by this call.
+pub(super) fn finish_to_resource_sync(
+    read_later: &Path,
+    finished: &Path,
+    resource: &Path,
+    entry_block: &str,
+    finished_on: Option<NaiveDate>,
+    order: FinishedOrder,
+) -> Result<(ModifyOutcome, bool)> {
+    let (_, entries_rl) = read_entries(read_later)?;
+    let Some(entry) = entries_rl.iter().find(|e| e.block_string() == entry_block) else {
+        return Ok((ModifyOutcome::NotFound, false));
+    };
+    let added = matches!(
+        add_resource_entry_sync(resource, &resource_block_for_entry(entry))?,
+        AddOutcome::Added
+    );
+    let outcome = move_to_finished_sync(read_later, finished, entry_block, finished_on, order)?;
+    Ok((outcome, added))
+}
 
-    let (preamble_fin, mut entries_fin) = read_entries(finished)?;
-    let updated_entry = EntryBlock::from_block(updated_entry);
-    entries_fin.insert(0, updated_entry);
-    write_entries(finished, &preamble_fin, &entries_fin)?;
-    write_entries(read_later, &preamble_rl, &entries_rl)?;
-    Ok(ModifyOutcome::Applied)
+/// Reverses [`finish_to_resource_sync`]: the entry goes back to Read Later
+/// (at `position`, or the top) and its block is removed from the resource file.
+pub(super) fn undo_finish_to_resource_sync(
+    read_later: &Path,
+    finished: &Path,
+    resource: &Path,
+    entry_block: &str,
+    position: Option<usize>,
+) -> Result<ModifyOutcome> {
+    let outcome = move_to_read_later_sync(read_later, finished, entry_block, position)?;
+    if matches!(outcome, ModifyOutcome::Applied) {
+        let block = resource_block_for_entry(&EntryBlock::from_block(entry_block));
+        delete_entry_sync(resource, &block, None)?;
+    }
+    Ok(outcome)
 }
 
 pub(super) fn move_to_read_later_sync(
     read_later: &Path,
     finished: &Path,
     entry_block: &str,
+    position: Option<usize>,
 ) -> Result<ModifyOutcome> {
     let (preamble_fin, mut entries_fin) = read_entries(finished)?;
-    let pos = entries_fin
-        .iter()
-        .position(|e| e.block_string() == entry_block);
+    // Finished holds the entry without its pin, so match that form too and
+    // put the pinned original back.
+    let original = EntryBlock::from_block(entry_block);
+    let unpinned = without_pin(&original).block_string();
+    let pos = entries_fin.iter().position(|e| {
+        let raw = e.block_string();
+        let stripped = strip_finished_stamp(e).block_string();
+        [entry_block, unpinned.as_str()]
+            .iter()
+            .any(|block| raw == *block || stripped == *block)
+    });
     let Some(pos) = pos else {
         return Ok(ModifyOutcome::NotFound);
     };
-    let entry = entries_fin.remove(pos);
+    let mut entry = strip_finished_stamp(&entries_fin.remove(pos));
+    if entry.block_string() == unpinned {
+        entry = original;
+    }
 
     let (preamble_rl, mut entries_rl) = read_entries(read_later)?;
-    entries_rl.insert(0, entry);
+    insert_at_position(&mut entries_rl, entry, position);
     write_entries(read_later, &preamble_rl, &entries_rl)?;
     write_entries(finished, &preamble_fin, &entries_fin)?;
     Ok(ModifyOutcome::Applied)
@@ -1266,8 +4128,7 @@ pub(super) fn load_queue(path: &Path) -> Result<Vec<QueuedOp>> {
     }
     let data =
         fs::read_to_string(path).with_context(|| format!("read queue {}", path.display()))?;
-    let queue = serde_json::from_str(&data).context("parse queue")?;
-    Ok(queue)
+    parse_json_or_backup(path, &data, "queue")
 }
 
 pub(super) fn save_queue(path: &Path, queue: &[QueuedOp]) -> Result<()> {
@@ -1280,8 +4141,27 @@ pub(super) fn load_undo(path: &Path) -> Result<Vec<UndoRecord>> {
         return Ok(Vec::new());
     }
     let data = fs::read_to_string(path).with_context(|| format!("read undo {}", path.display()))?;
-    let undo = serde_json::from_str(&data).context("parse undo")?;
-    Ok(undo)
+    parse_json_or_backup(path, &data, "undo")
+}
+
+/// Parses a persisted JSON list, moving an unreadable file aside to
+/// `<name>.corrupt` and starting empty instead of refusing to boot.
+pub(super) fn parse_json_or_backup<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    data: &str,
+    label: &str,
+) -> Result<Vec<T>> {
+    match serde_json::from_str(data) {
+        Ok(items) => Ok(items),
+        Err(err) => {
+            error!("parse {} {} failed: {}", label, path.display(), err);
+            let mut backup = path.as_os_str().to_owned();
+            backup.push(".corrupt");
+            fs::rename(path, &backup)
+                .with_context(|| format!("back up corrupt {} {}", label, path.display()))?;
+            Ok(Vec::new())
+        }
+    }
 }
 
 pub(super) fn save_undo(path: &Path, undo: &[UndoRecord]) -> Result<()> {
@@ -1289,11 +4169,120 @@ pub(super) fn save_undo(path: &Path, undo: &[UndoRecord]) -> Result<()> {
     atomic_write(path, &data)
 }
 
+pub(super) fn load_peeked(path: &Path) -> Result<HashSet<String>> {
+    if !path.exists() {
+        return Ok(HashSet::new());
+    }
+    let data =
+        fs::read_to_string(path).with_context(|| format!("read peeked {}", path.display()))?;
+    Ok(parse_json_or_backup(path, &data, "peeked")?
+        .into_iter()
+        .collect())
+}
+
+pub(super) fn save_peeked(path: &Path, peeked: &HashSet<String>) -> Result<()> {
+    let mut blocks: Vec<&String> = peeked.iter().collect();
+    blocks.sort();
+    let data = serde_json::to_vec_pretty(&blocks).context("serialize peeked")?;
+    atomic_write(path, &data)
+}
+
+/// Drops peeked blocks that are no longer in Read Later. Returns whether
+/// anything was removed.
+pub(super) fn prune_peeked(peeked: &mut HashSet<String>, entries: &[EntryBlock]) -> bool {
+    let present: HashSet<String> = entries.iter().map(|e| e.block_string()).collect();
+    let before = peeked.len();
+    peeked.retain(|block| present.contains(block));
+    peeked.len() != before
+}
+
+pub(super) fn build_snapshot(
+    config: &Config,
+    queue: &[QueuedOp],
+    undo: &[UndoRecord],
+) -> Result<Snapshot> {
+    Ok(Snapshot {
+        version: SNAPSHOT_VERSION,
+        created_at: now_ts(),
+        read_later: read_file_or_empty(&config.read_later_path)?,
+        finished: read_file_or_empty(&config.finished_path)?,
+        queue: queue.to_vec(),
+        undo: undo.to_vec(),
+    })
+}
+
+fn read_file_or_empty(path: &Path) -> Result<String> {
+    if !path.exists() {
+        return Ok(String::new());
+    }
+    fs::read_to_string(path).with_context(|| format!("read {}", path.display()))
+}
+
+pub(super) fn serialize_snapshot(snapshot: &Snapshot) -> Result<Vec<u8>> {
+    serde_json::to_vec_pretty(snapshot).context("serialize snapshot")
+}
+
+pub(super) fn parse_snapshot(data: &[u8]) -> Result<Snapshot> {
+    let snapshot: Snapshot = serde_json::from_slice(data).context("parse snapshot")?;
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(anyhow!("unsupported snapshot version {}", snapshot.version));
+    }
+    Ok(snapshot)
+}
+
+/// Overwrites Read Later, Finished, the queue and the undo log with the
+/// snapshot contents. Callers must hold the write lock.
+pub(super) fn restore_snapshot_sync(
+    config: &Config,
+    queue_path: &Path,
+    undo_path: &Path,
+    snapshot: &Snapshot,
+) -> Result<()> {
+    atomic_write(&config.read_later_path, snapshot.read_later.as_bytes())?;
+    atomic_write(&config.finished_path, snapshot.finished.as_bytes())?;
+    save_queue(queue_path, &snapshot.queue)?;
+    save_undo(undo_path, &snapshot.undo)
+}
+
 pub(super) fn prune_undo(undo: &mut Vec<UndoRecord>) {
     let now = now_ts();
     undo.retain(|r| r.expires_at > now);
 }
 
+/// Drops expired undo records and ones that can no longer do anything: a
+/// finished move whose item left Finished, or a delete whose item is already
+/// back in Read Later. Returns how many records were removed.
+pub(super) fn compact_undo(
+    undo: &mut Vec<UndoRecord>,
+    read_later: &[EntryBlock],
+    finished: &[EntryBlock],
+) -> usize {
+    let before = undo.len();
+    prune_undo(undo);
+    let read_later: HashSet<String> = read_later.iter().map(|e| e.block_string()).collect();
+    // Records hold the unstamped block, possibly pinned, so match finished
+    // items with and without their finished stamp and records without a pin.
+    let finished: HashSet<String> = finished
+        .iter()
+        .flat_map(|e| [e.block_string(), strip_finished_stamp(e).block_string()])
+        .collect();
+    undo.retain(|record| match &record.kind {
+        UndoKind::MoveToFinished | UndoKind::FinishToResource { .. } => {
+            finished.contains(&record.entry)
+                || finished
+                    .contains(&without_pin(&EntryBlock::from_block(&record.entry)).block_string())
+        }
+        UndoKind::Delete => !read_later.contains(&record.entry),
+        // Resource files are not checked here; those records stay until they expire.
+        UndoKind::Edit {
+            updated_entry,
+            resource_path,
+        } => resource_path.is_some() || read_later.contains(updated_entry),
+        UndoKind::AddResource { .. } => true,
+    });
+    before - undo.len()
+}
+
 pub(super) fn normalize_line_endings(input: &str) -> String {
     input.replace("\r\n", "\n").replace('\r', "\n")
 }
@@ -1352,6 +4341,42 @@ pub(super) fn sanitize_filename_with_default(input: &str, default_ext: Option<&s
     sanitized
 }
 
+/// `name`, or `name` with `-1`, `-2`, ... before the extension if a file with
+/// that name already exists in `dir`.
+pub(super) fn unique_filename(dir: &Path, name: &str) -> String {
+    if !dir.join(name).exists() {
+        return name.to_string();
+    }
+    let path = Path::new(name);
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(name);
+    let ext = path.extension().and_then(|ext| ext.to_str());
+    (1..)
+        .map(|n| match ext {
+            Some(ext) => format!("{}-{}.{}", stem, n, ext),
+            None => format!("{}-{}", stem, n),
+        })
+        .find(|candidate| !dir.join(candidate).exists())
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// `name` without the `-1`, `-2`, ... that `unique_filename` puts before the
+/// extension, or `None` if it has no such suffix.
+pub(super) fn strip_unique_suffix(name: &str) -> Option<String> {
+    let path = Path::new(name);
+    let stem = path.file_stem()?.to_str()?;
+    let (base, n) = stem.rsplit_once('-')?;
+    if base.is_empty() || n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => format!("{}.{}", base, ext),
+        None => base.to_string(),
+    })
+}
+
 pub(super) fn extension_from_mime(mime: &str) -> Option<&str> {
     let (_, subtype) = mime.split_once('/')?;
     if subtype.eq_ignore_ascii_case("jpeg") {
@@ -1361,6 +4386,17 @@ pub(super) fn extension_from_mime(mime: &str) -> Option<&str> {
     }
 }
 
+/// Like `extension_from_mime`, but maps the audio subtypes whose name isn't a
+/// usable extension.
+pub(super) fn audio_extension_from_mime(mime: &str) -> Option<&str> {
+    match extension_from_mime(mime)?.to_ascii_lowercase().as_str() {
+        "mpeg" | "mp3" => Some("mp3"),
+        "mp4" | "x-m4a" | "m4a" => Some("m4a"),
+        "x-wav" | "wav" => Some("wav"),
+        _ => extension_from_mime(mime),
+    }
+}
+
 pub(super) fn build_media_entry_text(filename: &str, caption: Option<&str>) -> String {
     let mut text = format!("![[{}]]", filename);
     if let Some(caption) = caption {
@@ -1373,6 +4409,19 @@ pub(super) fn build_media_entry_text(filename: &str, caption: Option<&str>) -> S
     text
 }
 
+/// Lines as shown in the peek and selected views. Only the rendered text is
+/// affected; stored entries keep their markdown links.
+pub(super) fn format_display_lines(lines: &[String], config: &Config) -> Vec<String> {
+    let lines = format_embedded_references_for_lines(lines, config);
+    if !config.display_normalize_links {
+        return lines;
+    }
+    lines
+        .iter()
+        .map(|line| normalize_markdown_links(line).0)
+        .collect()
+}
+
 pub(super) fn format_embedded_references_for_lines(lines: &[String], config: &Config) -> Vec<String> {
     let mut labels: HashMap<PathBuf, usize> = HashMap::new();
     let mut next_label = 1usize;
@@ -1409,6 +4458,8 @@ pub(super) fn format_embedded_references_for_lines(lines: &[String], config: &Co
                     formatted.push_str(&format!("image #{}", label));
                 } else if is_video_path(&path) {
                     formatted.push_str(&format!("video #{}", label));
+                } else if is_audio_path(&path) {
+                    formatted.push_str(&format!("audio #{}", label));
                 } else {
                     formatted.push_str(&format!("file #{}", label));
                 }
@@ -1428,7 +4479,31 @@ pub(super) fn format_embedded_references_for_lines(lines: &[String], config: &Co
 pub(super) fn pick_best_photo(photos: &[teloxide::types::PhotoSize]) -> Option<&teloxide::types::PhotoSize> {
     photos
         .iter()
-        .max_by_key(|photo| photo.file.size.max((photo.width * photo.height) as u32) as u64)
+        .max_by_key(|photo| photo.file.size.max(photo.width * photo.height) as u64)
+}
+
+/// Returns the size to scale a photo down to so its longest side fits within
+/// `max_dimension`, or `None` when it already fits.
+pub(super) fn downscale_target(width: u32, height: u32, max_dimension: u32) -> Option<(u32, u32)> {
+    let longest = width.max(height);
+    if longest <= max_dimension || max_dimension == 0 {
+        return None;
+    }
+    let scale = |side: u32| ((side as u64 * max_dimension as u64) / longest as u64).max(1) as u32;
+    Some((scale(width), scale(height)))
+}
+
+pub(super) fn maybe_downscale(path: &Path, max_dimension: u32) -> Result<bool> {
+    let img = image::open(path).with_context(|| format!("open image {}", path.display()))?;
+    let Some((width, height)) = downscale_target(img.width(), img.height(), max_dimension) else {
+        return Ok(false);
+    };
+    let resized = img.resize_exact(width, height, image::imageops::FilterType::Lanczos3);
+    resized
+        .to_rgb8()
+        .save_with_format(path, image::ImageFormat::Jpeg)
+        .with_context(|| format!("save image {}", path.display()))?;
+    Ok(true)
 }
 
 pub(super) async fn download_telegram_file(bot: &Bot, file_id: &str, dest_path: &Path) -> Result<()> {
@@ -1439,6 +4514,14 @@ pub(super) async fn download_telegram_file(bot: &Bot, file_id: &str, dest_path:
 }
 
 pub(super) fn extract_embedded_paths(lines: &[String], config: &Config) -> Vec<PathBuf> {
+    embedded_target_paths(lines, config)
+        .into_iter()
+        .filter(|path| path.exists())
+        .collect()
+}
+
+/// Paths that `![[...]]` markers point at, whether or not the files exist.
+pub(super) fn embedded_target_paths(lines: &[String], config: &Config) -> Vec<PathBuf> {
     let mut paths = Vec::new();
     let mut seen = HashSet::new();
     for line in lines {
@@ -1450,7 +4533,7 @@ pub(super) fn extract_embedded_paths(lines: &[String], config: &Config) -> Vec<P
             };
             let end = start + end_rel;
             let inner = &line[start..end];
-            if let Some(path) = resolve_embedded_path(inner, config) {
+            if let Some(path) = embedded_target_path(inner, config) {
                 if seen.insert(path.clone()) {
                     paths.push(path);
                 }
@@ -1462,6 +4545,10 @@ pub(super) fn extract_embedded_paths(lines: &[String], config: &Config) -> Vec<P
 }
 
 pub(super) fn resolve_embedded_path(inner: &str, config: &Config) -> Option<PathBuf> {
+    embedded_target_path(inner, config).filter(|path| path.exists())
+}
+
+pub(super) fn embedded_target_path(inner: &str, config: &Config) -> Option<PathBuf> {
     let mut inner = inner.trim();
     if let Some((path_part, _)) = inner.split_once('|') {
         inner = path_part.trim();
@@ -1474,19 +4561,462 @@ pub(super) fn resolve_embedded_path(inner: &str, config: &Config) -> Option<Path
         .read_later_path
         .parent()
         .unwrap_or_else(|| Path::new("."));
-    let path = if Path::new(inner).is_absolute() {
-        PathBuf::from(inner)
+    if Path::new(inner).is_absolute() {
+        Some(PathBuf::from(inner))
     } else if inner.contains('/') || inner.contains('\\') {
-        vault_root.join(inner)
+        Some(vault_root.join(inner))
     } else {
-        config.media_dir.join(inner)
-    };
+        let found = media_dirs(config)
+            .into_iter()
+            .map(|dir| dir.join(inner))
+            .find(|path| path.exists());
+        Some(found.unwrap_or_else(|| config.media_dir.join(inner)))
+    }
+}
+
+/// `media_dir` plus any separate image, video, and document directories.
+pub(super) fn media_dirs(config: &Config) -> Vec<&Path> {
+    let mut dirs: Vec<&Path> = Vec::new();
+    for dir in [
+        &config.media_dir,
+        &config.image_dir,
+        &config.video_dir,
+        &config.document_dir,
+    ] {
+        if !dirs.contains(&dir.as_path()) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
 
-    if path.exists() {
-        Some(path)
+/// Where a media file with this name is saved.
+pub(super) fn media_dir_for<'a>(config: &'a Config, path: &Path) -> &'a Path {
+    if is_image_path(path) {
+        &config.image_dir
+    } else if is_video_path(path) {
+        &config.video_dir
     } else {
-        None
+        &config.document_dir
+    }
+}
+
+/// What is wrong with a referenced media file, if anything. Images are also
+/// checked for a readable header.
+pub(super) fn media_problem(path: &Path) -> Option<MediaProblem> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return Some(MediaProblem::Missing),
+    };
+    if metadata.len() == 0 {
+        return Some(MediaProblem::Empty);
+    }
+    let is_image = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| matches!(ext.to_ascii_lowercase().as_str(), "jpg" | "jpeg" | "png"))
+        .unwrap_or(false);
+    if is_image {
+        if let Err(err) = image::image_dimensions(path) {
+            return Some(MediaProblem::Unreadable(err.to_string()));
+        }
+    }
+    None
+}
+
+/// Problem files referenced by `entries`, with the first line of the entry
+/// that embeds each one.
+pub(super) fn find_media_problems(
+    entries: &[EntryBlock],
+    config: &Config,
+) -> Vec<(PathBuf, MediaProblem, String)> {
+    let mut problems = Vec::new();
+    for entry in entries {
+        for path in embedded_target_paths(&entry.lines, config) {
+            if let Some(problem) = media_problem(&path) {
+                let first = entry.display_lines().into_iter().next().unwrap_or_default();
+                problems.push((path, problem, first));
+            }
+        }
+    }
+    problems
+}
+
+/// Lists the non-blank preamble lines of each file. Preamble lines sit before
+/// the first item, so list views never show them.
+pub(super) fn build_orphans_text(files: &[(&str, Vec<String>)]) -> String {
+    let mut text = String::new();
+    for (name, preamble) in files {
+        let lines: Vec<&String> = preamble.iter().filter(|l| !l.trim().is_empty()).collect();
+        if lines.is_empty() {
+            continue;
+        }
+        text.push_str(&format!(
+            "{} ({} lines before the first item)\n",
+            name,
+            lines.len()
+        ));
+        for line in lines {
+            text.push_str(&format!("  {}\n", line));
+        }
+        text.push('\n');
+    }
+    if text.is_empty() {
+        return "Nothing before the first item.".to_string();
+    }
+    text.trim_end().to_string()
+}
+
+pub(super) fn build_verify_media_text(problems: &[(PathBuf, MediaProblem, String)]) -> String {
+    if problems.is_empty() {
+        return "All referenced media looks fine.".to_string();
+    }
+    let mut text = format!("Media problems ({})\n\n", problems.len());
+    for (path, problem, first) in problems {
+        let problem = match problem {
+            MediaProblem::Missing => "missing".to_string(),
+            MediaProblem::Empty => "empty".to_string(),
+            MediaProblem::Unreadable(err) => format!("unreadable: {}", err),
+        };
+        text.push_str(&format!(
+            "{} ({})\n   in: {}\n",
+            resource_file_label(path),
+            problem,
+            first
+        ));
+    }
+    text.trim_end().to_string()
+}
+
+pub(super) fn trash_media_dir(config: &Config) -> PathBuf {
+    config.data_dir.join("trash").join("media")
+}
+
+/// Where pruned media goes. Pruning needs both `prune_media` and a trash, so
+/// a pruned file can always be brought back. A name already in the trash
+/// gets a `unique_filename` suffix rather than being overwritten.
+pub(super) fn media_trash_destination(path: &Path, config: &Config) -> Option<PathBuf> {
+    if !config.prune_media
+        || config.trash_path.is_none()
+        || !media_dirs(config).iter().any(|dir| path.starts_with(dir))
+    {
+        return None;
     }
+    let name = path.file_name()?.to_str()?;
+    let dir = trash_media_dir(config);
+    let name = unique_filename(&dir, name);
+    Some(dir.join(name))
+}
+
+/// The trashed copy of media named `name`: the file itself, or else the first
+/// copy that was renamed on the way in because the name was taken.
+fn trashed_media_path(config: &Config, name: &str) -> Result<Option<PathBuf>> {
+    let dir = trash_media_dir(config);
+    if dir.join(name).exists() {
+        return Ok(Some(dir.join(name)));
+    }
+    Ok(list_trashed_media(config)?
+        .into_iter()
+        .find(|trashed| strip_unique_suffix(trashed).as_deref() == Some(name))
+        .map(|trashed| dir.join(trashed)))
+}
+
+/// Moves media embedded in a deleted entry to the trash, unless an entry
+/// outside the trash still references it.
+pub(super) fn prune_entry_media_sync(config: &Config, entry_block: &str) -> Result<()> {
+    if !config.prune_media || config.trash_path.is_none() {
+        return Ok(());
+    }
+    let entry = EntryBlock::from_block(entry_block);
+    let paths = extract_embedded_paths(&entry.lines, config);
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    // The deleted entry is already in the trash, so only live entries count.
+    let still_referenced = live_media_paths(config)?;
+
+    for path in paths {
+        if still_referenced.contains(&path) {
+            continue;
+        }
+        if let Some(dest) = media_trash_destination(&path, config) {
+            if let Some(dir) = dest.parent() {
+                fs::create_dir_all(dir).with_context(|| format!("create dir {}", dir.display()))?;
+            }
+            fs::rename(&path, &dest)
+                .with_context(|| format!("move media {} to trash", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves media pruned with an entry back to where the entry embeds it, so
+/// restoring the entry from the trash (or undoing its delete) restores both.
+pub(super) fn restore_entry_media_sync(config: &Config, entry_block: &str) -> Result<usize> {
+    let entry = EntryBlock::from_block(entry_block);
+    let mut restored = 0;
+    for path in embedded_target_paths(&entry.lines, config) {
+        if path.exists() {
+            continue;
+        }
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some(source) = trashed_media_path(config, name)? else {
+            continue;
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("create dir {}", dir.display()))?;
+        }
+        fs::rename(&source, &path).with_context(|| format!("restore media {}", path.display()))?;
+        restored += 1;
+    }
+    Ok(restored)
+}
+
+/// Media embedded by any entry outside the trash: Read Later, Finished,
+/// resource files and the yearly Finished archives.
+pub(super) fn live_media_paths(config: &Config) -> Result<HashSet<PathBuf>> {
+    let mut sources = vec![config.read_later_path.clone(), config.finished_path.clone()];
+    sources.extend(list_resource_files(&config.resources_path)?);
+    sources.extend(list_archive_files(archive_dir(config))?);
+    let mut referenced = HashSet::new();
+    for source in sources {
+        for entry in read_entries(&source)?.1 {
+            referenced.extend(extract_embedded_paths(&entry.lines, config));
+        }
+    }
+    Ok(referenced)
+}
+
+/// Media embedded by any entry at all, trashed ones included.
+pub(super) fn referenced_media_paths(config: &Config) -> Result<HashSet<PathBuf>> {
+    let mut referenced = live_media_paths(config)?;
+    if let Some(trash_path) = config.trash_path.as_ref() {
+        for entry in read_entries(trash_path)?.1 {
+            referenced.extend(extract_embedded_paths(&entry.lines, config));
+        }
+    }
+    Ok(referenced)
+}
+
+pub(super) fn is_media_sweep_eligible(
+    path: &Path,
+    modified: SystemTime,
+    now: SystemTime,
+    max_age_days: u64,
+    referenced: &HashSet<PathBuf>,
+) -> bool {
+    if referenced.contains(path) {
+        return false;
+    }
+    let max_age = Duration::from_secs(max_age_days * 24 * 60 * 60);
+    now.duration_since(modified)
+        .map(|age| age > max_age)
+        .unwrap_or(false)
+}
+
+pub(super) fn sweep_old_media_sync(config: &Config, max_age_days: u64) -> Result<usize> {
+    let referenced = referenced_media_paths(config)?;
+    let now = SystemTime::now();
+    let mut removed = 0;
+    for media_dir in media_dirs(config) {
+        if !media_dir.exists() {
+            continue;
+        }
+        let dir =
+            fs::read_dir(media_dir).with_context(|| format!("read dir {}", media_dir.display()))?;
+        for entry in dir {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let path = entry.path();
+            if is_media_sweep_eligible(&path, metadata.modified()?, now, max_age_days, &referenced)
+            {
+                fs::remove_file(&path)
+                    .with_context(|| format!("remove media {}", path.display()))?;
+                removed += 1;
+            }
+        }
+    }
+    Ok(removed)
+}
+
+pub(super) fn start_media_sweeper(state: std::sync::Arc<AppState>, max_age_days: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(MEDIA_SWEEP_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let _guard = state.write_lock.lock().await;
+            if let Err(err) = sweep_old_media_sync(&state.config, max_age_days) {
+                error!("media sweep failed: {:#}", err);
+            }
+        }
+    });
+}
+
+/// Ids of sessions with no interaction for more than `ttl` seconds.
+pub(super) fn stale_session_ids(
+    sessions: &HashMap<String, ListSession>,
+    now: u64,
+    ttl: u64,
+) -> Vec<String> {
+    sessions
+        .iter()
+        .filter(|(_, session)| now.saturating_sub(session.last_activity) > ttl)
+        .map(|(id, _)| id.clone())
+        .collect()
+}
+
+pub(super) fn start_session_reaper(bot: Bot, state: std::sync::Arc<AppState>, ttl: u64) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(SESSION_SWEEP_INTERVAL_SECS.min(ttl)));
+        loop {
+            interval.tick().await;
+            let stale: Vec<ListSession> = {
+                let mut sessions = state.sessions.lock().await;
+                stale_session_ids(&sessions, now_ts(), ttl)
+                    .iter()
+                    .filter_map(|id| sessions.remove(id))
+                    .collect()
+            };
+            for session in stale {
+                let chat_id = ChatId(session.chat_id);
+                delete_embedded_media_messages(&bot, chat_id, &session.sent_media_message_ids)
+                    .await;
+                if let Some(message_id) = session.message_id {
+                    let _ = bot.delete_message(chat_id, message_id).await;
+                }
+                let mut active = state.active_sessions.lock().await;
+                if active.get(&session.chat_id) == Some(&session.id) {
+                    active.remove(&session.chat_id);
+                }
+            }
+            let stale: Vec<DuplicatesSession> = {
+                let mut sessions = state.duplicate_sessions.lock().await;
+                let now = now_ts();
+                let ids: Vec<String> = sessions
+                    .iter()
+                    .filter(|(_, session)| now.saturating_sub(session.last_activity) > ttl)
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                ids.iter().filter_map(|id| sessions.remove(id)).collect()
+            };
+            for session in stale {
+                let _ = bot
+                    .delete_message(ChatId(session.chat_id), session.message_id)
+                    .await;
+            }
+        }
+    });
+}
+
+pub(super) fn start_digest_scheduler(bot: Bot, state: std::sync::Arc<AppState>, at: NaiveTime) {
+    tokio::spawn(async move {
+        loop {
+            // Recomputed every round so the schedule follows the local clock
+            // across midnight and DST changes.
+            let now = Local::now().naive_local();
+            let wait = (next_digest_at(now, at) - now)
+                .to_std()
+                .unwrap_or(Duration::from_secs(60));
+            tokio::time::sleep(wait).await;
+            if let Err(err) = send_digest(&bot, &state).await {
+                error!("scheduled digest failed: {:#}", err);
+            }
+        }
+    });
+}
+
+async fn send_digest(bot: &Bot, state: &std::sync::Arc<AppState>) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let peeked = state.peeked.lock().await.clone();
+    if let Some(text) = build_digest_text(&entries, &peeked) {
+        let chat_id = chat_id_from_user_id(state.config.user_id);
+        send_message_with_delete_button(bot, &state.config, chat_id, text).await?;
+    }
+    Ok(())
+}
+
+pub(super) fn restore_media_sync(config: &Config, filename: &str) -> Result<PathBuf> {
+    let filename = filename.trim();
+    if filename.is_empty()
+        || filename.contains('/')
+        || filename.contains('\\')
+        || filename == "."
+        || filename == ".."
+    {
+        return Err(anyhow!("invalid filename"));
+    }
+    let source = trash_media_dir(config).join(filename);
+    if !source.exists() {
+        return Err(anyhow!("not in trash"));
+    }
+    let name = restored_media_name(config, filename)?;
+    let media_dir = media_dir_for(config, Path::new(&name));
+    let dest = media_dir.join(&name);
+    if dest.exists() {
+        return Err(anyhow!("already exists in media"));
+    }
+    fs::create_dir_all(media_dir)
+        .with_context(|| format!("create media dir {}", media_dir.display()))?;
+    fs::rename(&source, &dest).with_context(|| format!("restore media {}", filename))?;
+    Ok(dest)
+}
+
+/// The name a trashed media file goes back under. A file renamed on its way
+/// into the trash gets its original name back when an entry embeds that name
+/// and it is free in the media dir.
+fn restored_media_name(config: &Config, trashed: &str) -> Result<String> {
+    if let Some(original) = strip_unique_suffix(trashed) {
+        let free = !media_dir_for(config, Path::new(&original))
+            .join(&original)
+            .exists();
+        if free && embedded_media_names(config)?.contains(&original) {
+            return Ok(original);
+        }
+    }
+    Ok(trashed.to_string())
+}
+
+/// File names of media embedded by any entry, trashed ones included, whether
+/// or not the files exist.
+fn embedded_media_names(config: &Config) -> Result<HashSet<String>> {
+    let mut sources = vec![config.read_later_path.clone(), config.finished_path.clone()];
+    sources.extend(list_resource_files(&config.resources_path)?);
+    sources.extend(list_archive_files(archive_dir(config))?);
+    sources.extend(config.trash_path.clone());
+    let mut names = HashSet::new();
+    for source in sources {
+        for entry in read_entries(&source)?.1 {
+            names.extend(
+                embedded_target_paths(&entry.lines, config)
+                    .iter()
+                    .filter_map(|path| path.file_name()?.to_str().map(str::to_string)),
+            );
+        }
+    }
+    Ok(names)
+}
+
+pub(super) fn list_trashed_media(config: &Config) -> Result<Vec<String>> {
+    let dir = trash_media_dir(config);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut names = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("read dir {}", dir.display()))? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            names.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
 }
 
 pub(super) fn is_image_path(path: &Path) -> bool {
@@ -1499,6 +5029,16 @@ pub(super) fn is_image_path(path: &Path) -> bool {
     }
 }
 
+pub(super) fn is_audio_path(path: &Path) -> bool {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => matches!(
+            ext.to_ascii_lowercase().as_str(),
+            "m4a" | "mp3" | "aac" | "opus" | "ogg" | "flac" | "wav"
+        ),
+        None => false,
+    }
+}
+
 pub(super) fn is_video_path(path: &Path) -> bool {
     match path.extension().and_then(|ext| ext.to_str()) {
         Some(ext) => matches!(
@@ -1509,15 +5049,34 @@ pub(super) fn is_video_path(path: &Path) -> bool {
     }
 }
 
-pub(super) fn parse_command(text: &str) -> Option<&str> {
+pub(super) fn parse_command<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
     let first = text.split_whitespace().next()?;
-    if !first.starts_with('/') {
-        return None;
-    }
-    let cmd = first.trim_start_matches('/');
+    let cmd = first
+        .strip_prefix('/')
+        .or_else(|| first.strip_prefix(prefix))?;
     Some(cmd.split('@').next().unwrap_or(cmd))
 }
 
+pub(super) fn build_finished_pick_view(
+    pick_id: &str,
+    entry: &EntryBlock,
+    config: &Config,
+) -> (String, InlineKeyboardMarkup) {
+    let lines = format_display_lines(&entry.display_lines(), config);
+    let text = format!("From Finished:\n\n{}", lines.join("\n"));
+    let rows = vec![
+        vec![InlineKeyboardButton::callback(
+            "Move back to Read Later",
+            format!("rf:{}:back", pick_id),
+        )],
+        vec![
+            InlineKeyboardButton::callback("Another", format!("rf:{}:another", pick_id)),
+            InlineKeyboardButton::callback("Close", format!("rf:{}:close", pick_id)),
+        ],
+    ];
+    (text, InlineKeyboardMarkup::new(rows))
+}
+
 pub(super) fn quick_select_index(entries_len: usize, mode: QuickSelectMode) -> Option<usize> {
     if entries_len == 0 {
         return None;
@@ -1563,9 +5122,10 @@ pub(super) fn start_retry_loop(state: std::sync::Arc<AppState>, interval_secs: u
 }
 
 pub(super) async fn process_queue(state: std::sync::Arc<AppState>) -> Result<()> {
-    let pending = {
+    let (pending, generation) = {
         let mut queue = state.queue.lock().await;
-        std::mem::take(&mut *queue)
+        let generation = state.queue_generation.load(Ordering::Acquire);
+        (std::mem::take(&mut *queue), generation)
     };
 
     if pending.is_empty() {
@@ -1574,6 +5134,10 @@ pub(super) async fn process_queue(state: std::sync::Arc<AppState>) -> Result<()>
 
     let mut remaining = Vec::new();
     for op in pending {
+        if state.queue_generation.load(Ordering::Acquire) != generation {
+            // The queue was cleared or restored meanwhile; drop what's left.
+            return Ok(());
+        }
         match apply_op(&state, &op).await {
             Ok(_) => {}
             Err(err) => {
@@ -1584,6 +5148,9 @@ pub(super) async fn process_queue(state: std::sync::Arc<AppState>) -> Result<()>
     }
 
     let mut queue = state.queue.lock().await;
+    if state.queue_generation.load(Ordering::Acquire) != generation {
+        return Ok(());
+    }
     if !queue.is_empty() {
         remaining.extend(queue.drain(..));
     }
@@ -21,27 +21,7 @@ pub(super) fn run_push(sync: &SyncConfig) -> Result<PushOutcome> {
         ));
     }
 
-    let token = read_token_file(&sync.token_file)?;
-
-    let remotes = git_remote_names(&sync.repo_path)?;
-    let remote = if remotes.iter().any(|name| name == "origin") {
-        "origin".to_string()
-    } else {
-        remotes
-            .first()
-            .cloned()
-            .ok_or_else(|| anyhow!("Git remote not configured."))?
-    };
-    let remote_url = git_remote_url(&sync.repo_path, &remote)?;
-    if !remote_url.starts_with("https://") {
-        return Err(anyhow!(
-            "Sync requires HTTPS remote for PAT auth. Remote is {}",
-            remote_url
-        ));
-    }
-
-    let username =
-        extract_https_username(&remote_url).unwrap_or_else(|| "x-access-token".to_string());
+    let (remote, auth) = resolve_sync_remote(sync)?;
 
     let status_output = run_git(&sync.repo_path, &["status", "--porcelain"], Vec::new())?;
     if !status_output.status.success() {
@@ -57,11 +37,9 @@ pub(super) fn run_push(sync: &SyncConfig) -> Result<PushOutcome> {
     }
 
     let commit_message = sync_commit_message();
-    let commit_output = run_git(
-        &sync.repo_path,
-        &["commit", "-m", &commit_message],
-        Vec::new(),
-    )?;
+    let commit_args = sync_commit_args(sync, &commit_message);
+    let commit_args: Vec<&str> = commit_args.iter().map(String::as_str).collect();
+    let commit_output = run_git(&sync.repo_path, &commit_args, Vec::new())?;
     if !commit_output.status.success() {
         if is_nothing_to_commit(&commit_output) {
             return Ok(PushOutcome::NoChanges);
@@ -74,18 +52,10 @@ pub(super) fn run_push(sync: &SyncConfig) -> Result<PushOutcome> {
         return Err(anyhow!("Sync failed: detached HEAD."));
     }
 
-    let askpass = create_askpass_script()?;
-    let askpass_path = askpass.to_string_lossy().to_string();
-    let push_env = vec![
-        ("GIT_TERMINAL_PROMPT", "0".to_string()),
-        ("GIT_ASKPASS", askpass_path),
-        ("GIT_SYNC_USERNAME", username),
-        ("GIT_SYNC_PAT", token),
-    ];
     let push_output = run_git(
         &sync.repo_path,
         &["push", &remote, &format!("HEAD:refs/heads/{}", branch)],
-        push_env,
+        auth.env.clone(),
     )?;
     if !push_output.status.success() {
         return Err(anyhow!(format_git_error("git push", &push_output)));
@@ -115,27 +85,7 @@ pub(super) fn run_pull(sync: &SyncConfig, mode: PullMode) -> Result<PullOutcome>
         ));
     }
 
-    let token = read_token_file(&sync.token_file)?;
-
-    let remotes = git_remote_names(&sync.repo_path)?;
-    let remote = if remotes.iter().any(|name| name == "origin") {
-        "origin".to_string()
-    } else {
-        remotes
-            .first()
-            .cloned()
-            .ok_or_else(|| anyhow!("Git remote not configured."))?
-    };
-    let remote_url = git_remote_url(&sync.repo_path, &remote)?;
-    if !remote_url.starts_with("https://") {
-        return Err(anyhow!(
-            "Sync requires HTTPS remote for PAT auth. Remote is {}",
-            remote_url
-        ));
-    }
-
-    let username =
-        extract_https_username(&remote_url).unwrap_or_else(|| "x-access-token".to_string());
+    let (remote, auth) = resolve_sync_remote(sync)?;
 
     let status_output = run_git(&sync.repo_path, &["status", "--porcelain"], Vec::new())?;
     if !status_output.status.success() {
@@ -152,15 +102,6 @@ pub(super) fn run_pull(sync: &SyncConfig, mode: PullMode) -> Result<PullOutcome>
         return Err(anyhow!("Sync failed: detached HEAD."));
     }
 
-    let askpass = create_askpass_script()?;
-    let askpass_path = askpass.to_string_lossy().to_string();
-    let pull_env = vec![
-        ("GIT_TERMINAL_PROMPT", "0".to_string()),
-        ("GIT_ASKPASS", askpass_path),
-        ("GIT_SYNC_USERNAME", username),
-        ("GIT_SYNC_PAT", token),
-    ];
-
     let pull_args: Vec<String> = match mode {
         PullMode::FastForward => vec!["pull".to_string(), "--ff-only".to_string(), remote, branch],
         PullMode::Theirs => vec![
@@ -173,7 +114,7 @@ pub(super) fn run_pull(sync: &SyncConfig, mode: PullMode) -> Result<PullOutcome>
         ],
     };
     let pull_args_ref: Vec<&str> = pull_args.iter().map(|arg| arg.as_str()).collect();
-    let pull_output = run_git(&sync.repo_path, &pull_args_ref, pull_env)?;
+    let pull_output = run_git(&sync.repo_path, &pull_args_ref, auth.env.clone())?;
     if !pull_output.status.success() {
         return Err(anyhow!(format_git_error("git pull", &pull_output)));
     }
@@ -185,7 +126,7 @@ pub(super) fn run_pull(sync: &SyncConfig, mode: PullMode) -> Result<PullOutcome>
     }
 }
 
-pub(super) fn run_sync(sync: &SyncConfig) -> Result<SyncOutcome> {
+pub(super) fn run_sync_status(sync: &SyncConfig) -> Result<SyncStatus> {
     ensure_git_available()?;
     if !sync.repo_path.exists() {
         return Err(anyhow!(
@@ -206,27 +147,81 @@ pub(super) fn run_sync(sync: &SyncConfig) -> Result<SyncOutcome> {
         ));
     }
 
-    let token = read_token_file(&sync.token_file)?;
+    let (remote, auth) = resolve_sync_remote(sync)?;
 
-    let remotes = git_remote_names(&sync.repo_path)?;
-    let remote = if remotes.iter().any(|name| name == "origin") {
-        "origin".to_string()
-    } else {
-        remotes
-            .first()
-            .cloned()
-            .ok_or_else(|| anyhow!("Git remote not configured."))?
-    };
-    let remote_url = git_remote_url(&sync.repo_path, &remote)?;
-    if !remote_url.starts_with("https://") {
+    let fetch_output = run_git(&sync.repo_path, &["fetch", &remote], auth.env.clone())?;
+    if !fetch_output.status.success() {
+        return Err(anyhow!(format_git_error("git fetch", &fetch_output)));
+    }
+
+    let count_output = run_git(
+        &sync.repo_path,
+        &["rev-list", "--left-right", "--count", "@{u}...HEAD"],
+        Vec::new(),
+    )?;
+    if !count_output.status.success() {
+        return Err(anyhow!(format_git_error("git rev-list", &count_output)));
+    }
+    let (ahead, behind) = parse_ahead_behind(&count_output.stdout)
+        .ok_or_else(|| anyhow!("Unexpected git rev-list output: {}", count_output.stdout))?;
+
+    let status_output = run_git(&sync.repo_path, &["status", "--porcelain"], Vec::new())?;
+    if !status_output.status.success() {
+        return Err(anyhow!(format_git_error("git status", &status_output)));
+    }
+
+    Ok(SyncStatus {
+        ahead,
+        behind,
+        dirty: !status_output.stdout.trim().is_empty(),
+    })
+}
+
+/// Reads `git rev-list --left-right --count @{u}...HEAD` output, where the
+/// left count is commits only upstream. Returns `(ahead, behind)`.
+pub(super) fn parse_ahead_behind(output: &str) -> Option<(usize, usize)> {
+    let mut counts = output
+        .split_whitespace()
+        .map(|count| count.parse::<usize>());
+    let behind = counts.next()?.ok()?;
+    let ahead = counts.next()?.ok()?;
+    if counts.next().is_some() {
+        return None;
+    }
+    Some((ahead, behind))
+}
+
+pub(super) fn build_sync_status_text(status: &SyncStatus) -> String {
+    format!(
+        "{} ahead, {} behind, working tree {}",
+        status.ahead,
+        status.behind,
+        if status.dirty { "dirty" } else { "clean" }
+    )
+}
+
+pub(super) fn run_sync(sync: &SyncConfig) -> Result<SyncOutcome> {
+    ensure_git_available()?;
+    if !sync.repo_path.exists() {
+        return Err(anyhow!(
+            "Sync repo path not found: {}",
+            sync.repo_path.display()
+        ));
+    }
+
+    let repo_check = run_git(
+        &sync.repo_path,
+        &["rev-parse", "--is-inside-work-tree"],
+        Vec::new(),
+    )?;
+    if !repo_check.status.success() || repo_check.stdout.trim() != "true" {
         return Err(anyhow!(
-            "Sync requires HTTPS remote for PAT auth. Remote is {}",
-            remote_url
+            "Sync repo path not found or not a git repository: {}",
+            sync.repo_path.display()
         ));
     }
 
-    let username =
-        extract_https_username(&remote_url).unwrap_or_else(|| "x-access-token".to_string());
+    let (remote, auth) = resolve_sync_remote(sync)?;
 
     let status_output = run_git(&sync.repo_path, &["status", "--porcelain"], Vec::new())?;
     if !status_output.status.success() {
@@ -239,11 +234,9 @@ pub(super) fn run_sync(sync: &SyncConfig) -> Result<SyncOutcome> {
     }
 
     let commit_message = sync_commit_message();
-    let commit_output = run_git(
-        &sync.repo_path,
-        &["commit", "-m", &commit_message],
-        Vec::new(),
-    )?;
+    let commit_args = sync_commit_args(sync, &commit_message);
+    let commit_args: Vec<&str> = commit_args.iter().map(String::as_str).collect();
+    let commit_output = run_git(&sync.repo_path, &commit_args, Vec::new())?;
     let did_commit = if commit_output.status.success() {
         true
     } else if is_nothing_to_commit(&commit_output) {
@@ -257,19 +250,10 @@ pub(super) fn run_sync(sync: &SyncConfig) -> Result<SyncOutcome> {
         return Err(anyhow!("Sync failed: detached HEAD."));
     }
 
-    let askpass = create_askpass_script()?;
-    let askpass_path = askpass.to_string_lossy().to_string();
-    let auth_env = vec![
-        ("GIT_TERMINAL_PROMPT", "0".to_string()),
-        ("GIT_ASKPASS", askpass_path),
-        ("GIT_SYNC_USERNAME", username),
-        ("GIT_SYNC_PAT", token),
-    ];
-
     let pull_output = run_git(
         &sync.repo_path,
         &["pull", "--ff-only", &remote, &branch],
-        auth_env.clone(),
+        auth.env.clone(),
     )?;
     if !pull_output.status.success() {
         return Err(anyhow!(format_git_error("git pull", &pull_output)));
@@ -279,7 +263,7 @@ pub(super) fn run_sync(sync: &SyncConfig) -> Result<SyncOutcome> {
     let push_output = run_git(
         &sync.repo_path,
         &["push", &remote, &format!("HEAD:refs/heads/{}", branch)],
-        auth_env,
+        auth.env.clone(),
     )?;
     if !push_output.status.success() {
         return Err(anyhow!(format_git_error("git push", &push_output)));
@@ -607,6 +591,104 @@ pub(super) fn git_current_branch(repo_path: &Path) -> Result<String> {
     Ok(output.stdout.trim().to_string())
 }
 
+pub(super) struct GitAuth {
+    pub(super) env: Vec<(&'static str, String)>,
+    // Keeps the askpass script alive for as long as git may invoke it.
+    _askpass: Option<TempPath>,
+}
+
+pub(super) fn resolve_sync_remote(sync: &SyncConfig) -> Result<(String, GitAuth)> {
+    let remotes = git_remote_names(&sync.repo_path)?;
+    let remote = if remotes.iter().any(|name| name == "origin") {
+        "origin".to_string()
+    } else {
+        remotes
+            .first()
+            .cloned()
+            .ok_or_else(|| anyhow!("Git remote not configured."))?
+    };
+    let remote_url = git_remote_url(&sync.repo_path, &remote)?;
+    let auth = match (remote_kind(&remote_url), &sync.auth) {
+        (Some(RemoteKind::Https), SyncAuth::Ssh { .. }) => {
+            return Err(anyhow!(
+                "settings.sync.auth is ssh but the remote is HTTPS: {}",
+                remote_url
+            ));
+        }
+        (Some(RemoteKind::Https), SyncAuth::Pat) => {
+            let token_file = sync
+                .token_file
+                .as_deref()
+                .ok_or_else(|| anyhow!("Sync requires PAT in settings.sync.token_file."))?;
+            let token = read_token_file(token_file)?;
+            let username =
+                extract_https_username(&remote_url).unwrap_or_else(|| "x-access-token".to_string());
+            let askpass = create_askpass_script()?;
+            let askpass_path = askpass.to_string_lossy().to_string();
+            GitAuth {
+                env: vec![
+                    ("GIT_TERMINAL_PROMPT", "0".to_string()),
+                    ("GIT_ASKPASS", askpass_path),
+                    ("GIT_SYNC_USERNAME", username),
+                    ("GIT_SYNC_PAT", token),
+                ],
+                _askpass: Some(askpass),
+            }
+        }
+        (Some(RemoteKind::Ssh), auth) => {
+            let key_file = match auth {
+                SyncAuth::Ssh { key_file } => key_file.as_deref(),
+                SyncAuth::Pat => None,
+            };
+            GitAuth {
+                env: ssh_auth_env(key_file),
+                _askpass: None,
+            }
+        }
+        (None, _) => {
+            return Err(anyhow!(
+                "Sync requires an HTTPS or SSH remote. Remote is {}",
+                remote_url
+            ));
+        }
+    };
+    Ok((remote, auth))
+}
+
+/// Environment for SSH remotes. With a key file, git's ssh uses only that key.
+pub(super) fn ssh_auth_env(key_file: Option<&Path>) -> Vec<(&'static str, String)> {
+    let mut env = vec![("GIT_TERMINAL_PROMPT", "0".to_string())];
+    if let Some(key_file) = key_file {
+        let quoted = key_file.to_string_lossy().replace('\'', "'\\''");
+        env.push((
+            "GIT_SSH_COMMAND",
+            format!("ssh -i '{}' -o IdentitiesOnly=yes", quoted),
+        ));
+    }
+    env
+}
+
+pub(super) fn remote_kind(remote_url: &str) -> Option<RemoteKind> {
+    if remote_url.starts_with("https://") {
+        return Some(RemoteKind::Https);
+    }
+    if remote_url.starts_with("ssh://") {
+        return Some(RemoteKind::Ssh);
+    }
+    // scp-like syntax: [user@]host:path, without a scheme.
+    match remote_url.split_once(':') {
+        Some((host, path))
+            if !host.is_empty()
+                && !host.contains('/')
+                && !path.is_empty()
+                && !path.starts_with("//") =>
+        {
+            Some(RemoteKind::Ssh)
+        }
+        _ => None,
+    }
+}
+
 pub(super) fn read_token_file(path: &Path) -> Result<String> {
     let token = match fs::read_to_string(path) {
         Ok(token) => token,
@@ -672,6 +754,26 @@ pub(super) fn sync_commit_message() -> String {
     format!("Bot sync {}", Local::now().format("%Y-%m-%d %H:%M:%S"))
 }
 
+/// Arguments for the sync commit, overriding the git identity with the
+/// configured author where set.
+pub(super) fn sync_commit_args(sync: &SyncConfig, message: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(name) = sync.author_name.as_deref().filter(|v| !v.trim().is_empty()) {
+        args.push("-c".to_string());
+        args.push(format!("user.name={}", name.trim()));
+    }
+    if let Some(email) = sync
+        .author_email
+        .as_deref()
+        .filter(|v| !v.trim().is_empty())
+    {
+        args.push("-c".to_string());
+        args.push(format!("user.email={}", email.trim()));
+    }
+    args.extend(["commit".to_string(), "-m".to_string(), message.to_string()]);
+    args
+}
+
 pub(super) fn create_askpass_script() -> Result<TempPath> {
     let mut file = NamedTempFile::new().context("create askpass script")?;
     file.write_all(
@@ -692,11 +794,61 @@ pub(super) fn split_items(text: &str) -> Vec<String> {
         .collect()
 }
 
+pub(super) fn link_check_client(timeout_ms: u64) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_millis(timeout_ms))
+        .build()
+        .context("build link check client")
+}
+
+pub(super) async fn check_link(client: reqwest::Client, link: String) -> LinkHealth {
+    let mut response = client.head(&link).send().await;
+    if let Ok(head) = &response {
+        // Some servers refuse HEAD outright, so confirm with a GET.
+        if matches!(head.status().as_u16(), 405 | 501) {
+            response = client.get(&link).send().await;
+        }
+    }
+    match response {
+        Ok(response) if response.status().as_u16() < 400 => LinkHealth::Ok,
+        Ok(response) => LinkHealth::Status(response.status().as_u16()),
+        Err(err) if err.is_timeout() => LinkHealth::Failed("timed out".to_string()),
+        Err(err) => LinkHealth::Failed(err.to_string()),
+    }
+}
+
+/// Fetches the page title for `link`, or `None` if the page can't be read or
+/// isn't HTML. Only the first [`TITLE_FETCH_MAX_BYTES`] of the body are read.
+pub(super) async fn fetch_page_title(link: &str) -> Option<String> {
+    let client = link_check_client(TITLE_FETCH_TIMEOUT_MS).ok()?;
+    let mut response = client.get(link).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())?;
+    if !is_html_content_type(content_type) {
+        return None;
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.ok()? {
+        body.extend_from_slice(&chunk);
+        if body.len() >= TITLE_FETCH_MAX_BYTES {
+            body.truncate(TITLE_FETCH_MAX_BYTES);
+            break;
+        }
+    }
+    extract_html_title(&String::from_utf8_lossy(&body))
+}
+
 pub(super) async fn download_and_send_link(
     bot: &Bot,
     chat_id: ChatId,
     link: &str,
     format_selector: &str,
+    max_send_bytes: u64,
 ) -> Result<()> {
     let temp_dir = TempDir::new().context("create download temp dir")?;
     let target_dir = temp_dir.path().to_path_buf();
@@ -707,7 +859,70 @@ pub(super) async fn download_and_send_link(
     })
     .await
     .context("yt-dlp task failed")??;
+    let size = fs::metadata(&path)
+        .with_context(|| format!("stat {}", path.display()))?
+        .len();
+    if let Some(message) = send_size_error(size, max_send_bytes) {
+        return Err(anyhow!(message));
+    }
+    send_downloaded_file(bot, chat_id, path).await
+}
+
+/// Explains why a download is too big to upload, or `None` if it fits.
+pub(super) fn send_size_error(size: u64, max_send_bytes: u64) -> Option<String> {
+    (size > max_send_bytes).then(|| {
+        format!(
+            "File is {}, over the {} upload limit. Use Save instead.",
+            human_size(size),
+            human_size(max_send_bytes)
+        )
+    })
+}
+
+/// Sends the link's English subtitles as a document. Returns `false` when the
+/// video has none, since yt-dlp then succeeds without writing a file.
+pub(super) async fn download_and_send_subtitles(
+    bot: &Bot,
+    chat_id: ChatId,
+    link: &str,
+) -> Result<bool> {
+    let temp_dir = TempDir::new().context("create download temp dir")?;
+    let target_dir = temp_dir.path().to_path_buf();
+    let link = link.to_string();
+    let path = tokio::task::spawn_blocking(move || run_ytdlp_subtitles(&target_dir, &link))
+        .await
+        .context("yt-dlp task failed")??;
+    let Some(path) = path else {
+        return Ok(false);
+    };
     bot.send_document(chat_id, InputFile::file(path)).await?;
+    Ok(true)
+}
+
+pub(super) fn download_send_kind(path: &Path) -> DownloadSendKind {
+    if is_video_path(path) {
+        DownloadSendKind::Video
+    } else if is_audio_path(path) {
+        DownloadSendKind::Audio
+    } else {
+        DownloadSendKind::Document
+    }
+}
+
+pub(super) async fn send_downloaded_file(bot: &Bot, chat_id: ChatId, path: PathBuf) -> Result<()> {
+    match download_send_kind(&path) {
+        DownloadSendKind::Video => {
+            bot.send_video(chat_id, InputFile::file(path))
+                .supports_streaming(true)
+                .await?;
+        }
+        DownloadSendKind::Audio => {
+            bot.send_audio(chat_id, InputFile::file(path)).await?;
+        }
+        DownloadSendKind::Document => {
+            bot.send_document(chat_id, InputFile::file(path)).await?;
+        }
+    }
     Ok(())
 }
 
@@ -729,7 +944,150 @@ pub(super) async fn download_and_save_link(
     if !path.exists() {
         return Err(anyhow!("Download completed but file is missing."));
     }
-    Ok(path)
+    move_to_type_dir(&state.config, path)
+}
+
+/// yt-dlp picks the extension, so downloads land in `media_dir` and are moved
+/// to the image, video, or document folder once their type is known.
+pub(super) fn move_to_type_dir(config: &Config, path: PathBuf) -> Result<PathBuf> {
+    let type_dir = media_dir_for(config, &path);
+    if path.parent() == Some(type_dir) {
+        return Ok(path);
+    }
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(path);
+    };
+    fs::create_dir_all(type_dir)
+        .with_context(|| format!("create media dir {}", type_dir.display()))?;
+    let dest = type_dir.join(unique_filename(type_dir, name));
+    fs::rename(&path, &dest).with_context(|| format!("move download to {}", dest.display()))?;
+    Ok(dest)
+}
+
+/// Downloads a playlist for `action` and returns a summary for the chat.
+/// Items over `max_send_bytes` are kept (when saving) but not uploaded.
+pub(super) async fn download_playlist(
+    bot: &Bot,
+    state: &std::sync::Arc<AppState>,
+    chat_id: ChatId,
+    link: &str,
+    action: DownloadAction,
+) -> Result<String> {
+    let temp_dir = TempDir::new().context("create download temp dir")?;
+    let target_dir = match action {
+        DownloadAction::Send => temp_dir.path().to_path_buf(),
+        DownloadAction::Save | DownloadAction::SendAndSave => state.config.media_dir.clone(),
+    };
+    fs::create_dir_all(&target_dir)
+        .with_context(|| format!("create media dir {}", target_dir.display()))?;
+    let dir = target_dir.clone();
+    let link = link.to_string();
+    let limit = state.config.playlist_item_limit;
+    let mut paths = tokio::task::spawn_blocking(move || run_ytdlp_playlist(&dir, &link, limit))
+        .await
+        .context("yt-dlp task failed")??;
+    if !matches!(action, DownloadAction::Send) {
+        paths = paths
+            .into_iter()
+            .map(|path| move_to_type_dir(&state.config, path))
+            .collect::<Result<_>>()?;
+    }
+
+    let mut skipped = 0;
+    if !matches!(action, DownloadAction::Save) {
+        for path in &paths {
+            let size = fs::metadata(path)
+                .with_context(|| format!("stat {}", path.display()))?
+                .len();
+            if send_size_error(size, state.config.max_send_bytes).is_some() {
+                skipped += 1;
+                continue;
+            }
+            send_downloaded_file(bot, chat_id, path.clone()).await?;
+        }
+    }
+    // Playlist items usually share a type, so they share a folder too.
+    let saved_dir = paths
+        .first()
+        .and_then(|path| path.parent())
+        .unwrap_or(&target_dir);
+    Ok(playlist_summary(
+        action,
+        paths.len(),
+        skipped,
+        saved_dir,
+        state.config.max_send_bytes,
+    ))
+}
+
+pub(super) fn playlist_summary(
+    action: DownloadAction,
+    total: usize,
+    skipped: usize,
+    dir: &Path,
+    max_send_bytes: u64,
+) -> String {
+    let saved = format!("Saved {} files to {}", total, dir.display());
+    let mut sent = format!("Sent {} of {} files.", total - skipped, total);
+    if skipped > 0 {
+        sent.push_str(&format!(
+            " {} over the {} limit.",
+            skipped,
+            human_size(max_send_bytes)
+        ));
+    }
+    match action {
+        DownloadAction::Save => saved,
+        DownloadAction::Send => sent,
+        DownloadAction::SendAndSave => format!("{}. {}", saved, sent),
+    }
+}
+
+/// Saves the download and uploads it. A file over `max_send_bytes` stays
+/// saved but isn't uploaded; the size message comes back with the path.
+pub(super) async fn download_save_and_send_link(
+    bot: &Bot,
+    state: &std::sync::Arc<AppState>,
+    chat_id: ChatId,
+    link: &str,
+    format_selector: &str,
+) -> Result<(PathBuf, Option<String>)> {
+    let max_send_bytes = state.config.max_send_bytes;
+    save_then_send(
+        || download_and_save_link(state, link, format_selector),
+        |path| async move {
+            let size = fs::metadata(&path)
+                .with_context(|| format!("stat {}", path.display()))?
+                .len();
+            if send_size_error(size, max_send_bytes).is_some() {
+                // Already saved, so the upload note doesn't point at Save.
+                return Ok(Some(format!(
+                    "Not sent: {} is over the {} upload limit.",
+                    human_size(size),
+                    human_size(max_send_bytes)
+                )));
+            }
+            send_downloaded_file(bot, chat_id, path).await?;
+            Ok(None)
+        },
+    )
+    .await
+}
+
+/// Downloads once and uploads the saved file, so "Send + Save" never fetches twice.
+pub(super) async fn save_then_send<D, DFut, S, SFut, T>(
+    download: D,
+    send: S,
+) -> Result<(PathBuf, T)>
+where
+    D: FnOnce() -> DFut,
+    DFut: std::future::Future<Output = Result<PathBuf>>,
+    S: FnOnce(PathBuf) -> SFut,
+    SFut: std::future::Future<Output = Result<T>>,
+{
+    let path = download().await?;
+    let sent = send(path.clone()).await?;
+    Ok((path, sent))
 }
 
 pub(super) fn run_ytdlp_list_formats(link: &str) -> Result<Vec<DownloadQualityOption>> {
@@ -750,6 +1108,11 @@ pub(super) fn run_ytdlp_list_formats(link: &str) -> Result<Vec<DownloadQualityOp
     }];
 
     let Some(formats) = value.get("formats").and_then(|v| v.as_array()) else {
+        if offers_playlist(link, &value) {
+            options.push(playlist_option());
+        }
+        options.push(audio_m4a_option());
+        options.push(subtitles_option());
         return Ok(options);
     };
 
@@ -855,10 +1218,44 @@ pub(super) fn run_ytdlp_list_formats(link: &str) -> Result<Vec<DownloadQualityOp
             format_selector: format_id,
         });
     }
+    if offers_playlist(link, &value) {
+        options.push(playlist_option());
+    }
+    options.push(audio_m4a_option());
+    options.push(subtitles_option());
 
     Ok(options)
 }
 
+/// Audio extracted to m4a, offered for every link alongside the video formats.
+pub(super) fn audio_m4a_option() -> DownloadQualityOption {
+    DownloadQualityOption {
+        label: "Audio (m4a)".to_string(),
+        format_selector: AUDIO_M4A_FORMAT_SELECTOR.to_string(),
+    }
+}
+
+/// English subtitles (including auto-generated ones) as an `.srt` file.
+pub(super) fn subtitles_option() -> DownloadQualityOption {
+    DownloadQualityOption {
+        label: "Subtitles (en, srt)".to_string(),
+        format_selector: SUBTITLES_FORMAT_SELECTOR.to_string(),
+    }
+}
+
+/// Every item of the playlist at the best quality, up to `playlist_item_limit`.
+pub(super) fn playlist_option() -> DownloadQualityOption {
+    DownloadQualityOption {
+        label: "Whole playlist".to_string(),
+        format_selector: PLAYLIST_FORMAT_SELECTOR.to_string(),
+    }
+}
+
+/// Whether the link looks like (or yt-dlp reports it as) a playlist.
+pub(super) fn offers_playlist(link: &str, info: &serde_json::Value) -> bool {
+    info.get("_type").and_then(|v| v.as_str()) == Some("playlist") || link.contains("list=")
+}
+
 pub(super) fn human_size(bytes: u64) -> String {
     const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
     let mut value = bytes as f64;
@@ -877,14 +1274,7 @@ pub(super) fn human_size(bytes: u64) -> String {
 pub(super) fn run_ytdlp_download(target_dir: &Path, link: &str, format_selector: &str) -> Result<PathBuf> {
     let template = target_dir.join("%(title).200B-%(id)s.%(ext)s");
     let output = Command::new("yt-dlp")
-        .arg("--no-playlist")
-        .arg("-f")
-        .arg(format_selector)
-        .arg("--print")
-        .arg("after_move:filepath")
-        .arg("-o")
-        .arg(template.to_string_lossy().to_string())
-        .arg(link)
+        .args(ytdlp_download_args(&template, link, format_selector))
         .output()
         .context("run yt-dlp")?;
     if !output.status.success() {
@@ -906,6 +1296,126 @@ pub(super) fn run_ytdlp_download(target_dir: &Path, link: &str, format_selector:
     Ok(path)
 }
 
+pub(super) fn run_ytdlp_playlist(
+    target_dir: &Path,
+    link: &str,
+    limit: usize,
+) -> Result<Vec<PathBuf>> {
+    let template = target_dir.join("%(title).200B-%(id)s.%(ext)s");
+    let output = Command::new("yt-dlp")
+        .args(ytdlp_playlist_args(&template, link, limit))
+        .output()
+        .context("run yt-dlp")?;
+    if !output.status.success() {
+        return Err(anyhow!(format_ytdlp_error(&output)));
+    }
+    let paths = playlist_paths_from_output(&String::from_utf8_lossy(&output.stdout), target_dir);
+    if paths.is_empty() {
+        return Err(anyhow!("yt-dlp did not return any files"));
+    }
+    Ok(paths)
+}
+
+pub(super) fn ytdlp_playlist_args(template: &Path, link: &str, limit: usize) -> Vec<String> {
+    vec![
+        "--yes-playlist".to_string(),
+        "--playlist-items".to_string(),
+        format!("1:{}", limit),
+        "-f".to_string(),
+        "bestvideo+bestaudio/best".to_string(),
+        "--print".to_string(),
+        "after_move:filepath".to_string(),
+        "-o".to_string(),
+        template.to_string_lossy().to_string(),
+        link.to_string(),
+    ]
+}
+
+/// One `after_move:filepath` line is printed per downloaded item.
+pub(super) fn playlist_paths_from_output(stdout: &str, target_dir: &Path) -> Vec<PathBuf> {
+    stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let path = PathBuf::from(line);
+            if path.is_relative() {
+                target_dir.join(path)
+            } else {
+                path
+            }
+        })
+        .filter(|path| path.exists())
+        .collect()
+}
+
+pub(super) fn run_ytdlp_subtitles(target_dir: &Path, link: &str) -> Result<Option<PathBuf>> {
+    let template = target_dir.join("%(title).200B-%(id)s.%(ext)s");
+    let output = Command::new("yt-dlp")
+        .args(ytdlp_subtitle_args(&template, link))
+        .output()
+        .context("run yt-dlp")?;
+    if !output.status.success() {
+        return Err(anyhow!(format_ytdlp_error(&output)));
+    }
+    find_subtitle_file(target_dir)
+}
+
+pub(super) fn ytdlp_subtitle_args(template: &Path, link: &str) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "--no-playlist",
+        "--write-auto-sub",
+        "--sub-lang",
+        "en",
+        "--skip-download",
+        "--convert-subs",
+        "srt",
+        "-o",
+    ]
+    .map(String::from)
+    .to_vec();
+    args.push(template.to_string_lossy().to_string());
+    args.push(link.to_string());
+    args
+}
+
+pub(super) fn find_subtitle_file(dir: &Path) -> Result<Option<PathBuf>> {
+    for entry in fs::read_dir(dir).with_context(|| format!("read {}", dir.display()))? {
+        let path = entry?.path();
+        let is_srt = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("srt"));
+        if is_srt {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+pub(super) fn ytdlp_download_args(
+    template: &Path,
+    link: &str,
+    format_selector: &str,
+) -> Vec<String> {
+    let mut args = vec![
+        "--no-playlist".to_string(),
+        "-f".to_string(),
+        format_selector.to_string(),
+    ];
+    if format_selector == AUDIO_M4A_FORMAT_SELECTOR {
+        args.extend(["-x", "--audio-format", "m4a"].map(String::from));
+    }
+    args.extend([
+        "--print".to_string(),
+        "after_move:filepath".to_string(),
+        "-o".to_string(),
+        template.to_string_lossy().to_string(),
+        link.to_string(),
+    ]);
+    args
+}
+
 pub(super) fn format_ytdlp_error(output: &std::process::Output) -> String {
     let mut message = "yt-dlp failed.".to_string();
     let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
@@ -4,10 +4,11 @@ use std::io::Write;
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, Context, Result};
-use chrono::Local;
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime};
 use clap::Parser;
 use log::error;
 use rand::seq::SliceRandom;
@@ -32,13 +33,65 @@ use integrations::*;
 use message_handlers::handle_message;
 
 const ACK_TTL_SECS: u64 = 5;
-const UNDO_TTL_SECS: u64 = 30 * 60;
+const INFO_TTL_SECS: u64 = 30;
+const DEFAULT_UNDO_TTL_SECS: u64 = 30 * 60;
 const DELETE_CONFIRM_TTL_SECS: u64 = 5 * 60;
+const FINISH_ALL_CONFIRM_TTL_SECS: u64 = 5 * 60;
+const QUEUE_CLEAR_CONFIRM_TTL_SECS: u64 = 5 * 60;
 const RESOURCE_PROMPT_TTL_SECS: u64 = 5 * 60;
-const PAGE_SIZE: usize = 3;
+const DEFAULT_PAGE_SIZE: usize = 3;
+const MAX_PAGE_SIZE: usize = 10;
+const PICK_ROW_WIDTH: usize = 5;
+const EMPTY_PREVIEW_PLACEHOLDER: &str = "(no text)";
+const ARCHIVED_MARKER: &str = "(archived)";
+const PINNED_MARKER: &str = "📌";
+const PEEKED_AUTO_RESET_TEXT: &str = "Everything was peeked, so peeked items were reset.";
+const AUDIO_M4A_FORMAT_SELECTOR: &str = "bestaudio[ext=m4a]/bestaudio";
+// Not a real yt-dlp format; marks the option that fetches subtitles only.
+const SUBTITLES_FORMAT_SELECTOR: &str = "subtitles:en";
+// Likewise marks the option that downloads every item of a playlist.
+const PLAYLIST_FORMAT_SELECTOR: &str = "playlist:best";
+const DEFAULT_PLAYLIST_ITEM_LIMIT: usize = 20;
 const DOWNLOAD_PROMPT_TTL_SECS: u64 = 5 * 60;
 const FINISH_TITLE_PROMPT_TTL_SECS: u64 = 5 * 60;
+const TITLE_PROMPT_TTL_SECS: u64 = 5 * 60;
+const EDIT_PROMPT_TTL_SECS: u64 = 5 * 60;
 const SYNC_X_PROMPT_TTL_SECS: u64 = 10 * 60;
+const PROGRESS_BAR_WIDTH: usize = 10;
+const TRASH_VIEW_LIMIT: usize = 10;
+const TOP_DOMAINS_DEFAULT: usize = 10;
+const LINK_HEAVY_LIMIT: usize = 10;
+const BIGGEST_DEFAULT: usize = 10;
+const POLL_DEFAULT_OPTIONS: usize = 4;
+const POLL_MAX_OPTIONS: usize = 10;
+const POLL_OPTION_MAX_CHARS: usize = 100;
+const SHORT_ENTRY_MAX_LINES: usize = 2;
+const LONG_ENTRY_MIN_LINES: usize = 3;
+const WEEK_DIGEST_DAYS: i64 = 7;
+const HEATMAP_DAYS: i64 = 30;
+const CHART_DAYS: i64 = 14;
+const CHART_BAR_WIDTH: u32 = 32;
+const CHART_HEIGHT: u32 = 240;
+const SNAPSHOT_VERSION: u32 = 1;
+const SNAPSHOT_CONFIRM_TTL_SECS: u64 = 5 * 60;
+const DEFAULT_COMMAND_PREFIX: &str = "/";
+const MEDIA_SWEEP_INTERVAL_SECS: u64 = 6 * 60 * 60;
+const SESSION_SWEEP_INTERVAL_SECS: u64 = 60;
+const DIGEST_PREVIEW_COUNT: usize = 5;
+const DEFAULT_LINK_CHECK_CONCURRENCY: usize = 8;
+const DEFAULT_LINK_CHECK_TIMEOUT_MS: u64 = 10_000;
+const DEFAULT_SEND_RETRY_ATTEMPTS: u32 = 3;
+const SEND_RETRY_BACKOFF_MS: u64 = 500;
+const SEND_RETRY_MAX_WAIT_SECS: u64 = 5;
+// Telegram rejects bot uploads above 50 MB.
+const DEFAULT_MAX_SEND_BYTES: u64 = 50_000_000;
+const BROKEN_LINKS_LIMIT: usize = 20;
+const OPEN_LINK_BUTTONS: usize = 3;
+// Telegram caps message text at 4096 characters; leave room for escaping.
+const EXPORT_MESSAGE_LIMIT: usize = 3500;
+const FINISHED_STAMP_PREFIX: &str = "  - Finished: ";
+const TITLE_FETCH_TIMEOUT_MS: u64 = 3_000;
+const TITLE_FETCH_MAX_BYTES: usize = 256 * 1024;
 
 #[derive(Debug, Clone)]
 struct Config {
@@ -52,6 +105,42 @@ struct Config {
     retry_interval_seconds: Option<u64>,
     sync: Option<SyncConfig>,
     sync_x: Option<SyncXConfig>,
+    default_download_action: DefaultDownloadAction,
+    playlist_item_limit: usize,
+    parse_mode: OutgoingParseMode,
+    trash_path: Option<PathBuf>,
+    prune_media: bool,
+    max_photo_dimension: Option<u32>,
+    block_refinished_links: bool,
+    track_added_dates: bool,
+    media_max_age_days: Option<u64>,
+    command_prefix: String,
+    default_resource_file: Option<PathBuf>,
+    media_before_text: bool,
+    dedupe_by: DedupeBy,
+    min_link_length: usize,
+    download_scan_active_page: bool,
+    auto_create_default_resource: bool,
+    undos_refresh_in_place: bool,
+    link_check_concurrency: usize,
+    link_check_timeout_ms: u64,
+    display_normalize_links: bool,
+    auto_tag_domains: HashMap<String, String>,
+    finished_timestamp: bool,
+    page_size: usize,
+    source_delete_delay_ms: u64,
+    send_retry_attempts: u32,
+    random_skip_archived: bool,
+    max_send_bytes: u64,
+    digest_time: Option<NaiveTime>,
+    auto_reset_when_all_peeked: bool,
+    undo_ttl_seconds: u64,
+    fetch_titles: bool,
+    finished_order: FinishedOrder,
+    session_ttl_seconds: Option<u64>,
+    image_dir: PathBuf,
+    video_dir: PathBuf,
+    document_dir: PathBuf,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -66,6 +155,107 @@ struct ConfigFile {
     retry_interval_seconds: Option<u64>,
     sync: Option<SyncConfig>,
     sync_x: Option<SyncXConfig>,
+    #[serde(default)]
+    default_download_action: DefaultDownloadAction,
+    playlist_item_limit: Option<usize>,
+    #[serde(default)]
+    parse_mode: OutgoingParseMode,
+    trash_path: Option<PathBuf>,
+    #[serde(default)]
+    prune_media: bool,
+    max_photo_dimension: Option<u32>,
+    #[serde(default)]
+    block_refinished_links: bool,
+    #[serde(default)]
+    track_added_dates: bool,
+    media_max_age_days: Option<u64>,
+    command_prefix: Option<String>,
+    default_resource_file: Option<PathBuf>,
+    #[serde(default)]
+    media_before_text: bool,
+    #[serde(default)]
+    dedupe_by: DedupeBy,
+    #[serde(default)]
+    min_link_length: usize,
+    #[serde(default)]
+    download_scan_active_page: bool,
+    #[serde(default)]
+    auto_create_default_resource: bool,
+    #[serde(default)]
+    undos_refresh_in_place: bool,
+    link_check_concurrency: Option<usize>,
+    link_check_timeout_ms: Option<u64>,
+    #[serde(default)]
+    display_normalize_links: bool,
+    #[serde(default)]
+    auto_tag_domains: HashMap<String, String>,
+    #[serde(default)]
+    finished_timestamp: bool,
+    page_size: Option<usize>,
+    #[serde(default)]
+    source_delete_delay_ms: u64,
+    send_retry_attempts: Option<u32>,
+    #[serde(default)]
+    random_skip_archived: bool,
+    max_send_bytes: Option<u64>,
+    digest_time: Option<String>,
+    #[serde(default)]
+    auto_reset_when_all_peeked: bool,
+    undo_ttl_seconds: Option<u64>,
+    #[serde(default)]
+    fetch_titles: bool,
+    #[serde(default)]
+    finished_order: FinishedOrder,
+    session_ttl_seconds: Option<u64>,
+    image_dir: Option<PathBuf>,
+    video_dir: Option<PathBuf>,
+    document_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+enum OutgoingParseMode {
+    #[default]
+    #[serde(rename = "none")]
+    Plain,
+    MarkdownV2,
+    #[serde(rename = "HTML")]
+    Html,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum DedupeBy {
+    #[default]
+    Exact,
+    FirstLink,
+}
+
+/// Where newly finished items go in the Finished file.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum FinishedOrder {
+    #[default]
+    Prepend,
+    Append,
+}
+
+impl OutgoingParseMode {
+    fn telegram_mode(self) -> Option<teloxide::types::ParseMode> {
+        match self {
+            OutgoingParseMode::Plain => None,
+            OutgoingParseMode::MarkdownV2 => Some(teloxide::types::ParseMode::MarkdownV2),
+            OutgoingParseMode::Html => Some(teloxide::types::ParseMode::Html),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+enum DefaultDownloadAction {
+    #[default]
+    Ask,
+    Send,
+    Save,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -79,7 +269,33 @@ enum UserIdInput {
 #[derive(Debug, Deserialize, Clone)]
 struct SyncConfig {
     repo_path: PathBuf,
-    token_file: PathBuf,
+    #[serde(default)]
+    token_file: Option<PathBuf>,
+    #[serde(default)]
+    author_name: Option<String>,
+    #[serde(default)]
+    author_email: Option<String>,
+    #[serde(default)]
+    auth: SyncAuth,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum SyncAuth {
+    /// HTTPS remotes use the PAT in `token_file`; SSH remotes use the
+    /// service user's own SSH setup.
+    #[default]
+    Pat,
+    Ssh {
+        #[serde(default)]
+        key_file: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RemoteKind {
+    Https,
+    Ssh,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -148,8 +364,12 @@ impl EntryBlock {
     fn preview_lines(&self) -> Vec<String> {
         let display = self.display_lines();
         let mut preview = Vec::new();
-        if let Some(first) = display.get(0) {
-            preview.push(first.clone());
+        if let Some(first) = display.first() {
+            if first.trim().is_empty() {
+                preview.push(EMPTY_PREVIEW_PLACEHOLDER.to_string());
+            } else {
+                preview.push(first.clone());
+            }
         }
         if let Some(second) = display.get(1) {
             preview.push(second.clone());
@@ -171,6 +391,9 @@ struct QueuedOp {
     resource_path: Option<PathBuf>,
     #[serde(default)]
     updated_entry: Option<String>,
+    /// Read Later index an undone item goes back to; `None` means the top.
+    #[serde(default)]
+    position: Option<usize>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -182,6 +405,17 @@ enum QueuedOpKind {
     MoveToFinishedUpdated,
     MoveToReadLater,
     UpdateEntry,
+    RestoreFromTrash,
+    /// Archives Finished entries dated before `cutoff`, fixed when the op is
+    /// created so a retry moves the same entries.
+    Archive {
+        cutoff: NaiveDate,
+    },
+    Tidy,
+    NormalizeAll,
+    FinishToResource,
+    UndoFinishToResource,
+    DeleteResource,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -196,6 +430,39 @@ struct UndoRecord {
 enum UndoKind {
     MoveToFinished,
     Delete,
+    FinishToResource {
+        resource_path: PathBuf,
+        /// Whether the finish added the resource block; undo only removes
+        /// the block when it did.
+        #[serde(default)]
+        added: bool,
+    },
+    Edit {
+        updated_entry: String,
+        #[serde(default)]
+        resource_path: Option<PathBuf>,
+    },
+    AddResource {
+        resource_path: PathBuf,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Snapshot {
+    version: u32,
+    created_at: u64,
+    read_later: String,
+    finished: String,
+    queue: Vec<QueuedOp>,
+    undo: Vec<UndoRecord>,
+}
+
+#[derive(Clone, Debug)]
+struct SnapshotRestorePrompt {
+    chat_id: i64,
+    message_id: MessageId,
+    snapshot: Snapshot,
+    expires_at: u64,
 }
 
 #[derive(Clone, Debug)]
@@ -216,6 +483,16 @@ struct AddPrompt {
     source_message_id: MessageId,
 }
 
+#[derive(Clone, Debug)]
+struct TitlePrompt {
+    chat_id: i64,
+    message_id: MessageId,
+    link: String,
+    title: String,
+    source_message_id: Option<MessageId>,
+    expires_at: u64,
+}
+
 #[derive(Clone, Debug)]
 struct ResourcePickerState {
     chat_id: i64,
@@ -223,6 +500,14 @@ struct ResourcePickerState {
     text: String,
     source_message_id: Option<MessageId>,
     files: Vec<PathBuf>,
+    added_to: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug)]
+struct ResourceBrowserState {
+    chat_id: i64,
+    message_id: MessageId,
+    files: Vec<PathBuf>,
 }
 
 #[derive(Clone, Debug)]
@@ -251,10 +536,18 @@ enum DownloadPickerMode {
     },
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DownloadSendKind {
+    Video,
+    Audio,
+    Document,
+}
+
 #[derive(Clone, Debug, Copy)]
 enum DownloadAction {
     Send,
     Save,
+    SendAndSave,
 }
 
 #[derive(Clone, Debug)]
@@ -275,10 +568,37 @@ struct FinishTitlePrompt {
     session_id: String,
     chat_id: i64,
     entry: String,
-    link: String,
+    mode: FinishMode,
+    return_to: ListView,
+    prompt_message_id: MessageId,
+    expires_at: u64,
+}
+
+#[derive(Clone, Debug)]
+enum FinishMode {
+    Title { link: String },
+    Note,
+}
+
+#[derive(Clone, Debug)]
+struct EditPrompt {
+    session_id: String,
+    chat_id: i64,
+    entry: String,
+    target_path: Option<PathBuf>,
     return_to: ListView,
     prompt_message_id: MessageId,
     expires_at: u64,
+    mode: EditMode,
+}
+
+#[derive(Clone, Debug)]
+enum EditMode {
+    Text,
+    Title { link: String },
+    Category,
+    Due,
+    Position,
 }
 
 #[derive(Clone, Debug)]
@@ -287,6 +607,14 @@ struct SyncXCookiePrompt {
     expires_at: u64,
 }
 
+/// A "Clear" on a `/queue` message waiting for confirm step `step`.
+#[derive(Clone, Debug)]
+struct QueueClearConfirm {
+    message_id: MessageId,
+    step: u8,
+    expires_at: u64,
+}
+
 #[derive(Clone, Debug)]
 struct UndoSession {
     chat_id: i64,
@@ -294,10 +622,35 @@ struct UndoSession {
     records: Vec<UndoRecord>,
 }
 
+#[derive(Clone, Debug)]
+struct TrashSession {
+    chat_id: i64,
+    message_id: MessageId,
+    entries: Vec<EntryBlock>,
+}
+
+/// A `/random_finished` message and the Finished item it shows.
+#[derive(Clone, Debug)]
+struct FinishedPick {
+    chat_id: i64,
+    message_id: MessageId,
+    entry: EntryBlock,
+}
+
+#[derive(Clone, Debug)]
+struct DuplicatesSession {
+    chat_id: i64,
+    message_id: MessageId,
+    groups: Vec<Vec<EntryBlock>>,
+    page: usize,
+    last_activity: u64,
+}
+
 #[derive(Clone, Debug)]
 enum SessionKind {
     List,
     Search { query: String },
+    Resource { path: PathBuf },
 }
 
 #[derive(Clone, Debug)]
@@ -310,6 +663,18 @@ struct ListSession {
     seen_random: HashSet<usize>,
     message_id: Option<MessageId>,
     sent_media_message_ids: Vec<MessageId>,
+    hide_media: bool,
+    session_undo: Vec<SessionUndo>,
+    last_activity: u64,
+}
+
+/// A finish or delete done from a list session, kept so the session can put
+/// the item back where it was.
+#[derive(Clone, Debug)]
+struct SessionUndo {
+    undo_id: String,
+    entry: EntryBlock,
+    index: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -333,6 +698,33 @@ enum ListView {
         step: u8,
         expires_at: u64,
     },
+    FinishAllConfirm {
+        step: u8,
+        expires_at: u64,
+    },
+    FinishLinkPick {
+        selected: Box<ListView>,
+        index: usize,
+        links: Vec<String>,
+    },
+    FinishResourcePick {
+        selected: Box<ListView>,
+        index: usize,
+        files: Vec<PathBuf>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum FinishTitleStep {
+    NoLink,
+    Prompt(String),
+    PickLink(Vec<String>),
+}
+
+#[derive(Clone, Debug, Copy, PartialEq)]
+enum ListUpdateStep {
+    Text,
+    Media,
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -355,24 +747,48 @@ struct AppState {
     active_sessions: Mutex<HashMap<i64, String>>,
     peeked: Mutex<HashSet<String>>,
     undo_sessions: Mutex<HashMap<String, UndoSession>>,
+    trash_sessions: Mutex<HashMap<String, TrashSession>>,
+    duplicate_sessions: Mutex<HashMap<String, DuplicatesSession>>,
+    finished_picks: Mutex<HashMap<String, FinishedPick>>,
     pickers: Mutex<HashMap<String, PickerState>>,
     add_prompts: Mutex<HashMap<String, AddPrompt>>,
+    title_prompts: Mutex<HashMap<String, TitlePrompt>>,
     resource_pickers: Mutex<HashMap<String, ResourcePickerState>>,
     resource_filename_prompts: Mutex<HashMap<i64, ResourceFilenamePrompt>>,
+    resource_browsers: Mutex<HashMap<String, ResourceBrowserState>>,
     download_pickers: Mutex<HashMap<String, DownloadPickerState>>,
     download_link_prompts: Mutex<HashMap<i64, DownloadLinkPrompt>>,
     finish_title_prompts: Mutex<HashMap<i64, FinishTitlePrompt>>,
+    edit_prompts: Mutex<HashMap<i64, EditPrompt>>,
     sync_x_cookie_prompts: Mutex<HashMap<i64, SyncXCookiePrompt>>,
+    queue_clear_confirms: Mutex<HashMap<i64, QueueClearConfirm>>,
+    snapshot_restores: Mutex<HashMap<String, SnapshotRestorePrompt>>,
     queue: Mutex<Vec<QueuedOp>>,
+    /// Bumped whenever the queue is replaced wholesale, so a flush that
+    /// started earlier doesn't write its leftovers back over the new queue.
+    queue_generation: AtomicU64,
     undo: Mutex<Vec<UndoRecord>>,
     queue_path: PathBuf,
     undo_path: PathBuf,
+    peeked_path: PathBuf,
+    sync_in_progress: AtomicBool,
+}
+
+struct SyncGuard<'a> {
+    flag: &'a AtomicBool,
+}
+
+impl Drop for SyncGuard<'_> {
+    fn drop(&mut self) {
+        self.flag.store(false, Ordering::Release);
+    }
 }
 
 #[derive(Debug)]
 enum AddOutcome {
     Added,
     Duplicate,
+    AlreadyFinished,
 }
 
 #[derive(Debug)]
@@ -391,38 +807,65 @@ async fn main() -> Result<()> {
 
     let queue_path = config.data_dir.join("queue.json");
     let undo_path = config.data_dir.join("undo.json");
+    let peeked_path = config.data_dir.join("peeked.json");
 
     let mut undo = load_undo(&undo_path)?;
     prune_undo(&mut undo);
     save_undo(&undo_path, &undo)?;
 
+    let mut peeked = load_peeked(&peeked_path)?;
+    let read_later_entries = read_entries(&config.read_later_path)?.1;
+    if prune_peeked(&mut peeked, &read_later_entries) {
+        save_peeked(&peeked_path, &peeked)?;
+    }
+
     let state = AppState {
         config: config.clone(),
         write_lock: Mutex::new(()),
         sessions: Mutex::new(HashMap::new()),
         active_sessions: Mutex::new(HashMap::new()),
-        peeked: Mutex::new(HashSet::new()),
+        peeked: Mutex::new(peeked),
         undo_sessions: Mutex::new(HashMap::new()),
+        trash_sessions: Mutex::new(HashMap::new()),
+        duplicate_sessions: Mutex::new(HashMap::new()),
+        finished_picks: Mutex::new(HashMap::new()),
         pickers: Mutex::new(HashMap::new()),
         add_prompts: Mutex::new(HashMap::new()),
+        title_prompts: Mutex::new(HashMap::new()),
         resource_pickers: Mutex::new(HashMap::new()),
         resource_filename_prompts: Mutex::new(HashMap::new()),
+        resource_browsers: Mutex::new(HashMap::new()),
         download_pickers: Mutex::new(HashMap::new()),
         download_link_prompts: Mutex::new(HashMap::new()),
         finish_title_prompts: Mutex::new(HashMap::new()),
+        edit_prompts: Mutex::new(HashMap::new()),
         sync_x_cookie_prompts: Mutex::new(HashMap::new()),
+        queue_clear_confirms: Mutex::new(HashMap::new()),
+        snapshot_restores: Mutex::new(HashMap::new()),
         queue: Mutex::new(load_queue(&queue_path)?),
+        queue_generation: AtomicU64::new(0),
         undo: Mutex::new(undo),
         queue_path,
         undo_path,
+        peeked_path,
+        sync_in_progress: AtomicBool::new(false),
     };
 
     let state = std::sync::Arc::new(state);
 
     let retry_secs = config.retry_interval_seconds.unwrap_or(30);
     start_retry_loop(state.clone(), retry_secs);
+    if let Some(max_age_days) = config.media_max_age_days {
+        start_media_sweeper(state.clone(), max_age_days);
+    }
 
     let bot = Bot::new(config.token.clone());
+    if let Some(digest_time) = config.digest_time {
+        start_digest_scheduler(bot.clone(), state.clone(), digest_time);
+    }
+    if let Some(ttl) = config.session_ttl_seconds {
+        start_session_reaper(bot.clone(), state.clone(), ttl);
+    }
 
     let handler = dptree::entry()
         .branch(Update::filter_message().endpoint(handle_message))
@@ -455,11 +898,24 @@ async fn apply_op(state: &std::sync::Arc<AppState>, op: &QueuedOp) -> Result<App
     match op.kind {
         QueuedOpKind::Add => {
             let entry = EntryBlock::from_block(&op.entry);
-            let outcome =
-                with_retries(|| add_entry_sync(&state.config.read_later_path, &entry)).await?;
+            let finished = state
+                .config
+                .block_refinished_links
+                .then_some(state.config.finished_path.as_path());
+            let outcome = with_retries(|| {
+                add_entry_sync(
+                    &state.config.read_later_path,
+                    &entry,
+                    finished,
+                    state.config.dedupe_by,
+                    op.position,
+                )
+            })
+            .await?;
             Ok(match outcome {
                 AddOutcome::Added => ApplyOutcome::Applied,
                 AddOutcome::Duplicate => ApplyOutcome::Duplicate,
+                AddOutcome::AlreadyFinished => ApplyOutcome::AlreadyFinished,
             })
         }
         QueuedOpKind::AddResource => {
@@ -470,13 +926,23 @@ async fn apply_op(state: &std::sync::Arc<AppState>, op: &QueuedOp) -> Result<App
             let outcome = with_retries(|| add_resource_entry_sync(path, &op.entry)).await?;
             Ok(match outcome {
                 AddOutcome::Added => ApplyOutcome::Applied,
-                AddOutcome::Duplicate => ApplyOutcome::Duplicate,
+                AddOutcome::Duplicate | AddOutcome::AlreadyFinished => ApplyOutcome::Duplicate,
             })
         }
         QueuedOpKind::Delete => {
-            let outcome =
-                with_retries(|| delete_entry_sync(&state.config.read_later_path, &op.entry))
-                    .await?;
+            let outcome = with_retries(|| {
+                delete_entry_sync(
+                    &state.config.read_later_path,
+                    &op.entry,
+                    state.config.trash_path.as_deref(),
+                )
+            })
+            .await?;
+            if matches!(outcome, ModifyOutcome::Applied) {
+                if let Err(err) = prune_entry_media_sync(&state.config, &op.entry) {
+                    error!("prune media failed: {:#}", err);
+                }
+            }
             Ok(match outcome {
                 ModifyOutcome::Applied => ApplyOutcome::Applied,
                 ModifyOutcome::NotFound => ApplyOutcome::NotFound,
@@ -488,6 +954,8 @@ async fn apply_op(state: &std::sync::Arc<AppState>, op: &QueuedOp) -> Result<App
                     &state.config.read_later_path,
                     &state.config.finished_path,
                     &op.entry,
+                    finished_stamp_date(&state.config),
+                    state.config.finished_order,
                 )
             })
             .await?;
@@ -507,6 +975,8 @@ async fn apply_op(state: &std::sync::Arc<AppState>, op: &QueuedOp) -> Result<App
                     &state.config.finished_path,
                     &op.entry,
                     updated_entry,
+                    finished_stamp_date(&state.config),
+                    state.config.finished_order,
                 )
             })
             .await?;
@@ -521,6 +991,7 @@ async fn apply_op(state: &std::sync::Arc<AppState>, op: &QueuedOp) -> Result<App
                     &state.config.read_later_path,
                     &state.config.finished_path,
                     &op.entry,
+                    op.position,
                 )
             })
             .await?;
@@ -529,14 +1000,101 @@ async fn apply_op(state: &std::sync::Arc<AppState>, op: &QueuedOp) -> Result<App
                 ModifyOutcome::NotFound => ApplyOutcome::NotFound,
             })
         }
+        QueuedOpKind::RestoreFromTrash => {
+            let trash_path = state
+                .config
+                .trash_path
+                .as_ref()
+                .ok_or_else(|| anyhow!("missing trash path"))?;
+            let outcome = with_retries(|| {
+                restore_from_trash_sync(
+                    &state.config.read_later_path,
+                    trash_path,
+                    &op.entry,
+                    op.position,
+                )
+            })
+            .await?;
+            if matches!(outcome, ModifyOutcome::Applied) {
+                if let Err(err) = restore_entry_media_sync(&state.config, &op.entry) {
+                    error!("restore media failed: {:#}", err);
+                }
+            }
+            Ok(match outcome {
+                ModifyOutcome::Applied => ApplyOutcome::Applied,
+                ModifyOutcome::NotFound => ApplyOutcome::NotFound,
+            })
+        }
+        QueuedOpKind::Archive { cutoff } => {
+            let (moved, _) = run_archive(state, cutoff).await?;
+            Ok(if moved > 0 {
+                ApplyOutcome::Applied
+            } else {
+                ApplyOutcome::NotFound
+            })
+        }
+        QueuedOpKind::Tidy => {
+            let tidied = with_retries(|| tidy_entries_sync(&state.config.read_later_path)).await?;
+            Ok(if tidied > 0 {
+                ApplyOutcome::Applied
+            } else {
+                ApplyOutcome::NotFound
+            })
+        }
         QueuedOpKind::UpdateEntry => {
             let updated_entry = op
                 .updated_entry
                 .as_ref()
                 .ok_or_else(|| anyhow!("missing updated entry"))?;
             let updated_entry = EntryBlock::from_block(updated_entry);
+            let path = op
+                .resource_path
+                .as_deref()
+                .unwrap_or(&state.config.read_later_path);
+            let outcome =
+                with_retries(|| update_entry_sync(path, &op.entry, &updated_entry)).await?;
+            Ok(match outcome {
+                ModifyOutcome::Applied => ApplyOutcome::Applied,
+                ModifyOutcome::NotFound => ApplyOutcome::NotFound,
+            })
+        }
+        QueuedOpKind::NormalizeAll => {
+            let changed =
+                with_retries(|| normalize_all_entries_sync(&state.config.read_later_path)).await?;
+            Ok(if changed > 0 {
+                ApplyOutcome::Applied
+            } else {
+                ApplyOutcome::NotFound
+            })
+        }
+        QueuedOpKind::DeleteResource => {
+            let path = op
+                .resource_path
+                .as_ref()
+                .ok_or_else(|| anyhow!("missing resource path"))?;
+            let outcome = with_retries(|| delete_entry_sync(path, &op.entry, None)).await?;
+            Ok(match outcome {
+                ModifyOutcome::Applied => ApplyOutcome::Applied,
+                ModifyOutcome::NotFound => ApplyOutcome::NotFound,
+            })
+        }
+        QueuedOpKind::FinishToResource => {
+            let (outcome, _) = run_finish_to_resource(state, op).await?;
+            Ok(outcome)
+        }
+        QueuedOpKind::UndoFinishToResource => {
+            let resource_path = op
+                .resource_path
+                .as_ref()
+                .ok_or_else(|| anyhow!("missing resource path"))?;
             let outcome = with_retries(|| {
-                update_entry_sync(&state.config.read_later_path, &op.entry, &updated_entry)
+                undo_finish_to_resource_sync(
+                    &state.config.read_later_path,
+                    &state.config.finished_path,
+                    resource_path,
+                    &op.entry,
+                    op.position,
+                )
             })
             .await?;
             Ok(match outcome {
@@ -547,10 +1105,49 @@ async fn apply_op(state: &std::sync::Arc<AppState>, op: &QueuedOp) -> Result<App
     }
 }
 
+/// Moves Finished entries dated before `cutoff` into the yearly archives and
+/// returns `(moved, undated)`. Callers hold `write_lock`.
+async fn run_archive(
+    state: &std::sync::Arc<AppState>,
+    cutoff: NaiveDate,
+) -> Result<(usize, usize)> {
+    let archive_dir = archive_dir(&state.config);
+    with_retries(|| archive_finished_sync(&state.config.finished_path, archive_dir, cutoff)).await
+}
+
+/// Applies a `FinishToResource` op and also returns whether the resource
+/// block was added by it. Callers hold `write_lock`.
+async fn run_finish_to_resource(
+    state: &std::sync::Arc<AppState>,
+    op: &QueuedOp,
+) -> Result<(ApplyOutcome, bool)> {
+    let resource_path = op
+        .resource_path
+        .as_ref()
+        .ok_or_else(|| anyhow!("missing resource path"))?;
+    let (outcome, added) = with_retries(|| {
+        finish_to_resource_sync(
+            &state.config.read_later_path,
+            &state.config.finished_path,
+            resource_path,
+            &op.entry,
+            finished_stamp_date(&state.config),
+            state.config.finished_order,
+        )
+    })
+    .await?;
+    let outcome = match outcome {
+        ModifyOutcome::Applied => ApplyOutcome::Applied,
+        ModifyOutcome::NotFound => ApplyOutcome::NotFound,
+    };
+    Ok((outcome, added))
+}
+
 #[derive(Debug)]
 enum ApplyOutcome {
     Applied,
     Duplicate,
+    AlreadyFinished,
     NotFound,
 }
 
@@ -569,6 +1166,34 @@ enum PullOutcome {
     Pulled,
 }
 
+struct SyncStatus {
+    ahead: usize,
+    behind: usize,
+    dirty: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AgeBucket {
+    Today,
+    ThisWeek,
+    ThisMonth,
+    Older,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum MediaProblem {
+    Missing,
+    Empty,
+    Unreadable(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum LinkHealth {
+    Ok,
+    Status(u16),
+    Failed(String),
+}
+
 enum PullMode {
     FastForward,
     Theirs,
@@ -592,3 +1217,11 @@ async fn queue_op(state: &std::sync::Arc<AppState>, op: QueuedOp) -> Result<()>
     save_queue(&state.queue_path, &queue)
 }
 
+/// Swaps in a whole new queue (clearing it, or restoring a snapshot's) and
+/// invalidates any flush already in flight.
+async fn replace_queue(state: &std::sync::Arc<AppState>, ops: Vec<QueuedOp>) -> Result<()> {
+    let mut queue = state.queue.lock().await;
+    state.queue_generation.fetch_add(1, Ordering::AcqRel);
+    *queue = ops;
+    save_queue(&state.queue_path, &queue)
+}
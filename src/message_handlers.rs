@@ -45,6 +45,32 @@ pub(super) async fn handle_message(bot: Bot, msg: Message, state: std::sync::Arc
         return Ok(());
     }
 
+    let mut expired_edit_prompt: Option<EditPrompt> = None;
+    let pending_edit_prompt = {
+        let mut prompts = state.edit_prompts.lock().await;
+        if let Some(prompt) = prompts.remove(&msg.chat.id.0) {
+            if prompt.expires_at > now_ts() {
+                Some(prompt)
+            } else {
+                expired_edit_prompt = Some(prompt);
+                None
+            }
+        } else {
+            None
+        }
+    };
+
+    if let Some(prompt) = expired_edit_prompt {
+        let _ = bot
+            .delete_message(msg.chat.id, prompt.prompt_message_id)
+            .await;
+    }
+
+    if let Some(prompt) = pending_edit_prompt {
+        handle_edit_response(&bot, msg.chat.id, msg.id, &state, &text, prompt).await?;
+        return Ok(());
+    }
+
     let mut expired_resource_prompt: Option<ResourceFilenamePrompt> = None;
     let pending_resource_prompt = {
         let mut prompts = state.resource_filename_prompts.lock().await;
@@ -123,107 +149,315 @@ pub(super) async fn handle_message(bot: Bot, msg: Message, state: std::sync::Arc
         return Ok(());
     }
 
-    if let Some(cmd) = parse_command(&text) {
+    if let Some(cmd) = parse_command(&text, &state.config.command_prefix) {
         let rest = text
-            .splitn(2, |c: char| c.is_whitespace())
-            .nth(1)
+            .split_once(|c: char| c.is_whitespace())
+            .map(|(_, rest)| rest)
             .unwrap_or("")
             .trim();
         match cmd {
             "start" | "help" => {
-                let help = "Send any text to save it. Commands: /start, /help, /add <text>, /list, /top, /last, /random, /search <query>, /delete <query>, /download [url], /undos, /reset_peeked, /pull, /pull theirs, /push, /sync, /sync_x. Use --- to split a message into multiple items. In list views, use buttons for Mark Finished, Add Resource, Delete, Random. Quick actions: reply with del/delete to remove the current item, or send norm to normalize links.";
-                send_message_with_delete_button(&bot, msg.chat.id, help).await?;
+                let help = "Send any text to save it. Commands: /start, /help, /add <text>, /list, /top, /last, /oldest, /random, /random_finished, /search <query> (or re:<pattern>), /delete <query>, /short [maxlines], /long [minlines], /untitled, /media, /due_today, /recategorize, /moveto [n], /resources, /download [url], /undos, /compact_undo, /trash, /restore_media [filename], /verify_media, /orphans, /snapshot, /archive <days>, /tidy, /norm_all, /queue, /export [finished|resources], /stats, /progress, /week, /digest, /age, /tags, /heatmap, /chart, /top_domains [n], /poll [n], /linkheavy, /biggest [n], /near_duplicates, /find_duplicates, /check_links, /reset_peeked, /unpeek <query>, /fresh, /refresh, /status, /pull, /pull theirs, /push, /sync, /sync_x. Send a snapshot file back to restore it. Use --- to split a message into multiple items. In list views, use buttons for Mark Finished, Add Resource, Delete, Random. Quick actions: reply with del/delete to remove the current item, or send norm to normalize links.";
+                send_message_with_delete_button(&bot, &state.config, msg.chat.id, help).await?;
                 return Ok(());
             }
             "add" => {
                 if rest.is_empty() {
-                    send_error(&bot, msg.chat.id, "Provide text to add.").await?;
+                    send_error(&bot, &state.config, msg.chat.id, "Provide text to add.").await?;
                 } else {
                     handle_add_command(bot, msg, state, rest).await?;
                 }
                 return Ok(());
             }
             "list" => {
-                handle_list_command(bot.clone(), msg.clone(), state).await?;
-                let _ = bot.delete_message(msg.chat.id, msg.id).await;
+                handle_list_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
                 return Ok(());
             }
             "search" | "delete" => {
                 if rest.is_empty() {
-                    send_ephemeral(&bot, msg.chat.id, "Provide a search query.", ACK_TTL_SECS)
-                        .await?;
+                    send_ephemeral(
+                        &bot,
+                        &state.config,
+                        msg.chat.id,
+                        "Provide a search query.",
+                        ACK_TTL_SECS,
+                    )
+                    .await?;
                 } else {
-                    handle_search_command(bot.clone(), msg.clone(), state, rest).await?;
+                    handle_search_command(bot.clone(), msg.clone(), state.clone(), rest).await?;
                 }
-                let _ = bot.delete_message(msg.chat.id, msg.id).await;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "moveto" => {
+                handle_moveto_command(&bot, msg.chat.id, &state, rest).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "recategorize" => {
+                handle_recategorize_command(&bot, msg.chat.id, &state).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "untitled" => {
+                handle_untitled_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "due_today" => {
+                handle_due_today_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "media" => {
+                handle_media_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "short" | "long" => {
+                handle_length_command(
+                    bot.clone(),
+                    msg.clone(),
+                    state.clone(),
+                    rest,
+                    cmd == "short",
+                )
+                .await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
                 return Ok(());
             }
             "top" => {
                 handle_quick_select_command(
                     bot.clone(),
                     msg.clone(),
-                    state,
+                    state.clone(),
                     QuickSelectMode::Top,
                 )
                 .await?;
-                let _ = bot.delete_message(msg.chat.id, msg.id).await;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
                 return Ok(());
             }
             "last" => {
                 handle_quick_select_command(
                     bot.clone(),
                     msg.clone(),
-                    state,
+                    state.clone(),
                     QuickSelectMode::Last,
                 )
                 .await?;
-                let _ = bot.delete_message(msg.chat.id, msg.id).await;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
                 return Ok(());
             }
             "random" => {
                 handle_quick_select_command(
                     bot.clone(),
                     msg.clone(),
-                    state,
+                    state.clone(),
                     QuickSelectMode::Random,
                 )
                 .await?;
-                let _ = bot.delete_message(msg.chat.id, msg.id).await;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "random_finished" => {
+                handle_random_finished_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "oldest" => {
+                handle_oldest_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "resources" => {
+                handle_resources_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
                 return Ok(());
             }
             "download" => {
-                handle_download_command(bot.clone(), msg.clone(), state, rest).await?;
-                let _ = bot.delete_message(msg.chat.id, msg.id).await;
+                handle_download_command(bot.clone(), msg.clone(), state.clone(), rest).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
                 return Ok(());
             }
             "reset_peeked" => {
-                reset_peeked(&state).await;
-                let _ = bot.delete_message(msg.chat.id, msg.id).await;
+                reset_peeked(&state).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "unpeek" => {
+                handle_unpeek_command(&bot, msg.chat.id, &state, rest).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "fresh" => {
+                handle_fresh_command(&bot, msg.chat.id, &state).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "verify_media" => {
+                handle_verify_media_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "orphans" => {
+                handle_orphans_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "archive" => {
+                handle_archive_command(bot.clone(), msg.clone(), state.clone(), rest).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "export" => {
+                handle_export_command(bot.clone(), msg.clone(), state.clone(), rest).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "queue" => {
+                handle_queue_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "norm_all" => {
+                handle_norm_all_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "tidy" => {
+                handle_tidy_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "stats" => {
+                handle_stats_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "progress" => {
+                handle_progress_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "heatmap" => {
+                handle_heatmap_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "chart" => {
+                handle_chart_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "week" => {
+                handle_week_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "digest" => {
+                handle_digest_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "age" => {
+                handle_age_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "tags" => {
+                handle_tags_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "linkheavy" => {
+                handle_linkheavy_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "check_links" => {
+                handle_check_links_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "find_duplicates" => {
+                handle_find_duplicates_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "near_duplicates" => {
+                handle_near_duplicates_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "poll" => {
+                handle_poll_command(bot.clone(), msg.clone(), state.clone(), rest).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "biggest" => {
+                handle_biggest_command(bot.clone(), msg.clone(), state.clone(), rest).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "top_domains" => {
+                handle_top_domains_command(bot.clone(), msg.clone(), state.clone(), rest).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
                 return Ok(());
             }
             "undos" => {
-                handle_undos_command(bot.clone(), msg.clone(), state).await?;
-                let _ = bot.delete_message(msg.chat.id, msg.id).await;
+                handle_undos_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "trash" => {
+                handle_trash_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "snapshot" => {
+                handle_snapshot_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "compact_undo" => {
+                handle_compact_undo_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "restore_media" => {
+                handle_restore_media_command(bot.clone(), msg.clone(), state.clone(), rest).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "refresh" => {
+                handle_refresh_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
                 return Ok(());
             }
             "pull" => {
-                handle_pull_command(bot.clone(), msg.clone(), state, rest).await?;
-                let _ = bot.delete_message(msg.chat.id, msg.id).await;
+                handle_pull_command(bot.clone(), msg.clone(), state.clone(), rest).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
+                return Ok(());
+            }
+            "status" => {
+                handle_sync_status_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
                 return Ok(());
             }
             "push" => {
-                handle_push_command(bot.clone(), msg.clone(), state).await?;
-                let _ = bot.delete_message(msg.chat.id, msg.id).await;
+                handle_push_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
                 return Ok(());
             }
             "sync" => {
-                handle_sync_command(bot.clone(), msg.clone(), state).await?;
-                let _ = bot.delete_message(msg.chat.id, msg.id).await;
+                handle_sync_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
                 return Ok(());
             }
             "sync_x" => {
-                handle_sync_x_command(bot.clone(), msg.clone(), state).await?;
-                let _ = bot.delete_message(msg.chat.id, msg.id).await;
+                handle_sync_x_command(bot.clone(), msg.clone(), state.clone()).await?;
+                delete_source_message(&bot, &state.config, msg.chat.id, msg.id).await;
                 return Ok(());
             }
             _ => {
@@ -232,16 +466,13 @@ pub(super) async fn handle_message(bot: Bot, msg: Message, state: std::sync::Arc
         }
     }
 
-    if is_instant_delete_message(&text) {
-        if handle_instant_delete_message(&bot, &msg, &state).await? {
-            return Ok(());
-        }
+    if is_instant_delete_message(&text) && handle_instant_delete_message(&bot, &msg, &state).await?
+    {
+        return Ok(());
     }
 
-    if is_norm_message(&text) {
-        if handle_norm_message(&bot, &msg, &state).await? {
-            return Ok(());
-        }
+    if is_norm_message(&text) && handle_norm_message(&bot, &msg, &state).await? {
+        return Ok(());
     }
 
     if text.contains("---") {
@@ -260,15 +491,25 @@ async fn handle_media_message(
 ) -> Result<bool> {
     let chat_id = msg.chat.id;
     let caption = msg.caption().map(|text| text.to_string());
-    let media_dir = state.config.media_dir.clone();
 
     if let Some(photos) = msg.photo() {
         if let Some(photo) = pick_best_photo(photos) {
-            fs::create_dir_all(&media_dir)
+            let media_dir = &state.config.image_dir;
+            fs::create_dir_all(media_dir)
                 .with_context(|| format!("create media dir {}", media_dir.display()))?;
             let filename = format!("image-{}.jpg", Uuid::new_v4());
             let dest_path = media_dir.join(&filename);
             download_telegram_file(bot, &photo.file.id, &dest_path).await?;
+            if let Some(max_dimension) = state.config.max_photo_dimension {
+                let path = dest_path.clone();
+                match tokio::task::spawn_blocking(move || maybe_downscale(&path, max_dimension))
+                    .await
+                {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(err)) => error!("downscale photo failed: {:#}", err),
+                    Err(err) => error!("downscale photo task failed: {}", err),
+                }
+            }
             let entry_text = build_media_entry_text(&filename, caption.as_deref());
             handle_single_item(
                 bot.clone(),
@@ -284,16 +525,23 @@ async fn handle_media_message(
 
     if let Some(document) = msg.document() {
         let mime = document.mime_type.as_ref().map(|m| m.essence_str());
-        fs::create_dir_all(&media_dir)
-            .with_context(|| format!("create media dir {}", media_dir.display()))?;
         let ext = mime.and_then(extension_from_mime);
-        let filename = if let Some(name) = document.file_name.as_deref() {
-            sanitize_filename_with_default(name, ext)
-        } else {
-            format!("file-{}.{}", Uuid::new_v4(), ext.unwrap_or("bin"))
+        let name = match document.file_name.as_deref() {
+            Some(name) => sanitize_filename_with_default(name, ext),
+            None => format!("file-{}.{}", Uuid::new_v4(), ext.unwrap_or("bin")),
         };
+        // Documents can be images or videos too, so route them by extension.
+        let media_dir = media_dir_for(&state.config, Path::new(&name));
+        fs::create_dir_all(media_dir)
+            .with_context(|| format!("create media dir {}", media_dir.display()))?;
+        let filename = unique_filename(media_dir, &name);
         let dest_path = media_dir.join(&filename);
         download_telegram_file(bot, &document.file.id, &dest_path).await?;
+        if filename.ends_with(".json")
+            && handle_snapshot_upload(bot, msg, state, &dest_path).await?
+        {
+            return Ok(true);
+        }
         let entry_text = build_media_entry_text(&filename, caption.as_deref());
         handle_single_item(
             bot.clone(),
@@ -307,7 +555,8 @@ async fn handle_media_message(
     }
 
     if let Some(video) = msg.video() {
-        fs::create_dir_all(&media_dir)
+        let media_dir = &state.config.video_dir;
+        fs::create_dir_all(media_dir)
             .with_context(|| format!("create media dir {}", media_dir.display()))?;
         let ext = video
             .mime_type
@@ -315,7 +564,7 @@ async fn handle_media_message(
             .map(|m| m.essence_str())
             .and_then(extension_from_mime);
         let filename = if let Some(name) = video.file_name.as_deref() {
-            sanitize_filename_with_default(name, ext)
+            unique_filename(media_dir, &sanitize_filename_with_default(name, ext))
         } else {
             format!("video-{}.{}", Uuid::new_v4(), ext.unwrap_or("mp4"))
         };
@@ -333,6 +582,58 @@ async fn handle_media_message(
         return Ok(true);
     }
 
+    if let Some(voice) = msg.voice() {
+        let media_dir = &state.config.document_dir;
+        fs::create_dir_all(media_dir)
+            .with_context(|| format!("create media dir {}", media_dir.display()))?;
+        let ext = voice
+            .mime_type
+            .as_ref()
+            .map(|m| m.essence_str())
+            .and_then(audio_extension_from_mime);
+        let filename = format!("voice-{}.{}", Uuid::new_v4(), ext.unwrap_or("ogg"));
+        let dest_path = media_dir.join(&filename);
+        download_telegram_file(bot, &voice.file.id, &dest_path).await?;
+        let entry_text = build_media_entry_text(&filename, caption.as_deref());
+        handle_single_item(
+            bot.clone(),
+            chat_id,
+            state.clone(),
+            &entry_text,
+            Some(msg.id),
+        )
+        .await?;
+        return Ok(true);
+    }
+
+    if let Some(audio) = msg.audio() {
+        let media_dir = &state.config.document_dir;
+        fs::create_dir_all(media_dir)
+            .with_context(|| format!("create media dir {}", media_dir.display()))?;
+        let ext = audio
+            .mime_type
+            .as_ref()
+            .map(|m| m.essence_str())
+            .and_then(audio_extension_from_mime);
+        let filename = if let Some(name) = audio.file_name.as_deref() {
+            unique_filename(media_dir, &sanitize_filename_with_default(name, ext))
+        } else {
+            format!("audio-{}.{}", Uuid::new_v4(), ext.unwrap_or("mp3"))
+        };
+        let dest_path = media_dir.join(&filename);
+        download_telegram_file(bot, &audio.file.id, &dest_path).await?;
+        let entry_text = build_media_entry_text(&filename, caption.as_deref());
+        handle_single_item(
+            bot.clone(),
+            chat_id,
+            state.clone(),
+            &entry_text,
+            Some(msg.id),
+        )
+        .await?;
+        return Ok(true);
+    }
+
     Ok(false)
 }
 
@@ -360,9 +661,10 @@ async fn handle_norm_message(
         state.sessions.lock().await.insert(session_id, session);
         return Ok(false);
     }
+    session.last_activity = now_ts();
 
     let peeked_snapshot = state.peeked.lock().await.clone();
-    let target_index = match norm_target_index(&session, &peeked_snapshot) {
+    let target_index = match norm_target_index(&session, &peeked_snapshot, state.config.page_size) {
         Some(index) => index,
         None => {
             state
@@ -370,8 +672,15 @@ async fn handle_norm_message(
                 .lock()
                 .await
                 .insert(session.id.clone(), session);
-            let _ = bot.delete_message(chat_id, msg.id).await;
-            send_ephemeral(bot, chat_id, "Couldn't normalize.", ACK_TTL_SECS).await?;
+            delete_source_message(bot, &state.config, chat_id, msg.id).await;
+            send_ephemeral(
+                bot,
+                &state.config,
+                chat_id,
+                "Couldn't normalize.",
+                ACK_TTL_SECS,
+            )
+            .await?;
             return Ok(true);
         }
     };
@@ -384,8 +693,15 @@ async fn handle_norm_message(
                 .lock()
                 .await
                 .insert(session.id.clone(), session);
-            let _ = bot.delete_message(chat_id, msg.id).await;
-            send_ephemeral(bot, chat_id, "Couldn't normalize.", ACK_TTL_SECS).await?;
+            delete_source_message(bot, &state.config, chat_id, msg.id).await;
+            send_ephemeral(
+                bot,
+                &state.config,
+                chat_id,
+                "Couldn't normalize.",
+                ACK_TTL_SECS,
+            )
+            .await?;
             return Ok(true);
         }
     };
@@ -396,44 +712,51 @@ async fn handle_norm_message(
             .lock()
             .await
             .insert(session.id.clone(), session);
-        let _ = bot.delete_message(chat_id, msg.id).await;
-        send_ephemeral(bot, chat_id, "Couldn't normalize.", ACK_TTL_SECS).await?;
+        delete_source_message(bot, &state.config, chat_id, msg.id).await;
+        send_ephemeral(
+            bot,
+            &state.config,
+            chat_id,
+            "Couldn't normalize.",
+            ACK_TTL_SECS,
+        )
+        .await?;
         return Ok(true);
     };
 
     let op = QueuedOp {
         kind: QueuedOpKind::UpdateEntry,
         entry: entry.block_string(),
-        resource_path: None,
+        resource_path: session_target_path(&session),
         updated_entry: Some(normalized_entry.block_string()),
+        position: None,
     };
 
     match apply_user_op(state, &op).await? {
         UserOpOutcome::Applied(ApplyOutcome::Applied) => {
             session.entries[target_index] = normalized_entry;
-            let (text, kb) =
-                render_list_view(&session.id, &session, &peeked_snapshot, &state.config);
-            if let Some(message_id) = session.message_id {
-                bot.edit_message_text(chat_id, message_id, text)
-                    .reply_markup(kb)
-                    .await?;
-            } else {
-                let sent = bot.send_message(chat_id, text).reply_markup(kb).await?;
-                session.message_id = Some(sent.id);
-            }
-            if let Err(err) =
-                refresh_embedded_media_for_view(bot, chat_id, state, &mut session, &peeked_snapshot)
-                    .await
-            {
-                error!("send embedded media failed: {:#}", err);
-            }
+            show_list_view(bot, chat_id, state, &mut session, &peeked_snapshot).await?;
         }
         UserOpOutcome::Applied(ApplyOutcome::NotFound)
-        | UserOpOutcome::Applied(ApplyOutcome::Duplicate) => {
-            send_ephemeral(bot, chat_id, "Couldn't normalize.", ACK_TTL_SECS).await?;
+        | UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+        | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {
+            send_ephemeral(
+                bot,
+                &state.config,
+                chat_id,
+                "Couldn't normalize.",
+                ACK_TTL_SECS,
+            )
+            .await?;
         }
         UserOpOutcome::Queued => {
-            send_error(bot, chat_id, "Write failed; queued for retry.").await?;
+            send_error(
+                bot,
+                &state.config,
+                chat_id,
+                "Write failed; queued for retry.",
+            )
+            .await?;
         }
     }
 
@@ -442,7 +765,7 @@ async fn handle_norm_message(
         .lock()
         .await
         .insert(session.id.clone(), session);
-    let _ = bot.delete_message(chat_id, msg.id).await;
+    delete_source_message(bot, &state.config, chat_id, msg.id).await;
     Ok(true)
 }
 
@@ -470,18 +793,26 @@ async fn handle_instant_delete_message(
         state.sessions.lock().await.insert(session_id, session);
         return Ok(false);
     }
+    session.last_activity = now_ts();
 
     let peeked_snapshot = state.peeked.lock().await.clone();
-    let target_index = match norm_target_index(&session, &peeked_snapshot) {
-        Some(index) => index,
-        None => {
+    let target_index = match norm_target_index(&session, &peeked_snapshot, state.config.page_size) {
+        Some(index) if !matches!(&session.kind, SessionKind::Resource { .. }) => index,
+        _ => {
             state
                 .sessions
                 .lock()
                 .await
                 .insert(session.id.clone(), session);
-            let _ = bot.delete_message(chat_id, msg.id).await;
-            send_ephemeral(bot, chat_id, "Couldn't delete.", ACK_TTL_SECS).await?;
+            delete_source_message(bot, &state.config, chat_id, msg.id).await;
+            send_ephemeral(
+                bot,
+                &state.config,
+                chat_id,
+                "Couldn't delete.",
+                ACK_TTL_SECS,
+            )
+            .await?;
             return Ok(true);
         }
     };
@@ -494,8 +825,15 @@ async fn handle_instant_delete_message(
                 .lock()
                 .await
                 .insert(session.id.clone(), session);
-            let _ = bot.delete_message(chat_id, msg.id).await;
-            send_ephemeral(bot, chat_id, "Couldn't delete.", ACK_TTL_SECS).await?;
+            delete_source_message(bot, &state.config, chat_id, msg.id).await;
+            send_ephemeral(
+                bot,
+                &state.config,
+                chat_id,
+                "Couldn't delete.",
+                ACK_TTL_SECS,
+            )
+            .await?;
             return Ok(true);
         }
     };
@@ -505,39 +843,44 @@ async fn handle_instant_delete_message(
         entry: entry_block,
         resource_path: None,
         updated_entry: None,
+        position: None,
     };
 
     match apply_user_op(state, &op).await? {
         UserOpOutcome::Applied(ApplyOutcome::Applied) => {
-            session.entries.remove(target_index);
+            let entry = session.entries.remove(target_index);
             if let ListView::Selected { return_to, .. } = session.view.clone() {
                 session.view = *return_to;
             }
-            let _ = add_undo(state, UndoKind::Delete, op.entry.clone()).await?;
-            normalize_peek_view(&mut session, &peeked_snapshot);
-            let (text, kb) =
-                render_list_view(&session.id, &session, &peeked_snapshot, &state.config);
-            if let Some(message_id) = session.message_id {
-                bot.edit_message_text(chat_id, message_id, text)
-                    .reply_markup(kb)
-                    .await?;
-            } else {
-                let sent = bot.send_message(chat_id, text).reply_markup(kb).await?;
-                session.message_id = Some(sent.id);
-            }
-            if let Err(err) =
-                refresh_embedded_media_for_view(bot, chat_id, state, &mut session, &peeked_snapshot)
-                    .await
-            {
-                error!("send embedded media failed: {:#}", err);
-            }
+            let undo_id = add_undo(state, UndoKind::Delete, op.entry.clone()).await?;
+            session.session_undo.push(SessionUndo {
+                undo_id,
+                entry,
+                index: target_index,
+            });
+            normalize_peek_view(&mut session, &peeked_snapshot, state.config.page_size);
+            show_list_view(bot, chat_id, state, &mut session, &peeked_snapshot).await?;
         }
         UserOpOutcome::Applied(ApplyOutcome::NotFound)
-        | UserOpOutcome::Applied(ApplyOutcome::Duplicate) => {
-            send_ephemeral(bot, chat_id, "Couldn't delete.", ACK_TTL_SECS).await?;
+        | UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+        | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {
+            send_ephemeral(
+                bot,
+                &state.config,
+                chat_id,
+                "Couldn't delete.",
+                ACK_TTL_SECS,
+            )
+            .await?;
         }
         UserOpOutcome::Queued => {
-            send_error(bot, chat_id, "Write failed; queued for retry.").await?;
+            send_error(
+                bot,
+                &state.config,
+                chat_id,
+                "Write failed; queued for retry.",
+            )
+            .await?;
         }
     }
 
@@ -546,7 +889,7 @@ async fn handle_instant_delete_message(
         .lock()
         .await
         .insert(session.id.clone(), session);
-    let _ = bot.delete_message(chat_id, msg.id).await;
+    delete_source_message(bot, &state.config, chat_id, msg.id).await;
     Ok(true)
 }
 
@@ -574,10 +917,15 @@ async fn handle_list_command(
         seen_random: HashSet::new(),
         message_id: None,
         sent_media_message_ids: Vec::new(),
+        hide_media: false,
+        session_undo: Vec::new(),
+        last_activity: now_ts(),
     };
 
     let (text, kb) = build_menu_view(&session_id, &session);
-    let sent = bot.send_message(msg.chat.id, text).reply_markup(kb).await?;
+    let sent = send(&bot, &state.config, msg.chat.id, text)
+        .reply_markup(kb)
+        .await?;
     session.message_id = Some(sent.id);
     state
         .sessions
@@ -599,11 +947,73 @@ async fn handle_quick_select_command(
     mode: QuickSelectMode,
 ) -> Result<()> {
     let entries = read_entries(&state.config.read_later_path)?.1;
-    let Some(index) = quick_select_index(entries.len(), mode) else {
-        send_ephemeral(&bot, msg.chat.id, "Read Later is empty.", ACK_TTL_SECS).await?;
+    let index = if matches!(mode, QuickSelectMode::Random) && state.config.random_skip_archived {
+        let candidates = random_candidates(&entries, &HashSet::new(), &HashSet::new(), true);
+        candidates.choose(&mut rand::thread_rng()).copied()
+    } else {
+        quick_select_index(entries.len(), mode)
+    };
+    let Some(index) = index else {
+        send_ephemeral(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "Read Later is empty.",
+            ACK_TTL_SECS,
+        )
+        .await?;
+        return Ok(());
+    };
+    let random = matches!(mode, QuickSelectMode::Random);
+    open_selected_session(&bot, &msg, &state, entries, index, random).await
+}
+
+async fn handle_oldest_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    if entries.is_empty() {
+        send_ephemeral(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "Read Later is empty.",
+            ACK_TTL_SECS,
+        )
+        .await?;
+        return Ok(());
+    }
+    let index = {
+        let peeked = state.peeked.lock().await;
+        ordered_unpeeked_indices(&entries, &peeked, ListMode::Bottom)
+            .first()
+            .copied()
+    };
+    let Some(index) = index else {
+        send_ephemeral(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "Everything's been peeked already.",
+            ACK_TTL_SECS,
+        )
+        .await?;
         return Ok(());
     };
+    open_selected_session(&bot, &msg, &state, entries, index, false).await
+}
 
+/// Opens a list session straight on the selected item, marking it peeked.
+async fn open_selected_session(
+    bot: &Bot,
+    msg: &Message,
+    state: &std::sync::Arc<AppState>,
+    entries: Vec<EntryBlock>,
+    index: usize,
+    random: bool,
+) -> Result<()> {
     let session_id = short_id();
     let mut session = ListSession {
         id: session_id.clone(),
@@ -617,25 +1027,20 @@ async fn handle_quick_select_command(
         seen_random: HashSet::new(),
         message_id: None,
         sent_media_message_ids: Vec::new(),
+        hide_media: false,
+        session_undo: Vec::new(),
+        last_activity: now_ts(),
     };
 
-    if matches!(mode, QuickSelectMode::Random) {
+    if random {
         session.seen_random.insert(index);
     }
     if let Some(entry) = session.entries.get(index) {
-        state.peeked.lock().await.insert(entry.block_string());
+        mark_peeked(state, entry).await?;
     }
 
     let peeked_snapshot = state.peeked.lock().await.clone();
-    let (text, kb) = render_list_view(&session_id, &session, &peeked_snapshot, &state.config);
-    let sent = bot.send_message(msg.chat.id, text).reply_markup(kb).await?;
-    session.message_id = Some(sent.id);
-    if let Err(err) =
-        refresh_embedded_media_for_view(&bot, msg.chat.id, &state, &mut session, &peeked_snapshot)
-            .await
-    {
-        error!("send embedded media failed: {:#}", err);
-    }
+    show_list_view(bot, msg.chat.id, state, &mut session, &peeked_snapshot).await?;
     state
         .sessions
         .lock()
@@ -656,33 +1061,108 @@ async fn handle_search_command(
     query: &str,
 ) -> Result<()> {
     let entries = read_entries(&state.config.read_later_path)?.1;
-    let matches = search_entries(&entries, query);
-
-    if matches.is_empty() {
-        send_ephemeral(&bot, msg.chat.id, "No matches.", ACK_TTL_SECS).await?;
-        return Ok(());
-    }
-
-    let session_id = short_id();
-    let mut session = ListSession {
-        id: session_id.clone(),
-        chat_id: msg.chat.id.0,
-        kind: SessionKind::Search {
-            query: query.to_string(),
-        },
-        entries: matches,
-        view: ListView::Peek {
-            mode: ListMode::Top,
-            page: 0,
+    let matches = match query.strip_prefix("re:") {
+        Some(pattern) => match regex_search_entries(&entries, pattern.trim()) {
+            Ok(matches) => matches,
+            Err(err) => {
+                let text = format!("Invalid pattern: {}", err);
+                send_error(&bot, &state.config, msg.chat.id, &text).await?;
+                return Ok(());
+            }
         },
-        seen_random: HashSet::new(),
-        message_id: None,
-        sent_media_message_ids: Vec::new(),
+        None => search_entries(&entries, query),
     };
+    open_search_session(&bot, &msg, &state, query, matches).await
+}
 
-    let peeked_snapshot = state.peeked.lock().await.clone();
-    let (text, kb) = render_list_view(&session_id, &session, &peeked_snapshot, &state.config);
-    let sent = bot.send_message(msg.chat.id, text).reply_markup(kb).await?;
+async fn handle_length_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+    rest: &str,
+    short: bool,
+) -> Result<()> {
+    let default = if short {
+        SHORT_ENTRY_MAX_LINES
+    } else {
+        LONG_ENTRY_MIN_LINES
+    };
+    let limit = if rest.is_empty() {
+        default
+    } else {
+        match rest.parse::<usize>() {
+            Ok(limit) if limit > 0 => limit,
+            _ => {
+                send_error(
+                    &bot,
+                    &state.config,
+                    msg.chat.id,
+                    "Provide a positive number.",
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+    };
+
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let (label, matches): (String, Vec<EntryBlock>) = if short {
+        (
+            format!("at most {} lines", limit),
+            entries
+                .into_iter()
+                .filter(|entry| is_short_entry(entry, limit))
+                .collect(),
+        )
+    } else {
+        (
+            format!("at least {} lines", limit),
+            entries
+                .into_iter()
+                .filter(|entry| is_long_entry(entry, limit))
+                .collect(),
+        )
+    };
+    open_search_session(&bot, &msg, &state, &label, matches).await
+}
+
+async fn open_search_session(
+    bot: &Bot,
+    msg: &Message,
+    state: &std::sync::Arc<AppState>,
+    query: &str,
+    matches: Vec<EntryBlock>,
+) -> Result<()> {
+    if matches.is_empty() {
+        send_ephemeral(bot, &state.config, msg.chat.id, "No matches.", ACK_TTL_SECS).await?;
+        return Ok(());
+    }
+
+    let session_id = short_id();
+    let mut session = ListSession {
+        id: session_id.clone(),
+        chat_id: msg.chat.id.0,
+        kind: SessionKind::Search {
+            query: query.to_string(),
+        },
+        entries: matches,
+        view: ListView::Peek {
+            mode: ListMode::Top,
+            page: 0,
+        },
+        seen_random: HashSet::new(),
+        message_id: None,
+        sent_media_message_ids: Vec::new(),
+        hide_media: false,
+        session_undo: Vec::new(),
+        last_activity: now_ts(),
+    };
+
+    let peeked_snapshot = state.peeked.lock().await.clone();
+    let (text, kb) = render_list_view(&session_id, &session, &peeked_snapshot, &state.config);
+    let sent = send(bot, &state.config, msg.chat.id, text)
+        .reply_markup(kb)
+        .await?;
     session.message_id = Some(sent.id);
     state
         .sessions
@@ -697,6 +1177,84 @@ async fn handle_search_command(
     Ok(())
 }
 
+async fn handle_untitled_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let matches: Vec<EntryBlock> = read_entries(&state.config.read_later_path)?
+        .1
+        .into_iter()
+        .filter(is_bare_link_entry)
+        .collect();
+    open_search_session(&bot, &msg, &state, "untitled", matches).await?;
+    Ok(())
+}
+
+async fn handle_due_today_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let matches = due_entries(entries, Local::now().date_naive());
+    open_search_session(&bot, &msg, &state, "due today", matches).await?;
+    Ok(())
+}
+
+async fn handle_media_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let matches: Vec<EntryBlock> = read_entries(&state.config.read_later_path)?
+        .1
+        .into_iter()
+        .filter(has_embedded_media)
+        .collect();
+    open_search_session(&bot, &msg, &state, "media", matches).await?;
+    Ok(())
+}
+
+async fn handle_resources_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let files = list_resource_files(&state.config.resources_path)?;
+    if files.is_empty() {
+        send_ephemeral(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "No resource files found.",
+            ACK_TTL_SECS,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let browser_id = short_id();
+    let kb = build_resource_browser_keyboard(&browser_id, &files);
+    let sent = send(
+        &bot,
+        &state.config,
+        msg.chat.id,
+        "Choose a resource file to browse:",
+    )
+    .reply_markup(kb)
+    .await?;
+    state.resource_browsers.lock().await.insert(
+        browser_id,
+        ResourceBrowserState {
+            chat_id: msg.chat.id.0,
+            message_id: sent.id,
+            files,
+        },
+    );
+    Ok(())
+}
+
 async fn handle_download_command(
     bot: Bot,
     msg: Message,
@@ -704,10 +1262,10 @@ async fn handle_download_command(
     rest: &str,
 ) -> Result<()> {
     let links = if !rest.trim().is_empty() {
-        extract_links(rest)
+        extract_links(rest, state.config.min_link_length)
     } else {
         match active_entry_text(&state, msg.chat.id.0).await {
-            Some(text) => extract_links(&text),
+            Some(text) => extract_links(&text, state.config.min_link_length),
             None => Vec::new(),
         }
     };
@@ -729,24 +1287,12 @@ async fn active_entry_text(state: &std::sync::Arc<AppState>, chat_id: i64) -> Op
         return None;
     }
     let peeked_snapshot = state.peeked.lock().await.clone();
-    match &session.view {
-        ListView::Selected { index, .. } => session
-            .entries
-            .get(*index)
-            .map(|entry| entry.display_lines().join("\n")),
-        ListView::Peek { mode, page } => {
-            let indices = peek_indices_for_session(&session, &peeked_snapshot, *mode, *page);
-            if indices.len() == 1 {
-                session
-                    .entries
-                    .get(indices[0])
-                    .map(|entry| entry.display_lines().join("\n"))
-            } else {
-                None
-            }
-        }
-        _ => None,
-    }
+    view_entry_text(
+        &session,
+        &peeked_snapshot,
+        state.config.download_scan_active_page,
+        state.config.page_size,
+    )
 }
 
 async fn handle_push_command(
@@ -757,8 +1303,21 @@ async fn handle_push_command(
     let Some(sync) = state.config.sync.clone() else {
         send_error(
             &bot,
+            &state.config,
+            msg.chat.id,
+            "Sync not configured. Set settings.sync.repo_path (and settings.sync.token_file for HTTPS remotes).",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let Some(_sync_guard) = try_acquire_sync(&state.sync_in_progress) else {
+        send_ephemeral(
+            &bot,
+            &state.config,
             msg.chat.id,
-            "Sync not configured. Set settings.sync.repo_path and settings.sync.token_file.",
+            "Sync already running.",
+            ACK_TTL_SECS,
         )
         .await?;
         return Ok(());
@@ -771,19 +1330,186 @@ async fn handle_push_command(
 
     match outcome {
         Ok(PushOutcome::NoChanges) => {
-            send_ephemeral(&bot, chat_id, "Nothing to sync.", ACK_TTL_SECS).await?;
+            send_ephemeral(
+                &bot,
+                &state.config,
+                chat_id,
+                "Nothing to sync.",
+                ACK_TTL_SECS,
+            )
+            .await?;
         }
         Ok(PushOutcome::Pushed) => {
-            send_ephemeral(&bot, chat_id, "Synced.", ACK_TTL_SECS).await?;
+            send_ephemeral(&bot, &state.config, chat_id, "Synced.", ACK_TTL_SECS).await?;
+        }
+        Err(err) => {
+            send_error(&bot, &state.config, chat_id, &err.to_string()).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_sync_status_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let Some(sync) = state.config.sync.clone() else {
+        send_error(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "Sync not configured. Set settings.sync.repo_path (and settings.sync.token_file for HTTPS remotes).",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let Some(_sync_guard) = try_acquire_sync(&state.sync_in_progress) else {
+        send_ephemeral(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "Sync already running.",
+            ACK_TTL_SECS,
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let outcome = tokio::task::spawn_blocking(move || run_sync_status(&sync))
+        .await
+        .context("status task failed")?;
+    match outcome {
+        Ok(status) => {
+            send_ephemeral(
+                &bot,
+                &state.config,
+                msg.chat.id,
+                &build_sync_status_text(&status),
+                INFO_TTL_SECS,
+            )
+            .await?;
         }
         Err(err) => {
-            send_error(&bot, chat_id, &err.to_string()).await?;
+            send_error(&bot, &state.config, msg.chat.id, &err.to_string()).await?;
         }
     }
+    Ok(())
+}
+
+async fn handle_check_links_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let mut seen = HashSet::new();
+    let links: Vec<String> = entries
+        .iter()
+        .flat_map(|entry| extract_links(&entry.block_string(), state.config.min_link_length))
+        .filter(|link| seen.insert(link.clone()))
+        .collect();
+    if links.is_empty() {
+        send_ephemeral(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "No links found.",
+            ACK_TTL_SECS,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let status = send(
+        &bot,
+        &state.config,
+        msg.chat.id,
+        format!("Checking {} links...", links.len()),
+    )
+    .await?;
+    let client = link_check_client(state.config.link_check_timeout_ms)?;
+    let results = check_links_bounded(links, state.config.link_check_concurrency, |link| {
+        check_link(client.clone(), link)
+    })
+    .await;
+    let _ = bot.delete_message(msg.chat.id, status.id).await;
+    let text = build_broken_links_text(&results);
+    send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_chart_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let mut entries = read_entries(&state.config.read_later_path)?.1;
+    entries.extend(read_entries(&state.config.finished_path)?.1);
+    if !entries
+        .iter()
+        .any(|entry| entry_added_date(entry).is_some())
+    {
+        send_error(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "No dated entries. Enable track_added_dates to record when items are added.",
+        )
+        .await?;
+        return Ok(());
+    }
 
+    let series = chart_series(&entries, Local::now().date_naive());
+    let total: usize = series.iter().map(|(_, count)| count).sum();
+    let caption = format!("Added per day, last {} days: {}", CHART_DAYS, total);
+    let png = match render_chart_png(&series) {
+        Ok(png) => png,
+        Err(err) => {
+            error!("render chart failed: {:#}", err);
+            let text = format!("{}\n\n{}", caption, build_chart_text(&series));
+            send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+            return Ok(());
+        }
+    };
+    let photo = InputFile::memory(png).file_name("chart.png");
+    if let Err(err) = bot
+        .send_photo(msg.chat.id, photo)
+        .caption(caption.clone())
+        .await
+    {
+        error!("send chart failed: {}", err);
+        let text = format!("{}\n\n{}", caption, build_chart_text(&series));
+        send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    }
     Ok(())
 }
 
+async fn handle_refresh_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let outcome = match state.config.sync.clone() {
+        Some(sync) => match try_acquire_sync(&state.sync_in_progress) {
+            Some(_sync_guard) => Some(
+                tokio::task::spawn_blocking(move || run_pull(&sync, PullMode::FastForward))
+                    .await
+                    .context("pull task failed")?,
+            ),
+            None => Some(Err(anyhow!("sync already running"))),
+        },
+        None => None,
+    };
+
+    if let Some(warning) = refresh_pull_warning(outcome.as_ref()) {
+        send_error(&bot, &state.config, msg.chat.id, &warning).await?;
+    }
+    handle_list_command(bot, msg, state).await
+}
+
 async fn handle_pull_command(
     bot: Bot,
     msg: Message,
@@ -793,8 +1519,21 @@ async fn handle_pull_command(
     let Some(sync) = state.config.sync.clone() else {
         send_error(
             &bot,
+            &state.config,
+            msg.chat.id,
+            "Sync not configured. Set settings.sync.repo_path (and settings.sync.token_file for HTTPS remotes).",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let Some(_sync_guard) = try_acquire_sync(&state.sync_in_progress) else {
+        send_ephemeral(
+            &bot,
+            &state.config,
             msg.chat.id,
-            "Sync not configured. Set settings.sync.repo_path and settings.sync.token_file.",
+            "Sync already running.",
+            ACK_TTL_SECS,
         )
         .await?;
         return Ok(());
@@ -803,7 +1542,7 @@ async fn handle_pull_command(
     let mode = match parse_pull_mode(rest) {
         Ok(mode) => mode,
         Err(message) => {
-            send_error(&bot, msg.chat.id, &message).await?;
+            send_error(&bot, &state.config, msg.chat.id, &message).await?;
             return Ok(());
         }
     };
@@ -815,13 +1554,20 @@ async fn handle_pull_command(
 
     match outcome {
         Ok(PullOutcome::UpToDate) => {
-            send_ephemeral(&bot, chat_id, "Already up to date.", ACK_TTL_SECS).await?;
+            send_ephemeral(
+                &bot,
+                &state.config,
+                chat_id,
+                "Already up to date.",
+                ACK_TTL_SECS,
+            )
+            .await?;
         }
         Ok(PullOutcome::Pulled) => {
-            send_ephemeral(&bot, chat_id, "Pulled.", ACK_TTL_SECS).await?;
+            send_ephemeral(&bot, &state.config, chat_id, "Pulled.", ACK_TTL_SECS).await?;
         }
         Err(err) => {
-            send_error(&bot, chat_id, &err.to_string()).await?;
+            send_error(&bot, &state.config, chat_id, &err.to_string()).await?;
         }
     }
 
@@ -836,8 +1582,21 @@ async fn handle_sync_command(
     let Some(sync) = state.config.sync.clone() else {
         send_error(
             &bot,
+            &state.config,
+            msg.chat.id,
+            "Sync not configured. Set settings.sync.repo_path (and settings.sync.token_file for HTTPS remotes).",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let Some(_sync_guard) = try_acquire_sync(&state.sync_in_progress) else {
+        send_ephemeral(
+            &bot,
+            &state.config,
             msg.chat.id,
-            "Sync not configured. Set settings.sync.repo_path and settings.sync.token_file.",
+            "Sync already running.",
+            ACK_TTL_SECS,
         )
         .await?;
         return Ok(());
@@ -850,13 +1609,20 @@ async fn handle_sync_command(
 
     match outcome {
         Ok(SyncOutcome::Synced) => {
-            send_ephemeral(&bot, chat_id, "Synced.", ACK_TTL_SECS).await?;
+            send_ephemeral(&bot, &state.config, chat_id, "Synced.", ACK_TTL_SECS).await?;
         }
         Ok(SyncOutcome::NoChanges) => {
-            send_ephemeral(&bot, chat_id, "Nothing to sync.", ACK_TTL_SECS).await?;
+            send_ephemeral(
+                &bot,
+                &state.config,
+                chat_id,
+                "Nothing to sync.",
+                ACK_TTL_SECS,
+            )
+            .await?;
         }
         Err(err) => {
-            send_error(&bot, chat_id, &err.to_string()).await?;
+            send_error(&bot, &state.config, chat_id, &err.to_string()).await?;
         }
     }
 
@@ -871,6 +1637,7 @@ async fn handle_sync_x_command(
     if state.config.sync_x.is_none() {
         send_error(
             &bot,
+            &state.config,
             msg.chat.id,
             "sync_x not configured. Set settings.sync_x.source_project_path (and optionally settings.sync_x.python_bin/work_dir).",
         )
@@ -880,7 +1647,7 @@ async fn handle_sync_x_command(
 
     let prompt_text =
         "Paste the Cloudflare cookie header string from x.com (must include auth_token and ct0).";
-    let sent = bot.send_message(msg.chat.id, prompt_text).await?;
+    let sent = send(&bot, &state.config, msg.chat.id, prompt_text).await?;
     state.sync_x_cookie_prompts.lock().await.insert(
         msg.chat.id.0,
         SyncXCookiePrompt {
@@ -903,6 +1670,7 @@ async fn handle_sync_x_cookie_response(
     if cookie_header.is_empty() {
         send_error(
             bot,
+            &state.config,
             chat_id,
             "Cookie header is empty. Paste the full header string.",
         )
@@ -914,14 +1682,14 @@ async fn handle_sync_x_cookie_response(
                 expires_at: now_ts() + SYNC_X_PROMPT_TTL_SECS,
             },
         );
-        let _ = bot.delete_message(chat_id, message_id).await;
+        delete_source_message(bot, &state.config, chat_id, message_id).await;
         return Ok(());
     }
 
     let _ = bot.delete_message(chat_id, prompt.prompt_message_id).await;
-    let _ = bot.delete_message(chat_id, message_id).await;
+    delete_source_message(bot, &state.config, chat_id, message_id).await;
 
-    let status_msg = bot.send_message(chat_id, "Syncing X bookmarks...").await?;
+    let status_msg = send(bot, &state.config, chat_id, "Syncing X bookmarks...").await?;
     let config = state.config.clone();
     let cookie_header = cookie_header.to_string();
     let outcome = tokio::task::spawn_blocking(move || run_sync_x(&config, &cookie_header))
@@ -929,56 +1697,967 @@ async fn handle_sync_x_cookie_response(
         .context("sync_x task failed")?;
     let _ = bot.delete_message(chat_id, status_msg.id).await;
 
-    match outcome {
-        Ok(sync_outcome) => {
-            if sync_outcome.extracted_count == 0 {
-                send_ephemeral(bot, chat_id, "No X bookmarks found.", ACK_TTL_SECS).await?;
-            } else {
-                let text = format!(
-                    "X sync complete: extracted {}, added {}, skipped {} duplicates.",
-                    sync_outcome.extracted_count,
-                    sync_outcome.added_count,
-                    sync_outcome.duplicate_count
-                );
-                send_message_with_delete_button(bot, chat_id, text).await?;
+    match outcome {
+        Ok(sync_outcome) => {
+            if sync_outcome.extracted_count == 0 {
+                send_ephemeral(
+                    bot,
+                    &state.config,
+                    chat_id,
+                    "No X bookmarks found.",
+                    ACK_TTL_SECS,
+                )
+                .await?;
+            } else {
+                let text = format!(
+                    "X sync complete: extracted {}, added {}, skipped {} duplicates.",
+                    sync_outcome.extracted_count,
+                    sync_outcome.added_count,
+                    sync_outcome.duplicate_count
+                );
+                send_message_with_delete_button(bot, &state.config, chat_id, text).await?;
+            }
+        }
+        Err(err) => {
+            send_error(
+                bot,
+                &state.config,
+                chat_id,
+                &format!("sync_x failed: {}", err),
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_progress_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let peeked_snapshot = state.peeked.lock().await.clone();
+    let reviewed = count_reviewed_entries(&entries, &peeked_snapshot);
+    let text = build_progress_text(reviewed, entries.len());
+    send_ephemeral(&bot, &state.config, msg.chat.id, &text, INFO_TTL_SECS).await?;
+    Ok(())
+}
+
+async fn handle_top_domains_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+    rest: &str,
+) -> Result<()> {
+    let limit = if rest.is_empty() {
+        TOP_DOMAINS_DEFAULT
+    } else {
+        match rest.parse::<usize>() {
+            Ok(limit) if limit > 0 => limit,
+            _ => {
+                send_error(
+                    &bot,
+                    &state.config,
+                    msg.chat.id,
+                    "Provide a positive number.",
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+    };
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let text = build_top_domains_text(&top_domains(&entries, limit));
+    send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_norm_all_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let changed = entries
+        .iter()
+        .filter(|entry| normalize_entry_markdown_links(entry).is_some())
+        .count();
+    if changed == 0 {
+        send_ephemeral(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "No links to normalize.",
+            INFO_TTL_SECS,
+        )
+        .await?;
+        return Ok(());
+    }
+    let op = QueuedOp {
+        kind: QueuedOpKind::NormalizeAll,
+        entry: String::new(),
+        resource_path: None,
+        updated_entry: None,
+        position: None,
+    };
+
+    match apply_user_op(&state, &op).await? {
+        UserOpOutcome::Applied(_) => {
+            let text = format!("Normalized links in {} items.", changed);
+            send_ephemeral(&bot, &state.config, msg.chat.id, &text, INFO_TTL_SECS).await?;
+        }
+        UserOpOutcome::Queued => {
+            send_error(
+                &bot,
+                &state.config,
+                msg.chat.id,
+                "Write failed; queued for retry.",
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn handle_tidy_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let previews: Vec<String> = entries
+        .iter()
+        .filter(|entry| collapse_blank_lines(entry).is_some())
+        .map(|entry| entry.preview_lines().remove(0))
+        .collect();
+    if previews.is_empty() {
+        send_ephemeral(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "Nothing to tidy.",
+            INFO_TTL_SECS,
+        )
+        .await?;
+        return Ok(());
+    }
+    let op = QueuedOp {
+        kind: QueuedOpKind::Tidy,
+        entry: String::new(),
+        resource_path: None,
+        updated_entry: None,
+        position: None,
+    };
+
+    match apply_user_op(&state, &op).await? {
+        UserOpOutcome::Applied(_) => {
+            let text = format!("Tidied {}:\n{}", previews.len(), previews.join("\n"));
+            send_ephemeral(&bot, &state.config, msg.chat.id, &text, INFO_TTL_SECS).await?;
+        }
+        UserOpOutcome::Queued => {
+            send_error(
+                &bot,
+                &state.config,
+                msg.chat.id,
+                "Write failed; queued for retry.",
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn handle_stats_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let read_later = read_entries(&state.config.read_later_path)?.1;
+    let finished = read_entries(&state.config.finished_path)?.1;
+    let mut resource_count = 0;
+    for path in list_resource_files(&state.config.resources_path)? {
+        resource_count += read_entries(&path)?.1.len();
+    }
+    let text = build_stats_text(
+        &read_later,
+        &finished,
+        resource_count,
+        state.config.min_link_length,
+        state.config.finished_order,
+    );
+    send_ephemeral(&bot, &state.config, msg.chat.id, &text, INFO_TTL_SECS).await?;
+    Ok(())
+}
+
+async fn handle_week_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let text = build_week_text(&entries, Local::now().date_naive());
+    send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_age_command(bot: Bot, msg: Message, state: std::sync::Arc<AppState>) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let text = build_age_text(&entries, Local::now().date_naive());
+    send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_tags_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let text = build_tags_text(&tag_counts(&entries));
+    send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_digest_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let peeked = state.peeked.lock().await.clone();
+    let text =
+        build_digest_text(&entries, &peeked).unwrap_or_else(|| "Nothing unpeeked.".to_string());
+    send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_queue_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let queue = state.queue.lock().await.clone();
+    let (text, kb) = build_queue_view(&queue, 0);
+    send(&bot, &state.config, msg.chat.id, text)
+        .reply_markup(kb)
+        .await?;
+    Ok(())
+}
+
+async fn handle_heatmap_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let mut entries = read_entries(&state.config.read_later_path)?.1;
+    entries.extend(read_entries(&state.config.finished_path)?.1);
+    let text = build_heatmap_text(&entries, Local::now().date_naive());
+    send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_linkheavy_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let text = build_link_heavy_text(&entries);
+    send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_near_duplicates_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let text = build_near_duplicates_text(&entries);
+    send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_verify_media_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let mut entries = read_entries(&state.config.read_later_path)?.1;
+    entries.extend(read_entries(&state.config.finished_path)?.1);
+    let text = build_verify_media_text(&find_media_problems(&entries, &state.config));
+    send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_orphans_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let read_later = read_entries(&state.config.read_later_path)?.0;
+    let finished = read_entries(&state.config.finished_path)?.0;
+    let text = build_orphans_text(&[("Read Later", read_later), ("Finished", finished)]);
+    send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_archive_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+    rest: &str,
+) -> Result<()> {
+    let days = match rest.parse::<i64>() {
+        Ok(days) if days > 0 => days,
+        _ => {
+            send_error(
+                &bot,
+                &state.config,
+                msg.chat.id,
+                "Provide a number of days, e.g. /archive 30.",
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    let cutoff = Local::now().date_naive() - chrono::Duration::days(days);
+    // Run the archive directly, rather than through `apply_user_op`, so the
+    // reply reports what the write itself moved.
+    let result = {
+        let _guard = state.write_lock.lock().await;
+        run_archive(&state, cutoff).await
+    };
+
+    match result {
+        Ok((moved, undated)) => {
+            let text = format!("Archived {}. Skipped {} without a date.", moved, undated);
+            send_ephemeral(&bot, &state.config, msg.chat.id, &text, INFO_TTL_SECS).await?;
+        }
+        Err(err) => {
+            error!("write failed: {:#}", err);
+            let op = QueuedOp {
+                kind: QueuedOpKind::Archive { cutoff },
+                entry: String::new(),
+                resource_path: None,
+                updated_entry: None,
+                position: None,
+            };
+            queue_op(&state, op).await?;
+            send_error(
+                &bot,
+                &state.config,
+                msg.chat.id,
+                "Write failed; queued for retry.",
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn handle_biggest_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+    rest: &str,
+) -> Result<()> {
+    let limit = if rest.is_empty() {
+        BIGGEST_DEFAULT
+    } else {
+        match rest.parse::<usize>() {
+            Ok(limit) if limit > 0 => limit,
+            _ => {
+                send_error(
+                    &bot,
+                    &state.config,
+                    msg.chat.id,
+                    "Provide a positive number.",
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+    };
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let text = build_biggest_text(&largest_entries(&entries, limit));
+    send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn handle_poll_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+    rest: &str,
+) -> Result<()> {
+    let count = if rest.is_empty() {
+        POLL_DEFAULT_OPTIONS
+    } else {
+        match rest.parse::<usize>() {
+            Ok(count) if count >= 2 => count,
+            _ => {
+                send_error(
+                    &bot,
+                    &state.config,
+                    msg.chat.id,
+                    "Provide a number of at least 2.",
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+    };
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let peeked_snapshot = state.peeked.lock().await.clone();
+    let options = pick_poll_options(&entries, &peeked_snapshot, count);
+    if options.len() < 2 {
+        send_error(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "Need at least two unpeeked items for a poll.",
+        )
+        .await?;
+        return Ok(());
+    }
+    bot.send_poll(msg.chat.id, "What should I read next?", options)
+        .await?;
+    Ok(())
+}
+
+async fn handle_undos_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let (records, undo_snapshot) = {
+        let mut undo = state.undo.lock().await;
+        prune_undo(&mut undo);
+        let snapshot = undo.clone();
+        (undo.clone(), snapshot)
+    };
+    save_undo(&state.undo_path, &undo_snapshot)?;
+
+    if records.is_empty() {
+        send_ephemeral(&bot, &state.config, msg.chat.id, "No undos.", ACK_TTL_SECS).await?;
+        return Ok(());
+    }
+
+    let session_id = short_id();
+    let (text, kb) = build_undos_view(&session_id, &records);
+    let sent = send(&bot, &state.config, msg.chat.id, text)
+        .reply_markup(kb)
+        .await?;
+    let session = UndoSession {
+        chat_id: msg.chat.id.0,
+        message_id: sent.id,
+        records,
+    };
+    state.undo_sessions.lock().await.insert(session_id, session);
+    Ok(())
+}
+
+async fn handle_trash_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let Some(trash_path) = state.config.trash_path.as_ref() else {
+        send_error(&bot, &state.config, msg.chat.id, "Trash is not configured.").await?;
+        return Ok(());
+    };
+    let entries = read_entries(trash_path)?.1;
+    if entries.is_empty() {
+        send_ephemeral(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "Trash is empty.",
+            ACK_TTL_SECS,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let session_id = short_id();
+    let (text, kb) = build_trash_view(&session_id, &entries);
+    let sent = send(&bot, &state.config, msg.chat.id, text)
+        .reply_markup(kb)
+        .await?;
+    let session = TrashSession {
+        chat_id: msg.chat.id.0,
+        message_id: sent.id,
+        entries,
+    };
+    state.trash_sessions.lock().await.insert(session_id, session);
+    Ok(())
+}
+
+async fn handle_find_duplicates_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let groups = duplicate_groups(&entries, state.config.dedupe_by);
+    if groups.is_empty() {
+        send_ephemeral(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "No duplicates found.",
+            ACK_TTL_SECS,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let session_id = short_id();
+    let (text, kb) = build_duplicates_view(&session_id, &groups, 0);
+    let sent = send(&bot, &state.config, msg.chat.id, text)
+        .reply_markup(kb)
+        .await?;
+    let session = DuplicatesSession {
+        chat_id: msg.chat.id.0,
+        message_id: sent.id,
+        groups,
+        page: 0,
+        last_activity: now_ts(),
+    };
+    state
+        .duplicate_sessions
+        .lock()
+        .await
+        .insert(session_id, session);
+    Ok(())
+}
+
+async fn handle_restore_media_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+    filename: &str,
+) -> Result<()> {
+    if !state.config.prune_media || state.config.trash_path.is_none() {
+        send_error(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "Media trash is not enabled.",
+        )
+        .await?;
+        return Ok(());
+    }
+
+    if filename.is_empty() {
+        let names = list_trashed_media(&state.config)?;
+        let text = if names.is_empty() {
+            "No trashed media.".to_string()
+        } else {
+            format!("Trashed media:\n{}", names.join("\n"))
+        };
+        send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+        return Ok(());
+    }
+
+    let result = {
+        let _guard = state.write_lock.lock().await;
+        restore_media_sync(&state.config, filename)
+    };
+    match result {
+        Ok(_) => {
+            send_ephemeral(
+                &bot,
+                &state.config,
+                msg.chat.id,
+                "Media restored.",
+                ACK_TTL_SECS,
+            )
+            .await?;
+        }
+        Err(err) => {
+            send_error(
+                &bot,
+                &state.config,
+                msg.chat.id,
+                &format!("Couldn't restore media: {}", err),
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn handle_recategorize_command(
+    bot: &Bot,
+    chat_id: ChatId,
+    state: &std::sync::Arc<AppState>,
+) -> Result<()> {
+    let active_id = state.active_sessions.lock().await.get(&chat_id.0).cloned();
+    let session = match active_id {
+        Some(id) => state.sessions.lock().await.get(&id).cloned(),
+        None => None,
+    };
+    let opened = match session {
+        Some(session) if !matches!(session.kind, SessionKind::Resource { .. }) => {
+            open_edit_prompt(bot, state, &session, EditMode::Category).await?
+        }
+        _ => false,
+    };
+    if !opened {
+        send_error(bot, &state.config, chat_id, "Select an item first.").await?;
+    }
+    Ok(())
+}
+
+/// Moves the item at `entry_index` to `position` in Read Later and returns
+/// where the session should now select it.
+async fn move_session_entry(
+    bot: &Bot,
+    chat_id: ChatId,
+    state: &std::sync::Arc<AppState>,
+    session: &mut ListSession,
+    entry_index: usize,
+    position: usize,
+) -> Result<usize> {
+    let block = session.entries[entry_index].block_string();
+    let result = {
+        let _guard = state.write_lock.lock().await;
+        move_to_position_sync(&state.config.read_later_path, &block, position)
+    };
+    match result {
+        Ok(Some(index)) => {
+            let text = format!("Moved to position {}.", index + 1);
+            send_ephemeral(bot, &state.config, chat_id, &text, ACK_TTL_SECS).await?;
+            if matches!(session.kind, SessionKind::List) {
+                return Ok(move_entry_to_position(
+                    &mut session.entries,
+                    entry_index,
+                    position,
+                ));
+            }
+        }
+        Ok(None) => {
+            send_error(bot, &state.config, chat_id, "Item not found.").await?;
+        }
+        Err(err) => {
+            error!("move to position failed: {:#}", err);
+            send_error(bot, &state.config, chat_id, "Write failed.").await?;
+        }
+    }
+    Ok(entry_index)
+}
+
+async fn handle_moveto_command(
+    bot: &Bot,
+    chat_id: ChatId,
+    state: &std::sync::Arc<AppState>,
+    rest: &str,
+) -> Result<()> {
+    let active_id = state.active_sessions.lock().await.get(&chat_id.0).cloned();
+    let session = match active_id {
+        Some(id) => state.sessions.lock().await.get(&id).cloned(),
+        None => None,
+    };
+    let selected = session.and_then(|session| match &session.view {
+        ListView::Selected { return_to, index }
+            if !matches!(session.kind, SessionKind::Resource { .. })
+                && *index < session.entries.len() =>
+        {
+            let target = ((**return_to).clone(), *index);
+            Some((session, target))
+        }
+        _ => None,
+    });
+    let Some((mut session, (return_to, index))) = selected else {
+        send_error(bot, &state.config, chat_id, "Select an item first.").await?;
+        return Ok(());
+    };
+    let Some(position) = parse_position(rest) else {
+        open_edit_prompt(bot, state, &session, EditMode::Position).await?;
+        return Ok(());
+    };
+
+    session.last_activity = now_ts();
+    let index = move_session_entry(bot, chat_id, state, &mut session, index, position).await?;
+    session.view = ListView::Selected {
+        return_to: Box::new(return_to),
+        index,
+    };
+    let peeked_snapshot = state.peeked.lock().await.clone();
+    show_list_view(bot, chat_id, state, &mut session, &peeked_snapshot).await?;
+    state
+        .sessions
+        .lock()
+        .await
+        .insert(session.id.clone(), session);
+    Ok(())
+}
+
+async fn handle_fresh_command(
+    bot: &Bot,
+    chat_id: ChatId,
+    state: &std::sync::Arc<AppState>,
+) -> Result<()> {
+    let active_id = state.active_sessions.lock().await.get(&chat_id.0).cloned();
+    let session = match active_id {
+        Some(id) => state.sessions.lock().await.remove(&id),
+        None => None,
+    };
+    let Some(mut session) = session else {
+        reset_peeked(state).await?;
+        send_ephemeral(bot, &state.config, chat_id, "Peeked cleared.", ACK_TTL_SECS).await?;
+        return Ok(());
+    };
+    session.last_activity = now_ts();
+
+    let peeked_snapshot = {
+        let mut peeked = state.peeked.lock().await;
+        start_fresh(&mut session, &mut peeked);
+        if let Err(err) = save_peeked(&state.peeked_path, &peeked) {
+            error!("save peeked failed: {:#}", err);
+        }
+        peeked.clone()
+    };
+    let result = show_list_view(bot, chat_id, state, &mut session, &peeked_snapshot).await;
+    state
+        .sessions
+        .lock()
+        .await
+        .insert(session.id.clone(), session);
+    result
+}
+
+async fn handle_unpeek_command(
+    bot: &Bot,
+    chat_id: ChatId,
+    state: &std::sync::Arc<AppState>,
+    query: &str,
+) -> Result<()> {
+    if query.is_empty() {
+        send_ephemeral(
+            bot,
+            &state.config,
+            chat_id,
+            "Usage: /unpeek <query>",
+            ACK_TTL_SECS,
+        )
+        .await?;
+        return Ok(());
+    }
+    let entries = read_entries(&state.config.read_later_path)?.1;
+    let result = {
+        let mut peeked = state.peeked.lock().await;
+        let result = unpeek_entry(&entries, &mut peeked, query);
+        if result == Ok(true) {
+            save_peeked(&state.peeked_path, &peeked)?;
+        }
+        result
+    };
+    match result {
+        Ok(true) => {
+            send_ephemeral(
+                bot,
+                &state.config,
+                chat_id,
+                "Marked as unpeeked.",
+                ACK_TTL_SECS,
+            )
+            .await?
+        }
+        Ok(false) => {
+            send_ephemeral(
+                bot,
+                &state.config,
+                chat_id,
+                "Item wasn't peeked.",
+                ACK_TTL_SECS,
+            )
+            .await?
+        }
+        Err(message) => send_error(bot, &state.config, chat_id, &message).await?,
+    }
+    Ok(())
+}
+
+async fn handle_compact_undo_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let (removed, remaining) = {
+        let _guard = state.write_lock.lock().await;
+        let (_, read_later) = read_entries(&state.config.read_later_path)?;
+        let (_, finished) = read_entries(&state.config.finished_path)?;
+        let mut undo = state.undo.lock().await;
+        let removed = compact_undo(&mut undo, &read_later, &finished);
+        save_undo(&state.undo_path, &undo)?;
+        (removed, undo.len())
+    };
+    let text = format!(
+        "Undo log compacted: removed {}, {} left.",
+        removed, remaining
+    );
+    send_ephemeral(&bot, &state.config, msg.chat.id, &text, INFO_TTL_SECS).await?;
+    Ok(())
+}
+
+async fn handle_random_finished_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+) -> Result<()> {
+    let entries = read_entries(&state.config.finished_path)?.1;
+    let Some(index) = quick_select_index(entries.len(), QuickSelectMode::Random) else {
+        send_ephemeral(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "Finished is empty.",
+            INFO_TTL_SECS,
+        )
+        .await?;
+        return Ok(());
+    };
+    let entry = entries[index].clone();
+    let pick_id = short_id();
+    let (text, kb) = build_finished_pick_view(&pick_id, &entry, &state.config);
+    let sent = send(&bot, &state.config, msg.chat.id, text)
+        .reply_markup(kb)
+        .await?;
+    let pick = FinishedPick {
+        chat_id: msg.chat.id.0,
+        message_id: sent.id,
+        entry,
+    };
+    state.finished_picks.lock().await.insert(pick_id, pick);
+    Ok(())
+}
+
+async fn handle_export_command(
+    bot: Bot,
+    msg: Message,
+    state: std::sync::Arc<AppState>,
+    rest: &str,
+) -> Result<()> {
+    let (title, sections) = match rest.trim().to_ascii_lowercase().as_str() {
+        "" => {
+            let entries = read_entries(&state.config.read_later_path)?.1;
+            ("Read Later", vec![(String::new(), entries)])
+        }
+        "finished" => {
+            let entries = read_entries(&state.config.finished_path)?.1;
+            ("Finished", vec![(String::new(), entries)])
+        }
+        "resources" => {
+            let mut sections = Vec::new();
+            for path in list_resource_files(&state.config.resources_path)? {
+                sections.push((resource_file_label(&path), read_entries(&path)?.1));
             }
+            ("Resources", sections)
         }
-        Err(err) => {
-            send_error(bot, chat_id, &format!("sync_x failed: {}", err)).await?;
+        _ => {
+            send_error(
+                &bot,
+                &state.config,
+                msg.chat.id,
+                "Usage: /export [finished|resources]",
+            )
+            .await?;
+            return Ok(());
         }
+    };
+    if sections.iter().all(|(_, entries)| entries.is_empty()) {
+        send_ephemeral(
+            &bot,
+            &state.config,
+            msg.chat.id,
+            "Nothing to export.",
+            INFO_TTL_SECS,
+        )
+        .await?;
+        return Ok(());
     }
 
+    let text = build_export_markdown(title, &sections);
+    if text.chars().count() <= EXPORT_MESSAGE_LIMIT {
+        send_message_with_delete_button(&bot, &state.config, msg.chat.id, text).await?;
+    } else {
+        let filename = format!(
+            "{}-{}.md",
+            title.to_ascii_lowercase().replace(' ', "-"),
+            Local::now().format("%Y-%m-%d")
+        );
+        bot.send_document(
+            msg.chat.id,
+            InputFile::memory(text.into_bytes()).file_name(filename),
+        )
+        .await?;
+    }
     Ok(())
 }
 
-async fn handle_undos_command(
+async fn handle_snapshot_command(
     bot: Bot,
     msg: Message,
     state: std::sync::Arc<AppState>,
 ) -> Result<()> {
-    let (records, undo_snapshot) = {
-        let mut undo = state.undo.lock().await;
-        prune_undo(&mut undo);
-        let snapshot = undo.clone();
-        (undo.clone(), snapshot)
+    let snapshot = {
+        let _guard = state.write_lock.lock().await;
+        let queue = state.queue.lock().await.clone();
+        let undo = state.undo.lock().await.clone();
+        build_snapshot(&state.config, &queue, &undo)?
     };
-    save_undo(&state.undo_path, &undo_snapshot)?;
+    let data = serialize_snapshot(&snapshot)?;
+    let filename = format!("bookkeeper-snapshot-{}.json", snapshot.created_at);
+    bot.send_document(msg.chat.id, InputFile::memory(data).file_name(filename))
+        .await?;
+    Ok(())
+}
 
-    if records.is_empty() {
-        send_ephemeral(&bot, msg.chat.id, "No undos.", ACK_TTL_SECS).await?;
-        return Ok(());
-    }
+/// Treats an uploaded JSON document as a snapshot and asks before restoring it.
+/// Returns false when the file is not a snapshot so it is saved like any other.
+async fn handle_snapshot_upload(
+    bot: &Bot,
+    msg: &Message,
+    state: &std::sync::Arc<AppState>,
+    path: &Path,
+) -> Result<bool> {
+    let data = fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    let snapshot = match parse_snapshot(&data) {
+        Ok(snapshot) => snapshot,
+        Err(_) => return Ok(false),
+    };
+    fs::remove_file(path).with_context(|| format!("remove {}", path.display()))?;
 
-    let session_id = short_id();
-    let (text, kb) = build_undos_view(&session_id, &records);
-    let sent = bot.send_message(msg.chat.id, text).reply_markup(kb).await?;
-    let session = UndoSession {
+    let prompt_id = short_id();
+    let text = format!(
+        "Restore this snapshot? It replaces Read Later ({} items), Finished ({} items), the retry queue and the undo log.",
+        parse_entries(&snapshot.read_later).1.len(),
+        parse_entries(&snapshot.finished).1.len()
+    );
+    let kb = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback("Restore", format!("snap:{}:confirm", prompt_id)),
+        InlineKeyboardButton::callback("Cancel", format!("snap:{}:cancel", prompt_id)),
+    ]]);
+    let sent = send(bot, &state.config, msg.chat.id, text)
+        .reply_markup(kb)
+        .await?;
+    let prompt = SnapshotRestorePrompt {
         chat_id: msg.chat.id.0,
         message_id: sent.id,
-        records,
+        snapshot,
+        expires_at: now_ts() + SNAPSHOT_CONFIRM_TTL_SECS,
     };
-    state.undo_sessions.lock().await.insert(session_id, session);
-    Ok(())
+    state
+        .snapshot_restores
+        .lock()
+        .await
+        .insert(prompt_id, prompt);
+    Ok(true)
 }
 
 pub(crate) async fn handle_single_item(
@@ -988,32 +2667,122 @@ pub(crate) async fn handle_single_item(
     text: &str,
     source_message_id: Option<MessageId>,
 ) -> Result<()> {
-    let entry = EntryBlock::from_text(text);
+    if state.config.fetch_titles {
+        if let Some(link) = single_bare_link(text) {
+            if let Some(title) = fetch_page_title(link).await {
+                return send_title_prompt(&bot, chat_id, &state, link, title, source_message_id)
+                    .await;
+            }
+        }
+    }
+    save_single_item(bot, chat_id, state, text, source_message_id).await
+}
+
+async fn send_title_prompt(
+    bot: &Bot,
+    chat_id: ChatId,
+    state: &std::sync::Arc<AppState>,
+    link: &str,
+    title: String,
+    source_message_id: Option<MessageId>,
+) -> Result<()> {
+    let prompt_id = short_id();
+    let kb = build_title_prompt_keyboard(&prompt_id);
+    let prompt_text = format!("Save as \"{}\"?\n{}", title, link);
+    let sent = send(bot, &state.config, chat_id, prompt_text)
+        .reply_markup(kb)
+        .await?;
+
+    let prompt = TitlePrompt {
+        chat_id: chat_id.0,
+        message_id: sent.id,
+        link: link.to_string(),
+        title,
+        source_message_id,
+        expires_at: now_ts() + TITLE_PROMPT_TTL_SECS,
+    };
+    state
+        .title_prompts
+        .lock()
+        .await
+        .insert(prompt_id.clone(), prompt);
+
+    // An unanswered prompt saves the plain link once it expires.
+    let bot = bot.clone();
+    let state = state.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(TITLE_PROMPT_TTL_SECS)).await;
+        let Some(prompt) = state.title_prompts.lock().await.remove(&prompt_id) else {
+            return;
+        };
+        let _ = bot.delete_message(chat_id, prompt.message_id).await;
+        if let Err(err) =
+            save_single_item(bot, chat_id, state, &prompt.link, prompt.source_message_id).await
+        {
+            error!("saving expired title prompt failed: {:#}", err);
+        }
+    });
+    Ok(())
+}
+
+pub(crate) async fn save_single_item(
+    bot: Bot,
+    chat_id: ChatId,
+    state: std::sync::Arc<AppState>,
+    text: &str,
+    source_message_id: Option<MessageId>,
+) -> Result<()> {
+    let text = apply_auto_tag(text, &state.config.auto_tag_domains);
+    let entry = if state.config.track_added_dates {
+        EntryBlock::from_text(&stamp_added_date(&text, Local::now().date_naive()))
+    } else {
+        EntryBlock::from_text(&text)
+    };
     let op = QueuedOp {
         kind: QueuedOpKind::Add,
         entry: entry.block_string(),
         resource_path: None,
         updated_entry: None,
+        position: None,
     };
 
     match apply_user_op(&state, &op).await? {
         UserOpOutcome::Applied(ApplyOutcome::Applied) => {
-            send_ephemeral(&bot, chat_id, "Saved.", ACK_TTL_SECS).await?;
+            send_ephemeral(&bot, &state.config, chat_id, "Saved.", ACK_TTL_SECS).await?;
             if let Some(message_id) = source_message_id {
-                let _ = bot.delete_message(chat_id, message_id).await;
+                delete_source_message(&bot, &state.config, chat_id, message_id).await;
             }
         }
         UserOpOutcome::Applied(ApplyOutcome::Duplicate) => {
-            send_ephemeral(&bot, chat_id, "Already saved.", ACK_TTL_SECS).await?;
+            send_ephemeral(&bot, &state.config, chat_id, "Already saved.", ACK_TTL_SECS).await?;
+            if let Some(message_id) = source_message_id {
+                delete_source_message(&bot, &state.config, chat_id, message_id).await;
+            }
+        }
+        UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {
+            send_ephemeral(
+                &bot,
+                &state.config,
+                chat_id,
+                "Already finished earlier.",
+                ACK_TTL_SECS,
+            )
+            .await?;
             if let Some(message_id) = source_message_id {
-                let _ = bot.delete_message(chat_id, message_id).await;
+                delete_source_message(&bot, &state.config, chat_id, message_id).await;
             }
         }
         UserOpOutcome::Applied(ApplyOutcome::NotFound) => {
             // Not used for add.
         }
         UserOpOutcome::Queued => {
-            send_error(&bot, chat_id, "Write failed; queued for retry.").await?;
+            send_error(
+                &bot,
+                &state.config,
+                chat_id,
+                "Write failed; queued for retry.",
+            )
+            .await?;
         }
     }
 
@@ -1029,7 +2798,7 @@ async fn handle_multi_item(
 ) -> Result<()> {
     let items = split_items(text);
     if items.is_empty() {
-        send_error(&bot, chat_id, "No items found.").await?;
+        send_error(&bot, &state.config, chat_id, "No items found.").await?;
         return Ok(());
     }
 
@@ -1037,8 +2806,7 @@ async fn handle_multi_item(
     let selected = vec![false; items.len()];
     let view_text = build_picker_text(&items, &selected);
     let kb = build_picker_keyboard(&picker_id, &selected);
-    let sent = bot
-        .send_message(chat_id, view_text)
+    let sent = send(&bot, &state.config, chat_id, view_text)
         .reply_markup(kb)
         .await?;
 
@@ -1063,8 +2831,7 @@ async fn handle_add_command(
     let prompt_id = short_id();
     let kb = build_add_prompt_keyboard(&prompt_id);
     let prompt_text = "Add to reading list or resources?";
-    let sent = bot
-        .send_message(msg.chat.id, prompt_text)
+    let sent = send(&bot, &state.config, msg.chat.id, prompt_text)
         .reply_markup(kb)
         .await?;
 
@@ -1086,6 +2853,9 @@ pub(crate) async fn start_resource_picker(
     source_message_id: Option<MessageId>,
 ) -> Result<()> {
     let files = list_resource_files(&state.config.resources_path)?;
+    if let Some(path) = auto_create_resource_path(&state.config, &files) {
+        return add_resource_to_default(bot, chat_id, state, path, text, source_message_id).await;
+    }
     let picker_id = short_id();
     let kb = build_resource_picker_keyboard(&picker_id, &files);
     let prompt_text = if files.is_empty() {
@@ -1093,8 +2863,7 @@ pub(crate) async fn start_resource_picker(
     } else {
         "Choose a resource file:"
     };
-    let sent = bot
-        .send_message(chat_id, prompt_text)
+    let sent = send(bot, &state.config, chat_id, prompt_text)
         .reply_markup(kb)
         .await?;
 
@@ -1104,6 +2873,7 @@ pub(crate) async fn start_resource_picker(
         text: text.to_string(),
         source_message_id,
         files,
+        added_to: None,
     };
     state
         .resource_pickers
@@ -1113,6 +2883,88 @@ pub(crate) async fn start_resource_picker(
     Ok(())
 }
 
+/// Adds to the configured default resource file and offers to move the item
+/// to a different file instead.
+pub(crate) async fn add_resource_to_default(
+    bot: &Bot,
+    chat_id: ChatId,
+    state: &std::sync::Arc<AppState>,
+    resource_path: PathBuf,
+    text: &str,
+    source_message_id: Option<MessageId>,
+) -> Result<()> {
+    let op = QueuedOp {
+        kind: QueuedOpKind::AddResource,
+        entry: resource_block_from_text(text),
+        resource_path: Some(resource_path.clone()),
+        updated_entry: None,
+        position: None,
+    };
+
+    match apply_user_op(state, &op).await? {
+        UserOpOutcome::Applied(ApplyOutcome::Applied) => {
+            let kind = UndoKind::AddResource {
+                resource_path: resource_path.clone(),
+            };
+            add_undo(state, kind, op.entry.clone()).await?;
+            let picker_id = short_id();
+            let kb = InlineKeyboardMarkup::new(vec![vec![
+                InlineKeyboardButton::callback(
+                    "Choose different file",
+                    format!("res:{}:other", picker_id),
+                ),
+                InlineKeyboardButton::callback("OK", format!("res:{}:cancel", picker_id)),
+            ]]);
+            let prompt_text = format!("Added to {}.", resource_file_label(&resource_path));
+            let sent = send(bot, &state.config, chat_id, prompt_text)
+                .reply_markup(kb)
+                .await?;
+            let picker = ResourcePickerState {
+                chat_id: chat_id.0,
+                message_id: sent.id,
+                text: text.to_string(),
+                source_message_id: None,
+                files: Vec::new(),
+                added_to: Some(resource_path),
+            };
+            state
+                .resource_pickers
+                .lock()
+                .await
+                .insert(picker_id, picker);
+            if let Some(message_id) = source_message_id {
+                delete_source_message(bot, &state.config, chat_id, message_id).await;
+            }
+        }
+        UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+        | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {
+            send_ephemeral(
+                bot,
+                &state.config,
+                chat_id,
+                "Already in resources.",
+                ACK_TTL_SECS,
+            )
+            .await?;
+            if let Some(message_id) = source_message_id {
+                delete_source_message(bot, &state.config, chat_id, message_id).await;
+            }
+        }
+        UserOpOutcome::Applied(ApplyOutcome::NotFound) => {}
+        UserOpOutcome::Queued => {
+            send_error(
+                bot,
+                &state.config,
+                chat_id,
+                "Write failed; queued for retry.",
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
 pub(crate) async fn add_resource_from_text(
     bot: &Bot,
     chat_id: ChatId,
@@ -1124,27 +2976,50 @@ pub(crate) async fn add_resource_from_text(
     let entry_block = resource_block_from_text(text);
     let op = QueuedOp {
         kind: QueuedOpKind::AddResource,
-        entry: entry_block,
-        resource_path: Some(resource_path),
+        entry: entry_block.clone(),
+        resource_path: Some(resource_path.clone()),
         updated_entry: None,
+        position: None,
     };
 
     match apply_user_op(state, &op).await? {
         UserOpOutcome::Applied(ApplyOutcome::Applied) => {
-            send_ephemeral(bot, chat_id, "Added to resources.", ACK_TTL_SECS).await?;
+            add_undo(state, UndoKind::AddResource { resource_path }, entry_block).await?;
+            send_ephemeral(
+                bot,
+                &state.config,
+                chat_id,
+                "Added to resources.",
+                ACK_TTL_SECS,
+            )
+            .await?;
             if let Some(message_id) = source_message_id {
-                let _ = bot.delete_message(chat_id, message_id).await;
+                delete_source_message(bot, &state.config, chat_id, message_id).await;
             }
         }
-        UserOpOutcome::Applied(ApplyOutcome::Duplicate) => {
-            send_ephemeral(bot, chat_id, "Already in resources.", ACK_TTL_SECS).await?;
+        UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+        | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {
+            send_ephemeral(
+                bot,
+                &state.config,
+                chat_id,
+                "Already in resources.",
+                ACK_TTL_SECS,
+            )
+            .await?;
             if let Some(message_id) = source_message_id {
-                let _ = bot.delete_message(chat_id, message_id).await;
+                delete_source_message(bot, &state.config, chat_id, message_id).await;
             }
         }
         UserOpOutcome::Applied(ApplyOutcome::NotFound) => {}
         UserOpOutcome::Queued => {
-            send_error(bot, chat_id, "Write failed; queued for retry.").await?;
+            send_error(
+                bot,
+                &state.config,
+                chat_id,
+                "Write failed; queued for retry.",
+            )
+            .await?;
         }
     }
 
@@ -1162,7 +3037,7 @@ async fn handle_resource_filename_response(
     let filename = match sanitize_resource_filename(text) {
         Ok(name) => name,
         Err(err) => {
-            send_error(bot, chat_id, &err.to_string()).await?;
+            send_error(bot, &state.config, chat_id, &err.to_string()).await?;
             let mut prompts = state.resource_filename_prompts.lock().await;
             prompts.insert(
                 chat_id.0,
@@ -1171,7 +3046,7 @@ async fn handle_resource_filename_response(
                     ..prompt
                 },
             );
-            let _ = bot.delete_message(chat_id, message_id).await;
+            delete_source_message(bot, &state.config, chat_id, message_id).await;
             return Ok(());
         }
     };
@@ -1183,12 +3058,12 @@ async fn handle_resource_filename_response(
         state,
         resource_path,
         &prompt.text,
-        prompt.source_message_id.clone(),
+        prompt.source_message_id,
     )
     .await?;
 
     let _ = bot.delete_message(chat_id, prompt.prompt_message_id).await;
-    let _ = bot.delete_message(chat_id, message_id).await;
+    delete_source_message(bot, &state.config, chat_id, message_id).await;
     Ok(())
 }
 
@@ -1200,8 +3075,11 @@ async fn start_download_picker(
 ) -> Result<()> {
     let picker_id = short_id();
     let text = build_download_picker_text(&links);
-    let kb = build_download_picker_keyboard(&picker_id, &links);
-    let sent = bot.send_message(chat_id, text).reply_markup(kb).await?;
+    let kb =
+        build_download_picker_keyboard(&picker_id, &links, state.config.default_download_action);
+    let sent = send(bot, &state.config, chat_id, text)
+        .reply_markup(kb)
+        .await?;
     let picker = DownloadPickerState {
         chat_id: chat_id.0,
         message_id: sent.id,
@@ -1224,9 +3102,9 @@ async fn handle_download_link_response(
     text: &str,
     prompt: DownloadLinkPrompt,
 ) -> Result<()> {
-    let new_links = extract_links(text);
+    let new_links = extract_links(text, state.config.min_link_length);
     if new_links.is_empty() {
-        send_error(bot, chat_id, "No links found. Send a URL.").await?;
+        send_error(bot, &state.config, chat_id, "No links found. Send a URL.").await?;
         let mut prompts = state.download_link_prompts.lock().await;
         prompts.insert(
             chat_id.0,
@@ -1235,7 +3113,7 @@ async fn handle_download_link_response(
                 ..prompt
             },
         );
-        let _ = bot.delete_message(chat_id, message_id).await;
+        delete_source_message(bot, &state.config, chat_id, message_id).await;
         return Ok(());
     }
 
@@ -1247,7 +3125,7 @@ async fn handle_download_link_response(
     }
     start_download_picker(bot, chat_id, state, links).await?;
     let _ = bot.delete_message(chat_id, prompt.prompt_message_id).await;
-    let _ = bot.delete_message(chat_id, message_id).await;
+    delete_source_message(bot, &state.config, chat_id, message_id).await;
     Ok(())
 }
 
@@ -1259,9 +3137,19 @@ async fn handle_finish_title_response(
     text: &str,
     prompt: FinishTitlePrompt,
 ) -> Result<()> {
-    let title = text.lines().next().unwrap_or("").trim();
-    if title.is_empty() {
-        send_error(bot, chat_id, "Provide a title.").await?;
+    let updated_entry = match &prompt.mode {
+        FinishMode::Title { link } => {
+            let title = text.lines().next().unwrap_or("").trim();
+            (!title.is_empty()).then(|| entry_with_title(&prompt.entry, title, link))
+        }
+        FinishMode::Note => (!text.trim().is_empty()).then(|| entry_with_note(&prompt.entry, text)),
+    };
+    let Some(updated_entry) = updated_entry else {
+        let error = match prompt.mode {
+            FinishMode::Title { .. } => "Provide a title.",
+            FinishMode::Note => "Provide a note.",
+        };
+        send_error(bot, &state.config, chat_id, error).await?;
         let mut prompts = state.finish_title_prompts.lock().await;
         prompts.insert(
             chat_id.0,
@@ -1270,29 +3158,29 @@ async fn handle_finish_title_response(
                 ..prompt
             },
         );
-        let _ = bot.delete_message(chat_id, message_id).await;
+        delete_source_message(bot, &state.config, chat_id, message_id).await;
         return Ok(());
-    }
+    };
 
-    let updated_entry = entry_with_title(&prompt.entry, title, &prompt.link);
     let mut session = {
         let mut sessions = state.sessions.lock().await;
         let session = match sessions.remove(&prompt.session_id) {
             Some(session) => session,
             None => {
                 let _ = bot.delete_message(chat_id, prompt.prompt_message_id).await;
-                let _ = bot.delete_message(chat_id, message_id).await;
+                delete_source_message(bot, &state.config, chat_id, message_id).await;
                 return Ok(());
             }
         };
         if session.chat_id != prompt.chat_id {
             sessions.insert(prompt.session_id.clone(), session);
             let _ = bot.delete_message(chat_id, prompt.prompt_message_id).await;
-            let _ = bot.delete_message(chat_id, message_id).await;
+            delete_source_message(bot, &state.config, chat_id, message_id).await;
             return Ok(());
         }
         session
     };
+    session.last_activity = now_ts();
 
     let entry_index = session
         .entries
@@ -1304,9 +3192,9 @@ async fn handle_finish_title_response(
             .lock()
             .await
             .insert(prompt.session_id.clone(), session);
-        send_error(bot, chat_id, "Item not found.").await?;
+        send_error(bot, &state.config, chat_id, "Item not found.").await?;
         let _ = bot.delete_message(chat_id, prompt.prompt_message_id).await;
-        let _ = bot.delete_message(chat_id, message_id).await;
+        delete_source_message(bot, &state.config, chat_id, message_id).await;
         return Ok(());
     };
 
@@ -1315,6 +3203,7 @@ async fn handle_finish_title_response(
         entry: prompt.entry.clone(),
         resource_path: None,
         updated_entry: Some(updated_entry.clone()),
+        position: None,
     };
 
     match apply_user_op(state, &op).await? {
@@ -1322,34 +3211,187 @@ async fn handle_finish_title_response(
             session.entries.remove(entry_index);
             session.view = prompt.return_to.clone();
             let peeked_snapshot = state.peeked.lock().await.clone();
-            normalize_peek_view(&mut session, &peeked_snapshot);
-            send_ephemeral(bot, chat_id, "Moved.", ACK_TTL_SECS).await?;
-            let _ = add_undo(state, UndoKind::MoveToFinished, updated_entry).await?;
+            normalize_peek_view(&mut session, &peeked_snapshot, state.config.page_size);
+            send_ephemeral(bot, &state.config, chat_id, "Moved.", ACK_TTL_SECS).await?;
+            // Undo brings back the titled block, so the session must hold that one.
+            let entry = EntryBlock::from_block(&updated_entry);
+            let undo_id = add_undo(state, UndoKind::MoveToFinished, updated_entry).await?;
+            session.session_undo.push(SessionUndo {
+                undo_id,
+                entry,
+                index: entry_index,
+            });
         }
         UserOpOutcome::Applied(ApplyOutcome::NotFound) => {
-            send_error(bot, chat_id, "Item not found.").await?;
+            send_error(bot, &state.config, chat_id, "Item not found.").await?;
         }
-        UserOpOutcome::Applied(ApplyOutcome::Duplicate) => {}
+        UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+        | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {}
         UserOpOutcome::Queued => {
-            send_error(bot, chat_id, "Write failed; queued for retry.").await?;
+            send_error(
+                bot,
+                &state.config,
+                chat_id,
+                "Write failed; queued for retry.",
+            )
+            .await?;
         }
     }
 
     let peeked_snapshot = state.peeked.lock().await.clone();
-    let (text, kb) = render_list_view(&session.id, &session, &peeked_snapshot, &state.config);
-    if let Some(list_message_id) = session.message_id {
-        bot.edit_message_text(chat_id, list_message_id, text)
-            .reply_markup(kb)
-            .await?;
+    show_list_view(bot, chat_id, state, &mut session, &peeked_snapshot).await?;
+    state
+        .sessions
+        .lock()
+        .await
+        .insert(prompt.session_id.clone(), session);
+    state
+        .active_sessions
+        .lock()
+        .await
+        .insert(chat_id.0, prompt.session_id.clone());
+
+    let _ = bot.delete_message(chat_id, prompt.prompt_message_id).await;
+    delete_source_message(bot, &state.config, chat_id, message_id).await;
+    Ok(())
+}
+
+async fn handle_edit_response(
+    bot: &Bot,
+    chat_id: ChatId,
+    message_id: MessageId,
+    state: &std::sync::Arc<AppState>,
+    text: &str,
+    prompt: EditPrompt,
+) -> Result<()> {
+    let invalid = if text.trim().is_empty() {
+        Some("Provide the updated text.")
+    } else if matches!(prompt.mode, EditMode::Due) && parse_due_input(text).is_none() {
+        Some("Send the date as YYYY-MM-DD, or - to remove it.")
+    } else if matches!(prompt.mode, EditMode::Position) && parse_position(text).is_none() {
+        Some("Send a position number, starting from 1.")
     } else {
-        let sent = bot.send_message(chat_id, text).reply_markup(kb).await?;
-        session.message_id = Some(sent.id);
+        None
+    };
+    if let Some(error) = invalid {
+        send_error(bot, &state.config, chat_id, error).await?;
+        state.edit_prompts.lock().await.insert(
+            chat_id.0,
+            EditPrompt {
+                expires_at: now_ts() + EDIT_PROMPT_TTL_SECS,
+                ..prompt
+            },
+        );
+        delete_source_message(bot, &state.config, chat_id, message_id).await;
+        return Ok(());
     }
-    if let Err(err) =
-        refresh_embedded_media_for_view(bot, chat_id, state, &mut session, &peeked_snapshot).await
+
+    let mut session = {
+        let mut sessions = state.sessions.lock().await;
+        let session = match sessions.remove(&prompt.session_id) {
+            Some(session) => session,
+            None => {
+                let _ = bot.delete_message(chat_id, prompt.prompt_message_id).await;
+                delete_source_message(bot, &state.config, chat_id, message_id).await;
+                return Ok(());
+            }
+        };
+        if session.chat_id != prompt.chat_id {
+            sessions.insert(prompt.session_id.clone(), session);
+            let _ = bot.delete_message(chat_id, prompt.prompt_message_id).await;
+            delete_source_message(bot, &state.config, chat_id, message_id).await;
+            return Ok(());
+        }
+        session
+    };
+    session.last_activity = now_ts();
+
+    let entry_index = session
+        .entries
+        .iter()
+        .position(|entry| entry.block_string() == prompt.entry);
+    let Some(mut entry_index) = entry_index else {
+        state
+            .sessions
+            .lock()
+            .await
+            .insert(prompt.session_id.clone(), session);
+        send_error(bot, &state.config, chat_id, "Item not found.").await?;
+        let _ = bot.delete_message(chat_id, prompt.prompt_message_id).await;
+        delete_source_message(bot, &state.config, chat_id, message_id).await;
+        return Ok(());
+    };
+
+    let updated_entry = match &prompt.mode {
+        EditMode::Text => EntryBlock::from_text(text.trim_end()),
+        EditMode::Title { link } => {
+            let title = text.lines().next().unwrap_or("").trim();
+            let mut titled = EntryBlock::from_block(&entry_with_title(&prompt.entry, title, link));
+            if let Some(date) = entry_added_date(&EntryBlock::from_block(&prompt.entry)) {
+                titled.lines[0] = format!("- {}", stamp_added_date(&titled.lines[0][2..], date));
+            }
+            titled
+        }
+        EditMode::Category => {
+            let category = text.lines().next().unwrap_or("").trim();
+            let category = if category == "-" { "" } else { category };
+            EntryBlock::from_block(&set_entry_category(&prompt.entry, category))
+        }
+        EditMode::Due => {
+            let due = parse_due_input(text).flatten();
+            EntryBlock::from_block(&set_entry_due(&prompt.entry, due))
+        }
+        EditMode::Position => EntryBlock::from_block(&prompt.entry),
+    };
+    if let Some(position) =
+        parse_position(text).filter(|_| matches!(prompt.mode, EditMode::Position))
     {
-        error!("send embedded media failed: {:#}", err);
+        entry_index =
+            move_session_entry(bot, chat_id, state, &mut session, entry_index, position).await?;
+    } else if updated_entry.block_string() == prompt.entry {
+        send_ephemeral(bot, &state.config, chat_id, "No changes.", ACK_TTL_SECS).await?;
+    } else {
+        let op = QueuedOp {
+            kind: QueuedOpKind::UpdateEntry,
+            entry: prompt.entry.clone(),
+            resource_path: prompt.target_path.clone(),
+            updated_entry: Some(updated_entry.block_string()),
+            position: None,
+        };
+
+        match apply_user_op(state, &op).await? {
+            UserOpOutcome::Applied(ApplyOutcome::Applied) => {
+                let kind = UndoKind::Edit {
+                    updated_entry: updated_entry.block_string(),
+                    resource_path: prompt.target_path.clone(),
+                };
+                add_undo(state, kind, prompt.entry.clone()).await?;
+                session.entries[entry_index] = updated_entry;
+                send_ephemeral(bot, &state.config, chat_id, "Updated.", ACK_TTL_SECS).await?;
+            }
+            UserOpOutcome::Applied(ApplyOutcome::NotFound) => {
+                send_error(bot, &state.config, chat_id, "Item not found.").await?;
+            }
+            UserOpOutcome::Applied(ApplyOutcome::Duplicate)
+            | UserOpOutcome::Applied(ApplyOutcome::AlreadyFinished) => {}
+            UserOpOutcome::Queued => {
+                send_error(
+                    bot,
+                    &state.config,
+                    chat_id,
+                    "Write failed; queued for retry.",
+                )
+                .await?;
+            }
+        }
     }
+
+    session.view = ListView::Selected {
+        return_to: Box::new(prompt.return_to.clone()),
+        index: entry_index,
+    };
+    let peeked_snapshot = state.peeked.lock().await.clone();
+    show_list_view(bot, chat_id, state, &mut session, &peeked_snapshot).await?;
     state
         .sessions
         .lock()
@@ -1362,6 +3404,6 @@ async fn handle_finish_title_response(
         .insert(chat_id.0, prompt.session_id.clone());
 
     let _ = bot.delete_message(chat_id, prompt.prompt_message_id).await;
-    let _ = bot.delete_message(chat_id, message_id).await;
+    delete_source_message(bot, &state.config, chat_id, message_id).await;
     Ok(())
 }
@@ -18,9 +18,72 @@ fn test_config() -> Config {
         retry_interval_seconds: None,
         sync: None,
         sync_x: None,
+        default_download_action: DefaultDownloadAction::Ask,
+        playlist_item_limit: DEFAULT_PLAYLIST_ITEM_LIMIT,
+        parse_mode: OutgoingParseMode::Plain,
+        trash_path: None,
+        prune_media: false,
+        max_photo_dimension: None,
+        block_refinished_links: false,
+        track_added_dates: false,
+        media_max_age_days: None,
+        command_prefix: "/".to_string(),
+        default_resource_file: None,
+        media_before_text: false,
+        dedupe_by: DedupeBy::Exact,
+        min_link_length: 0,
+        download_scan_active_page: false,
+        auto_create_default_resource: false,
+        undos_refresh_in_place: false,
+        link_check_concurrency: DEFAULT_LINK_CHECK_CONCURRENCY,
+        link_check_timeout_ms: DEFAULT_LINK_CHECK_TIMEOUT_MS,
+        display_normalize_links: false,
+        auto_tag_domains: HashMap::new(),
+        finished_timestamp: false,
+        page_size: DEFAULT_PAGE_SIZE,
+        source_delete_delay_ms: 0,
+        send_retry_attempts: DEFAULT_SEND_RETRY_ATTEMPTS,
+        random_skip_archived: false,
+        max_send_bytes: DEFAULT_MAX_SEND_BYTES,
+        digest_time: None,
+        auto_reset_when_all_peeked: false,
+        undo_ttl_seconds: DEFAULT_UNDO_TTL_SECS,
+        fetch_titles: false,
+        finished_order: FinishedOrder::Prepend,
+        session_ttl_seconds: None,
+        image_dir: PathBuf::from("/tmp/media"),
+        video_dir: PathBuf::from("/tmp/media"),
+        document_dir: PathBuf::from("/tmp/media"),
     }
 }
 
+fn test_session(entries: Vec<EntryBlock>, view: ListView) -> ListSession {
+    ListSession {
+        id: "session".to_string(),
+        chat_id: 0,
+        kind: SessionKind::List,
+        entries,
+        view,
+        seen_random: HashSet::new(),
+        message_id: None,
+        sent_media_message_ids: Vec::new(),
+        hide_media: false,
+        session_undo: Vec::new(),
+        last_activity: 0,
+    }
+}
+
+fn callback_data(kb: &InlineKeyboardMarkup) -> Vec<String> {
+    kb.inline_keyboard
+        .iter()
+        .flatten()
+        .filter_map(|button| match &button.kind {
+            teloxide::types::InlineKeyboardButtonKind::CallbackData(data) => Some(data.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
 #[test]
 fn normalize_markdown_links_replaces_single_link() {
     let input = "See [post](https://example.com/post) now";
@@ -63,37 +126,83 @@ fn peek_indices_filters_and_pages() {
 
     assert_eq!(count_unpeeked_entries(&entries, &peeked), 4);
     assert_eq!(
-        peek_indices(&entries, &peeked, ListMode::Top, 0),
+        peek_indices(&entries, &peeked, ListMode::Top, 0, DEFAULT_PAGE_SIZE),
         vec![0, 2, 4]
     );
-    assert_eq!(peek_indices(&entries, &peeked, ListMode::Top, 1), vec![5]);
     assert_eq!(
-        peek_indices(&entries, &peeked, ListMode::Bottom, 0),
+        peek_indices(&entries, &peeked, ListMode::Top, 1, DEFAULT_PAGE_SIZE),
+        vec![5]
+    );
+    assert_eq!(
+        peek_indices(&entries, &peeked, ListMode::Bottom, 0, DEFAULT_PAGE_SIZE),
         vec![5, 4, 2]
     );
     assert_eq!(
-        peek_indices(&entries, &peeked, ListMode::Bottom, 1),
+        peek_indices(&entries, &peeked, ListMode::Bottom, 1, DEFAULT_PAGE_SIZE),
         vec![0]
     );
 }
 
+#[test]
+fn progress_counts_only_peeked_entries_still_present() {
+    let entries = vec![entry("one"), entry("two"), entry("three"), entry("four")];
+    let mut peeked = HashSet::new();
+    peeked.insert(entries[0].block_string());
+    peeked.insert(entries[2].block_string());
+    peeked.insert(entry("gone").block_string());
+
+    let reviewed = count_reviewed_entries(&entries, &peeked);
+    assert_eq!(reviewed, 2);
+    assert_eq!(
+        build_progress_text(reviewed, entries.len()),
+        "2 of 4 reviewed (50%)\n[█████░░░░░]"
+    );
+}
+
+#[test]
+fn top_domains_ranks_hosts_by_count() {
+    let entries = vec![
+        entry("https://www.example.com/a and http://news.site/x"),
+        entry("[post](https://Example.com:443/b)"),
+        entry("https://user@blog.dev/c?q=1"),
+        entry("https://news.site/y"),
+        entry("no links here"),
+    ];
+    assert_eq!(
+        host_of("https://www.Example.com/path"),
+        Some("example.com".to_string())
+    );
+    assert_eq!(host_of("ftp://example.com"), None);
+    assert_eq!(
+        top_domains(&entries, 2),
+        vec![("example.com".to_string(), 2), ("news.site".to_string(), 2)]
+    );
+    assert_eq!(top_domains(&entries, 10).len(), 3);
+}
+
+#[test]
+fn render_progress_bar_handles_bounds() {
+    assert_eq!(render_progress_bar(0, 3, 6), "[░░░░░░]");
+    assert_eq!(render_progress_bar(1, 3, 6), "[██░░░░]");
+    assert_eq!(render_progress_bar(3, 3, 6), "[██████]");
+    assert_eq!(render_progress_bar(0, 0, 4), "[░░░░]");
+    assert_eq!(build_progress_text(0, 0), "Read Later is empty.");
+}
+
 #[test]
 fn search_peek_indices_ignore_peeked_entries() {
     let entries: Vec<EntryBlock> = (0..4).map(|i| entry(&format!("match {}", i))).collect();
     let session = ListSession {
-        id: "session".to_string(),
-        chat_id: 0,
         kind: SessionKind::Search {
             query: "match".to_string(),
         },
-        entries: entries.clone(),
-        view: ListView::Peek {
-            mode: ListMode::Top,
-            page: 0,
-        },
-        seen_random: HashSet::new(),
-        message_id: None,
-        sent_media_message_ids: Vec::new(),
+        ..test_session(
+            entries.clone(),
+            ListView::Peek {
+                mode: ListMode::Top,
+                page: 0,
+            },
+        )
     };
     let mut peeked = HashSet::new();
     for entry in &entries {
@@ -102,31 +211,122 @@ fn search_peek_indices_ignore_peeked_entries() {
 
     assert_eq!(count_visible_entries(&session, &peeked), 4);
     assert_eq!(
-        peek_indices_for_session(&session, &peeked, ListMode::Top, 0),
+        peek_indices_for_session(&session, &peeked, ListMode::Top, 0, DEFAULT_PAGE_SIZE),
         vec![0, 1, 2]
     );
     assert_eq!(
-        peek_indices_for_session(&session, &peeked, ListMode::Top, 1),
+        peek_indices_for_session(&session, &peeked, ListMode::Top, 1, DEFAULT_PAGE_SIZE),
         vec![3]
     );
 }
 
+#[test]
+fn search_matches_quoted_phrases() {
+    let entries = vec![
+        entry("Intro to Machine Learning"),
+        entry("learning about machine shops"),
+        entry("deep learning"),
+    ];
+    let found: Vec<String> = search_entries(&entries, "\"machine learning\"")
+        .iter()
+        .map(|entry| entry.display_lines()[0].clone())
+        .collect();
+    assert_eq!(found, vec!["Intro to Machine Learning".to_string()]);
+    assert_eq!(search_entries(&entries, "machine learning").len(), 2);
+}
+
+#[test]
+fn search_or_switches_groups_to_disjunction() {
+    let entries = vec![
+        entry("rust async book"),
+        entry("go concurrency patterns"),
+        entry("python tips"),
+        entry("rust or go: a comparison"),
+    ];
+    let firsts = |query: &str| -> Vec<String> {
+        search_entries(&entries, query)
+            .iter()
+            .map(|entry| entry.display_lines()[0].clone())
+            .collect()
+    };
+    assert_eq!(
+        firsts("rust OR go"),
+        vec![
+            "rust async book".to_string(),
+            "go concurrency patterns".to_string(),
+            "rust or go: a comparison".to_string(),
+        ]
+    );
+    assert_eq!(
+        firsts("rust async OR \"go concurrency\""),
+        vec![
+            "rust async book".to_string(),
+            "go concurrency patterns".to_string(),
+        ]
+    );
+    assert_eq!(
+        firsts("rust or go"),
+        vec!["rust or go: a comparison".to_string()]
+    );
+    assert_eq!(
+        parse_query_groups("OR \"A b\" c OR"),
+        vec![vec!["a b".to_string(), "c".to_string()]]
+    );
+    assert!(firsts("OR").is_empty());
+}
+
+#[test]
+fn regex_search_matches_patterns() {
+    let entries = vec![
+        entry("[work] quarterly report #q3"),
+        entry("notes on [work] habits"),
+        entry("issue 1234 in tracker"),
+    ];
+    let firsts = |pattern: &str| -> Vec<String> {
+        regex_search_entries(&entries, pattern)
+            .unwrap()
+            .iter()
+            .map(|entry| entry.display_lines()[0].clone())
+            .collect()
+    };
+    assert_eq!(
+        firsts(r"#q\d\b"),
+        vec!["[work] quarterly report #q3".to_string()]
+    );
+    assert_eq!(
+        firsts(r"issue \d{4}"),
+        vec!["issue 1234 in tracker".to_string()]
+    );
+    assert_eq!(
+        firsts(r"^\[work\]"),
+        vec!["[work] quarterly report #q3".to_string()]
+    );
+    assert_eq!(firsts(r"\[work\]").len(), 2);
+    assert!(regex_search_entries(&entries, "(unclosed").is_err());
+}
+
+#[test]
+fn length_predicates_count_lines_without_trailing_blanks() {
+    let one = entry("one");
+    let three = EntryBlock::from_block("- one\ntwo\nthree\n\n");
+    assert_eq!(entry_line_count(&three), 3);
+    assert!(is_short_entry(&one, 2));
+    assert!(!is_short_entry(&three, 2));
+    assert!(is_short_entry(&three, 3));
+    assert!(is_long_entry(&three, 3));
+    assert!(!is_long_entry(&one, 2));
+}
+
 #[test]
 fn build_peek_view_shows_all_peeked_message() {
     let entries = vec![entry("one"), entry("two")];
-    let session = ListSession {
-        id: "session".to_string(),
-        chat_id: 0,
-        kind: SessionKind::List,
-        entries: entries.clone(),
-        view: ListView::Peek {
+    let session = test_session(
+        entries.clone(),
+        ListView::Peek {
             mode: ListMode::Top,
             page: 0,
         },
-        seen_random: HashSet::new(),
-        message_id: None,
-        sent_media_message_ids: Vec::new(),
-    };
+    );
     let mut peeked = HashSet::new();
     for entry in &entries {
         peeked.insert(entry.block_string());
@@ -136,6 +336,45 @@ fn build_peek_view_shows_all_peeked_message() {
     assert!(text.contains("Everything's been peeked already."));
 }
 
+#[test]
+fn hide_media_flag_gates_embedded_media_refresh() {
+    let temp = TempDir::new().unwrap();
+    let media_dir = temp.path().join("media");
+    fs::create_dir_all(&media_dir).unwrap();
+    fs::write(media_dir.join("image-1.jpg"), b"x").unwrap();
+    let mut config = test_config();
+    config.media_dir = media_dir.clone();
+
+    let mut session = test_session(
+        vec![entry("look ![[image-1.jpg]]")],
+        ListView::Selected {
+            return_to: Box::new(ListView::Menu),
+            index: 0,
+        },
+    );
+    let peeked = HashSet::new();
+    assert_eq!(
+        embedded_media_paths_for_view(&session, &peeked, &config),
+        vec![media_dir.join("image-1.jpg")]
+    );
+    let (_, kb) = render_list_view("session", &session, &peeked, &config);
+    assert!(kb
+        .inline_keyboard
+        .iter()
+        .flatten()
+        .any(|b| b.text == "Hide media"));
+
+    session.hide_media = true;
+    assert!(embedded_media_paths_for_view(&session, &peeked, &config).is_empty());
+    let (_, kb) = render_list_view("session", &session, &peeked, &config);
+    assert!(kb
+        .inline_keyboard
+        .iter()
+        .flatten()
+        .any(|b| b.text == "Show media"));
+    assert!(callback_data(&kb).contains(&"ls:session:media".to_string()));
+}
+
 #[test]
 fn format_embedded_references_labels_images_and_files() {
     let temp = TempDir::new().unwrap();
@@ -173,6 +412,33 @@ fn format_embedded_references_labels_videos() {
     assert_eq!(rendered[0], "Watch video #1");
 }
 
+#[test]
+fn format_embedded_references_labels_audio() {
+    let temp = TempDir::new().unwrap();
+    let media_dir = temp.path().join("media");
+    fs::create_dir_all(&media_dir).unwrap();
+    fs::write(media_dir.join("memo.ogg"), b"x").unwrap();
+
+    let mut config = test_config();
+    config.media_dir = media_dir;
+
+    let lines = vec!["Listen ![[memo.ogg]]".to_string()];
+    let rendered = format_embedded_references_for_lines(&lines, &config);
+
+    assert_eq!(rendered[0], "Listen audio #1");
+    assert_eq!(audio_extension_from_mime("audio/mpeg"), Some("mp3"));
+    assert_eq!(audio_extension_from_mime("audio/ogg"), Some("ogg"));
+}
+
+#[test]
+fn downscale_target_keeps_photos_within_bounds() {
+    assert_eq!(downscale_target(1280, 720, 2560), None);
+    assert_eq!(downscale_target(2560, 1440, 2560), None);
+    assert_eq!(downscale_target(5120, 2880, 2560), Some((2560, 1440)));
+    assert_eq!(downscale_target(3000, 6000, 1500), Some((750, 1500)));
+    assert_eq!(downscale_target(10000, 1, 100), Some((100, 1)));
+}
+
 #[test]
 fn human_size_formats_units() {
     assert_eq!(human_size(999), "999 B");
@@ -199,24 +465,49 @@ fn build_download_quality_text_lists_options() {
     assert!(text.contains("2: 720p mp4"));
 }
 
+#[test]
+fn download_picker_uses_link_shortcut_when_default_action_set() {
+    let links = vec![
+        "https://example.com/a".to_string(),
+        "https://example.com/b".to_string(),
+    ];
+    assert!(download_action_shortcut(DefaultDownloadAction::Ask).is_none());
+    assert!(matches!(
+        download_action_shortcut(DefaultDownloadAction::Save),
+        Some(DownloadAction::Save)
+    ));
+
+    let ask = callback_data(&build_download_picker_keyboard(
+        "p",
+        &links,
+        DefaultDownloadAction::Ask,
+    ));
+    assert!(ask.contains(&"dl:p:send:1".to_string()));
+    assert!(ask.contains(&"dl:p:save:1".to_string()));
+    assert!(ask.contains(&"dl:p:sendsave:1".to_string()));
+
+    let shortcut = callback_data(&build_download_picker_keyboard(
+        "p",
+        &links,
+        DefaultDownloadAction::Send,
+    ));
+    assert!(shortcut.contains(&"dl:p:link:0".to_string()));
+    assert!(shortcut.contains(&"dl:p:link:1".to_string()));
+    assert!(!shortcut.iter().any(|data| data.starts_with("dl:p:send")));
+}
+
 #[test]
 fn embedded_lines_for_peek_use_preview_only() {
     let entry = EntryBlock::from_text("first line\nsecond line\n![[image-2.jpg]]");
-    let session = ListSession {
-        id: "session".to_string(),
-        chat_id: 0,
-        kind: SessionKind::List,
-        entries: vec![entry],
-        view: ListView::Peek {
+    let session = test_session(
+        vec![entry],
+        ListView::Peek {
             mode: ListMode::Top,
             page: 0,
         },
-        seen_random: HashSet::new(),
-        message_id: None,
-        sent_media_message_ids: Vec::new(),
-    };
+    );
 
-    let lines = embedded_lines_for_view(&session, &HashSet::new());
+    let lines = embedded_lines_for_view(&session, &HashSet::new(), DEFAULT_PAGE_SIZE);
     assert_eq!(
         lines,
         vec!["first line".to_string(), "second line...".to_string()]
@@ -248,21 +539,18 @@ fn build_undos_view_includes_labels_and_previews() {
 #[test]
 fn displayed_indices_for_selected_view() {
     let entries = vec![entry("one"), entry("two"), entry("three")];
-    let session = ListSession {
-        id: "session".to_string(),
-        chat_id: 0,
-        kind: SessionKind::List,
+    let session = test_session(
         entries,
-        view: ListView::Selected {
+        ListView::Selected {
             return_to: Box::new(ListView::Menu),
             index: 1,
         },
-        seen_random: HashSet::new(),
-        message_id: None,
-        sent_media_message_ids: Vec::new(),
-    };
+    );
     let peeked = HashSet::new();
-    assert_eq!(displayed_indices_for_view(&session, &peeked), vec![1]);
+    assert_eq!(
+        displayed_indices_for_view(&session, &peeked, DEFAULT_PAGE_SIZE),
+        vec![1]
+    );
 }
 
 #[test]
@@ -270,24 +558,24 @@ fn norm_target_index_prefers_single_peek_item() {
     let entries = vec![entry("one"), entry("two")];
     let mut peeked = HashSet::new();
     peeked.insert(entries[0].block_string());
-    let session = ListSession {
-        id: "session".to_string(),
-        chat_id: 0,
-        kind: SessionKind::List,
-        entries: entries.clone(),
-        view: ListView::Peek {
+    let session = test_session(
+        entries.clone(),
+        ListView::Peek {
             mode: ListMode::Top,
             page: 0,
         },
-        seen_random: HashSet::new(),
-        message_id: None,
-        sent_media_message_ids: Vec::new(),
-    };
-    assert_eq!(norm_target_index(&session, &peeked), Some(1));
+    );
+    assert_eq!(
+        norm_target_index(&session, &peeked, DEFAULT_PAGE_SIZE),
+        Some(1)
+    );
 
     let session_multi = ListSession { entries, ..session };
     let empty_peeked = HashSet::new();
-    assert_eq!(norm_target_index(&session_multi, &empty_peeked), None);
+    assert_eq!(
+        norm_target_index(&session_multi, &empty_peeked, DEFAULT_PAGE_SIZE),
+        None
+    );
 }
 
 #[test]
@@ -323,6 +611,69 @@ fn extract_https_username_from_remote() {
     assert_eq!(extract_https_username("git@host:repo.git"), None);
 }
 
+#[test]
+fn remote_kind_only_requires_token_for_https() {
+    assert_eq!(
+        remote_kind("https://github.com/me/notes.git"),
+        Some(RemoteKind::Https)
+    );
+    assert_eq!(
+        remote_kind("git@github.com:me/notes.git"),
+        Some(RemoteKind::Ssh)
+    );
+    assert_eq!(
+        remote_kind("ssh://git@github.com/me/notes.git"),
+        Some(RemoteKind::Ssh)
+    );
+    assert_eq!(
+        remote_kind("github.com:me/notes.git"),
+        Some(RemoteKind::Ssh)
+    );
+    assert_eq!(remote_kind("file:///srv/git/notes.git"), None);
+    assert_eq!(remote_kind("/srv/git/notes.git"), None);
+    assert_eq!(remote_kind("http://example.com/notes.git"), None);
+}
+
+fn repo_with_origin(url: &str) -> TempDir {
+    let temp = TempDir::new().unwrap();
+    assert!(run_git(temp.path(), &["init", "-q"], Vec::new())
+        .unwrap()
+        .status
+        .success());
+    assert!(
+        run_git(temp.path(), &["remote", "add", "origin", url], Vec::new())
+            .unwrap()
+            .status
+            .success()
+    );
+    temp
+}
+
+#[test]
+fn resolve_sync_remote_enforces_token_only_for_https() {
+    let https = repo_with_origin("https://github.com/me/notes.git");
+    let sync = SyncConfig {
+        repo_path: https.path().to_path_buf(),
+        token_file: None,
+        author_name: None,
+        author_email: None,
+        auth: SyncAuth::Pat,
+    };
+    let Err(err) = resolve_sync_remote(&sync) else {
+        panic!("https remote without a token should be rejected");
+    };
+    assert!(err.to_string().contains("settings.sync.token_file"));
+
+    let ssh = repo_with_origin("git@github.com:me/notes.git");
+    let sync = SyncConfig {
+        repo_path: ssh.path().to_path_buf(),
+        ..sync
+    };
+    let (remote, auth) = resolve_sync_remote(&sync).unwrap();
+    assert_eq!(remote, "origin");
+    assert!(!auth.env.iter().any(|(key, _)| *key == "GIT_SYNC_PAT"));
+}
+
 #[test]
 fn read_token_file_trims_whitespace() {
     let mut file = NamedTempFile::new().unwrap();
@@ -331,6 +682,16 @@ fn read_token_file_trims_whitespace() {
     assert_eq!(token, "token");
 }
 
+#[test]
+fn try_acquire_sync_is_exclusive_until_guard_drops() {
+    let flag = AtomicBool::new(false);
+    let guard = try_acquire_sync(&flag).unwrap();
+    assert!(try_acquire_sync(&flag).is_none());
+    drop(guard);
+    assert!(!flag.load(Ordering::Acquire));
+    assert!(try_acquire_sync(&flag).is_some());
+}
+
 #[test]
 fn parse_pull_mode_accepts_theirs() {
     assert!(matches!(parse_pull_mode(""), Ok(PullMode::FastForward)));
@@ -406,3 +767,2754 @@ fn prepend_urls_to_read_later_sync_preserves_input_order() {
         ]
     );
 }
+
+#[test]
+fn update_entry_sync_rewrites_resource_entry_by_block() {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("Resources.md");
+    fs::write(
+        &path,
+        "# Resources\n- (Auto-Resource): first\n- (Auto-Resource): second\nnotes\n",
+    )
+    .unwrap();
+
+    let updated = EntryBlock::from_text("(Auto-Resource): second, revised");
+    let outcome =
+        update_entry_sync(&path, "- (Auto-Resource): second\nnotes", &updated).unwrap();
+    assert!(matches!(outcome, ModifyOutcome::Applied));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# Resources\n- (Auto-Resource): first\n- (Auto-Resource): second, revised\n"
+    );
+
+    let missing = update_entry_sync(&path, "- (Auto-Resource): gone", &updated).unwrap();
+    assert!(matches!(missing, ModifyOutcome::NotFound));
+}
+
+#[test]
+fn resource_session_selected_view_offers_edit() {
+    let session = ListSession {
+        kind: SessionKind::Resource {
+            path: PathBuf::from("/tmp/resources/Rust.md"),
+        },
+        ..test_session(
+            vec![entry("(Auto-Resource): one")],
+            ListView::Selected {
+                return_to: Box::new(ListView::Peek {
+                    mode: ListMode::Top,
+                    page: 0,
+                }),
+                index: 0,
+            },
+        )
+    };
+    let (_, kb) = render_list_view("session", &session, &HashSet::new(), &test_config());
+    let data = callback_data(&kb);
+    assert!(data.contains(&"ls:session:edit".to_string()));
+    assert!(!data.iter().any(|d| d.contains(":finish") || d.contains(":delete")));
+    assert_eq!(
+        session_target_path(&session),
+        Some(PathBuf::from("/tmp/resources/Rust.md"))
+    );
+}
+
+#[test]
+fn delete_entry_sync_appends_to_trash_when_configured() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    let trash = temp.path().join("trash.md");
+    fs::write(&read_later, "- one\n- two\n").unwrap();
+    fs::write(&trash, "# Trash\n- old\n").unwrap();
+
+    let outcome = delete_entry_sync(&read_later, "- one", Some(&trash)).unwrap();
+    assert!(matches!(outcome, ModifyOutcome::Applied));
+    assert_eq!(fs::read_to_string(&read_later).unwrap(), "- two\n");
+    assert_eq!(fs::read_to_string(&trash).unwrap(), "# Trash\n- old\n- one\n");
+
+    let outcome = restore_from_trash_sync(&read_later, &trash, "- one", None).unwrap();
+    assert!(matches!(outcome, ModifyOutcome::Applied));
+    assert_eq!(fs::read_to_string(&read_later).unwrap(), "- one\n- two\n");
+    assert_eq!(fs::read_to_string(&trash).unwrap(), "# Trash\n- old\n");
+}
+
+#[test]
+fn media_trash_destination_requires_prune_and_trash() {
+    let mut config = test_config();
+    let media = PathBuf::from("/tmp/media/image-1.jpg");
+    assert_eq!(media_trash_destination(&media, &config), None);
+
+    // Without a trash there is nowhere to move it, so nothing is pruned.
+    config.prune_media = true;
+    assert_eq!(media_trash_destination(&media, &config), None);
+
+    config.trash_path = Some(PathBuf::from("/tmp/trash.md"));
+    assert_eq!(
+        media_trash_destination(&media, &config),
+        Some(PathBuf::from("/tmp/data/trash/media/image-1.jpg"))
+    );
+    assert_eq!(
+        media_trash_destination(Path::new("/tmp/vault/other.jpg"), &config),
+        None
+    );
+}
+
+#[test]
+fn restore_entry_media_sync_moves_pruned_media_back() {
+    let temp = TempDir::new().unwrap();
+    let mut config = test_config();
+    config.media_dir = temp.path().join("media");
+    config.data_dir = temp.path().join("data");
+    config.read_later_path = temp.path().join("read-later.md");
+    config.finished_path = temp.path().join("finished.md");
+    config.trash_path = Some(temp.path().join("trash.md"));
+    config.prune_media = true;
+    fs::create_dir_all(&config.media_dir).unwrap();
+    fs::write(config.media_dir.join("image-1.jpg"), b"jpg").unwrap();
+    fs::write(&config.read_later_path, "").unwrap();
+    fs::write(&config.finished_path, "").unwrap();
+
+    let block = "- photo\n![[image-1.jpg]]";
+    prune_entry_media_sync(&config, block).unwrap();
+    assert!(!config.media_dir.join("image-1.jpg").exists());
+    assert!(trash_media_dir(&config).join("image-1.jpg").exists());
+
+    assert_eq!(restore_entry_media_sync(&config, block).unwrap(), 1);
+    assert!(config.media_dir.join("image-1.jpg").exists());
+    assert!(!trash_media_dir(&config).join("image-1.jpg").exists());
+}
+
+#[test]
+fn pruned_media_with_a_taken_name_is_kept_and_restored_under_its_name() {
+    let temp = TempDir::new().unwrap();
+    let mut config = test_config();
+    config.media_dir = temp.path().join("media");
+    config.image_dir = config.media_dir.clone();
+    config.data_dir = temp.path().join("data");
+    config.read_later_path = temp.path().join("read-later.md");
+    config.finished_path = temp.path().join("finished.md");
+    config.resources_path = temp.path().join("resources");
+    config.trash_path = Some(temp.path().join("trash.md"));
+    config.prune_media = true;
+    fs::create_dir_all(&config.media_dir).unwrap();
+    let block = "- photo\n![[photo.jpg]]";
+    fs::write(temp.path().join("trash.md"), format!("{}\n", block)).unwrap();
+
+    fs::write(config.media_dir.join("photo.jpg"), b"first").unwrap();
+    prune_entry_media_sync(&config, block).unwrap();
+    fs::write(config.media_dir.join("photo.jpg"), b"second").unwrap();
+    prune_entry_media_sync(&config, block).unwrap();
+
+    let trash = trash_media_dir(&config);
+    assert_eq!(fs::read(trash.join("photo.jpg")).unwrap(), b"first");
+    assert_eq!(fs::read(trash.join("photo-1.jpg")).unwrap(), b"second");
+
+    let restored = restore_media_sync(&config, "photo-1.jpg").unwrap();
+    assert_eq!(restored, config.media_dir.join("photo.jpg"));
+    assert_eq!(fs::read(&restored).unwrap(), b"second");
+
+    // With the name taken again, the renamed copy keeps its trash name.
+    fs::rename(trash.join("photo.jpg"), trash.join("photo-2.jpg")).unwrap();
+    assert_eq!(
+        restore_media_sync(&config, "photo-2.jpg").unwrap(),
+        config.media_dir.join("photo-2.jpg")
+    );
+}
+
+#[test]
+fn restore_entry_media_sync_finds_renamed_trash_copies() {
+    let temp = TempDir::new().unwrap();
+    let mut config = test_config();
+    config.media_dir = temp.path().join("media");
+    config.data_dir = temp.path().join("data");
+    config.trash_path = Some(temp.path().join("trash.md"));
+    fs::create_dir_all(trash_media_dir(&config)).unwrap();
+    fs::write(trash_media_dir(&config).join("clip-3.jpg"), b"jpg").unwrap();
+
+    assert_eq!(
+        strip_unique_suffix("clip-3.jpg").as_deref(),
+        Some("clip.jpg")
+    );
+    assert_eq!(strip_unique_suffix("clip-a.jpg"), None);
+    assert_eq!(
+        restore_entry_media_sync(&config, "- clip\n![[clip.jpg]]").unwrap(),
+        1
+    );
+    assert!(config.media_dir.join("clip.jpg").exists());
+}
+
+#[test]
+fn escape_markdown_v2_escapes_reserved_characters() {
+    assert_eq!(
+        escape_markdown_v2("Saved. See [post](https://a.example/x_y) - 1+1=2!"),
+        "Saved\\. See \\[post\\]\\(https://a\\.example/x\\_y\\) \\- 1\\+1\\=2\\!"
+    );
+    assert_eq!(
+        format_outgoing("a < b & c", OutgoingParseMode::Html),
+        "a &lt; b &amp; c"
+    );
+    assert_eq!(format_outgoing("a.b", OutgoingParseMode::Plain), "a.b");
+}
+
+#[test]
+fn queued_archive_keeps_its_cutoff() {
+    let temp = TempDir::new().unwrap();
+    let queue_path = temp.path().join("queue.json");
+    let cutoff = NaiveDate::from_ymd_opt(2026, 9, 16).unwrap();
+    let op = QueuedOp {
+        kind: QueuedOpKind::Archive { cutoff },
+        entry: String::new(),
+        resource_path: None,
+        updated_entry: None,
+        position: None,
+    };
+    save_queue(&queue_path, &[op]).unwrap();
+
+    let loaded = load_queue(&queue_path).unwrap();
+    assert!(matches!(
+        loaded[0].kind,
+        QueuedOpKind::Archive { cutoff: loaded_cutoff } if loaded_cutoff == cutoff
+    ));
+}
+
+#[test]
+fn load_queue_and_undo_recover_from_invalid_json() {
+    let temp = TempDir::new().unwrap();
+    let queue_path = temp.path().join("queue.json");
+    let undo_path = temp.path().join("undo.json");
+    fs::write(&queue_path, "[{\"kind\": \"Add\", \"entry\"").unwrap();
+    fs::write(&undo_path, "not json").unwrap();
+
+    assert!(load_queue(&queue_path).unwrap().is_empty());
+    assert!(load_undo(&undo_path).unwrap().is_empty());
+    assert!(!queue_path.exists());
+    assert_eq!(
+        fs::read_to_string(temp.path().join("queue.json.corrupt")).unwrap(),
+        "[{\"kind\": \"Add\", \"entry\""
+    );
+    assert!(temp.path().join("undo.json.corrupt").exists());
+}
+
+#[test]
+fn add_entry_sync_blocks_links_already_in_finished() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    let finished = temp.path().join("finished.md");
+    fs::write(&read_later, "- existing\n").unwrap();
+    fs::write(&finished, "- [Post](https://example.com/post) done\n").unwrap();
+
+    let repeat = entry("again https://example.com/post");
+    assert!(matches!(
+        add_entry_sync(&read_later, &repeat, Some(&finished), DedupeBy::Exact, None).unwrap(),
+        AddOutcome::AlreadyFinished
+    ));
+    assert_eq!(fs::read_to_string(&read_later).unwrap(), "- existing\n");
+
+    let fresh = entry("https://example.com/other");
+    assert!(matches!(
+        add_entry_sync(&read_later, &fresh, Some(&finished), DedupeBy::Exact, None).unwrap(),
+        AddOutcome::Added
+    ));
+    assert!(matches!(
+        add_entry_sync(&read_later, &repeat, None, DedupeBy::Exact, None).unwrap(),
+        AddOutcome::Added
+    ));
+}
+
+#[test]
+fn add_entry_sync_ignores_added_date_for_duplicates() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    fs::write(&read_later, "- 2026-10-15 same text\n").unwrap();
+
+    let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let again = entry(&stamp_added_date("same text", today));
+    assert!(matches!(
+        add_entry_sync(&read_later, &again, None, DedupeBy::Exact, None).unwrap(),
+        AddOutcome::Duplicate
+    ));
+    assert!(matches!(
+        add_entry_sync(
+            &read_later,
+            &entry("same text"),
+            None,
+            DedupeBy::Exact,
+            None
+        )
+        .unwrap(),
+        AddOutcome::Duplicate
+    ));
+    assert!(matches!(
+        add_entry_sync(&read_later, &again, None, DedupeBy::FirstLink, None).unwrap(),
+        AddOutcome::Duplicate
+    ));
+    assert_eq!(strip_added_date("- 2026-10-15x text"), "- 2026-10-15x text");
+}
+
+#[test]
+fn entries_per_day_groups_trailing_week() {
+    let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let entries = vec![
+        entry(&stamp_added_date("a", today)),
+        entry("2026-10-16 b"),
+        entry("2026-10-14 c\nmore"),
+        entry("2026-10-10 d"),
+        entry("2026-10-09 too old"),
+        entry("undated"),
+        entry("2026-10-15x not a date"),
+    ];
+    let d = |day| NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+    assert_eq!(
+        entries_per_day(&entries, today, 7),
+        vec![(d(16), 2), (d(14), 1), (d(10), 1)]
+    );
+    assert!(build_week_text(&entries, today).starts_with("Added in the last 7 days: 4"));
+    assert!(build_week_text(&[entry("undated")], today).starts_with("No dated entries."));
+}
+
+#[test]
+fn media_sweep_skips_referenced_and_recent_files() {
+    let day = Duration::from_secs(24 * 60 * 60);
+    let now = UNIX_EPOCH + day * 100;
+    let old = now - day * 31;
+    let recent = now - day * 29;
+    let kept = PathBuf::from("/tmp/media/kept.jpg");
+    let stray = PathBuf::from("/tmp/media/stray.jpg");
+    let referenced: HashSet<PathBuf> = [kept.clone()].into_iter().collect();
+
+    assert!(is_media_sweep_eligible(&stray, old, now, 30, &referenced));
+    assert!(!is_media_sweep_eligible(
+        &stray,
+        recent,
+        now,
+        30,
+        &referenced
+    ));
+    assert!(!is_media_sweep_eligible(&kept, old, now, 30, &referenced));
+    assert!(!is_media_sweep_eligible(
+        &stray,
+        now + day,
+        now,
+        30,
+        &referenced
+    ));
+}
+
+#[test]
+fn sweep_old_media_keeps_files_only_resources_reference() {
+    let temp = TempDir::new().unwrap();
+    let mut config = test_config();
+    config.media_dir = temp.path().join("media");
+    config.read_later_path = temp.path().join("read-later.md");
+    config.finished_path = temp.path().join("finished.md");
+    config.resources_path = temp.path().join("resources");
+    fs::create_dir_all(&config.media_dir).unwrap();
+    fs::create_dir_all(&config.resources_path).unwrap();
+    fs::write(
+        config.resources_path.join("Rust.md"),
+        "- diagram ![[kept.jpg]]\n",
+    )
+    .unwrap();
+
+    let old = SystemTime::now() - Duration::from_secs(60 * 24 * 60 * 60);
+    for name in ["kept.jpg", "stray.jpg"] {
+        let path = config.media_dir.join(name);
+        fs::write(&path, b"jpg").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+    }
+
+    assert_eq!(sweep_old_media_sync(&config, 30).unwrap(), 1);
+    assert!(config.media_dir.join("kept.jpg").exists());
+    assert!(!config.media_dir.join("stray.jpg").exists());
+}
+
+#[test]
+fn escape_nonprintable_reveals_nbsp_and_tab() {
+    assert_eq!(escape_nonprintable("a\u{00A0}b"), "a\\u{00A0}b");
+    assert_eq!(escape_nonprintable("a\tb"), "a\\tb");
+    assert_eq!(
+        escape_nonprintable("- plain text\n  next"),
+        "- plain text\n  next"
+    );
+}
+
+#[test]
+fn parse_command_accepts_custom_prefix() {
+    assert_eq!(parse_command("!add some text", "!"), Some("add"));
+    assert_eq!(parse_command("/add@readlater_bot text", "!"), Some("add"));
+    assert_eq!(parse_command("/list", "/"), Some("list"));
+    assert_eq!(parse_command("add some text", "!"), None);
+    assert_eq!(parse_command("?add", "!"), None);
+}
+
+#[test]
+fn snapshot_round_trips_through_json() {
+    let snapshot = Snapshot {
+        version: SNAPSHOT_VERSION,
+        created_at: 1_700_000_000,
+        read_later: "# Read Later\n\n- https://example.com/a\n".to_string(),
+        finished: "- https://example.com/b\n".to_string(),
+        queue: vec![QueuedOp {
+            kind: QueuedOpKind::Add,
+            entry: "- queued".to_string(),
+            resource_path: None,
+            updated_entry: None,
+            position: None,
+        }],
+        undo: vec![UndoRecord {
+            id: "abc".to_string(),
+            kind: UndoKind::Delete,
+            entry: "- deleted".to_string(),
+            expires_at: 42,
+        }],
+    };
+
+    let data = serialize_snapshot(&snapshot).unwrap();
+    let parsed = parse_snapshot(&data).unwrap();
+
+    assert_eq!(parsed.created_at, snapshot.created_at);
+    assert_eq!(parsed.read_later, snapshot.read_later);
+    assert_eq!(parsed.finished, snapshot.finished);
+    assert_eq!(parsed.queue.len(), 1);
+    assert_eq!(parsed.queue[0].entry, "- queued");
+    assert_eq!(parsed.undo.len(), 1);
+    assert_eq!(parsed.undo[0].id, "abc");
+    assert!(matches!(parsed.undo[0].kind, UndoKind::Delete));
+
+    assert!(parse_snapshot(b"[]").is_err());
+}
+
+#[test]
+fn compact_undo_drops_records_that_cannot_apply() {
+    let record = |id: &str, kind: UndoKind, text: &str, expires_at: u64| UndoRecord {
+        id: id.to_string(),
+        kind,
+        entry: entry(text).block_string(),
+        expires_at,
+    };
+    let live = now_ts() + 60;
+    let mut undo = vec![
+        record("finished", UndoKind::MoveToFinished, "alpha", live),
+        record("unfinished", UndoKind::MoveToFinished, "beta", live),
+        record("deleted", UndoKind::Delete, "gamma", live),
+        record("restored", UndoKind::Delete, "delta", live),
+        record("expired", UndoKind::Delete, "epsilon", 1),
+    ];
+    let read_later = vec![entry("beta"), entry("delta")];
+    let finished = vec![entry("alpha")];
+
+    let removed = compact_undo(&mut undo, &read_later, &finished);
+
+    assert_eq!(removed, 3);
+    let ids: Vec<&str> = undo.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, vec!["finished", "deleted"]);
+}
+
+#[test]
+fn compact_undo_keeps_finished_records_when_stamped() {
+    let date = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let mut undo = vec![
+        UndoRecord {
+            id: "finished".to_string(),
+            kind: UndoKind::MoveToFinished,
+            entry: entry("alpha").block_string(),
+            expires_at: now_ts() + 60,
+        },
+        UndoRecord {
+            id: "to-resource".to_string(),
+            kind: UndoKind::FinishToResource {
+                resource_path: PathBuf::from("/tmp/resources/Rust.md"),
+                added: true,
+            },
+            entry: entry("beta").block_string(),
+            expires_at: now_ts() + 60,
+        },
+    ];
+    let finished = vec![
+        stamp_finished(&entry("alpha"), date),
+        stamp_finished(&entry("beta"), date),
+    ];
+
+    assert_eq!(compact_undo(&mut undo, &[], &finished), 0);
+    assert_eq!(undo.len(), 2);
+}
+
+#[test]
+fn strip_finished_stamp_leaves_user_typed_lines() {
+    let date = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let stamped = stamp_finished(&entry("alpha\nnotes"), date);
+    assert_eq!(
+        strip_finished_stamp(&stamped).block_string(),
+        entry("alpha\nnotes").block_string()
+    );
+
+    for typed in [
+        "alpha\n  - Finished: soon",
+        "alpha\n  - Finished: 2026-1-5",
+        "alpha\n  - Finished: 2026-10-16 at home",
+    ] {
+        let entry = entry(typed);
+        assert_eq!(
+            strip_finished_stamp(&entry).block_string(),
+            entry.block_string()
+        );
+    }
+}
+
+#[test]
+fn default_resource_path_resolves_inside_resources_dir() {
+    let mut config = test_config();
+    assert_eq!(default_resource_path(&config), None);
+
+    config.default_resource_file = Some(PathBuf::from("Links.md"));
+    assert_eq!(
+        default_resource_path(&config),
+        Some(PathBuf::from("/tmp/resources/Links.md"))
+    );
+
+    config.default_resource_file = Some(PathBuf::from("/srv/notes/Tools.md"));
+    assert_eq!(
+        default_resource_path(&config),
+        Some(PathBuf::from("/srv/notes/Tools.md"))
+    );
+}
+
+#[test]
+fn resource_added_to_default_file_can_be_moved_out() {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("Links.md");
+    let block = resource_block_from_text("https://example.com/tool\nhandy");
+
+    add_resource_entry_sync(&path, &block).unwrap();
+    assert!(fs::read_to_string(&path)
+        .unwrap()
+        .contains("example.com/tool"));
+
+    let outcome = delete_entry_sync(&path, &block, None).unwrap();
+    assert!(matches!(outcome, ModifyOutcome::Applied));
+    assert!(!fs::read_to_string(&path)
+        .unwrap()
+        .contains("example.com/tool"));
+}
+
+#[test]
+fn daily_counts_buckets_every_day_in_range() {
+    let d = |day| NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+    let entries = vec![
+        entry("2026-10-01 before range"),
+        entry("2026-10-02 first day"),
+        entry("2026-10-04 a"),
+        entry("2026-10-04 b"),
+        entry("2026-10-05 last day"),
+        entry("2026-10-06 after range"),
+        entry("undated"),
+    ];
+    assert_eq!(
+        daily_counts(&entries, d(2), d(5)),
+        vec![(d(2), 1), (d(3), 0), (d(4), 2), (d(5), 1)]
+    );
+
+    let text = build_heatmap_text(&entries, d(16));
+    assert!(text.starts_with("Added in the last 30 days: 6"));
+    assert!(build_heatmap_text(&[entry("undated")], d(16)).starts_with("No dated entries."));
+}
+
+#[test]
+fn session_undo_reinserts_at_captured_index() {
+    let mut entries = vec![entry("one"), entry("two"), entry("three")];
+    let removed = entries.remove(1);
+
+    let index = reinsert_session_entry(&mut entries, removed, 1);
+    assert_eq!(index, 1);
+    let texts: Vec<String> = entries.iter().map(|e| e.block_string()).collect();
+    assert_eq!(
+        texts,
+        vec![
+            entry("one").block_string(),
+            entry("two").block_string(),
+            entry("three").block_string()
+        ]
+    );
+
+    let mut shrunk = vec![entry("one")];
+    assert_eq!(reinsert_session_entry(&mut shrunk, entry("late"), 5), 1);
+    assert_eq!(shrunk[1].block_string(), entry("late").block_string());
+}
+
+#[test]
+fn session_undo_restores_file_at_matching_position() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    let finished = temp.path().join("finished.md");
+    fs::write(&read_later, "- a\n- c\n- d\n").unwrap();
+    fs::write(&finished, "- b\n").unwrap();
+
+    // A search session showing a, b, d had b finished from index 1.
+    let file_entries = read_entries(&read_later).unwrap().1;
+    let session_entries = vec![entry("a"), entry("d")];
+    let position = undo_file_position(&file_entries, &session_entries, 1);
+    assert_eq!(position, 2);
+
+    let outcome = move_to_read_later_sync(&read_later, &finished, "- b", Some(position)).unwrap();
+    assert!(matches!(outcome, ModifyOutcome::Applied));
+    assert_eq!(
+        fs::read_to_string(&read_later).unwrap(),
+        "- a\n- c\n- b\n- d\n"
+    );
+
+    assert_eq!(undo_file_position(&file_entries, &[entry("a")], 1), 1);
+    assert_eq!(undo_file_position(&file_entries, &[], 7), 7);
+}
+
+#[test]
+fn menu_offers_session_undo_after_an_action() {
+    let mut session = test_session(vec![entry("one")], ListView::Menu);
+    let config = test_config();
+    let has_undo = |session: &ListSession| {
+        let (_, kb) = render_list_view("session", session, &HashSet::new(), &config);
+        kb.inline_keyboard
+            .iter()
+            .flatten()
+            .any(|button| button.text == "Undo last action")
+    };
+    assert!(!has_undo(&session));
+
+    session.session_undo.push(SessionUndo {
+        undo_id: "abc".to_string(),
+        entry: entry("two"),
+        index: 1,
+    });
+    assert!(has_undo(&session));
+}
+
+#[test]
+fn list_update_order_follows_media_before_text() {
+    let mut config = test_config();
+    assert_eq!(
+        list_update_order(&config),
+        [ListUpdateStep::Text, ListUpdateStep::Media]
+    );
+    config.media_before_text = true;
+    assert_eq!(
+        list_update_order(&config),
+        [ListUpdateStep::Media, ListUpdateStep::Text]
+    );
+}
+
+#[test]
+fn move_many_to_finished_moves_search_results() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    let finished = temp.path().join("finished.md");
+    fs::write(
+        &read_later,
+        "- rust async book\n- cooking tips\n- rust nomicon\n- gardening\n",
+    )
+    .unwrap();
+    fs::write(&finished, "- already done\n").unwrap();
+
+    let (_, entries) = read_entries(&read_later).unwrap();
+    let matches = search_entries(&entries, "rust");
+    let blocks: Vec<String> = matches.iter().map(|e| e.block_string()).collect();
+    let moved = move_many_to_finished_sync(
+        &read_later,
+        &finished,
+        &blocks,
+        None,
+        FinishedOrder::Prepend,
+    )
+    .unwrap();
+    assert_eq!(moved, blocks);
+
+    let remaining: Vec<String> = read_entries(&read_later)
+        .unwrap()
+        .1
+        .iter()
+        .map(|e| e.block_string())
+        .collect();
+    assert_eq!(remaining, vec!["- cooking tips", "- gardening"]);
+    let done: Vec<String> = read_entries(&finished)
+        .unwrap()
+        .1
+        .iter()
+        .map(|e| e.block_string())
+        .collect();
+    assert_eq!(
+        done,
+        vec!["- rust async book", "- rust nomicon", "- already done"]
+    );
+
+    assert!(move_many_to_finished_sync(
+        &read_later,
+        &finished,
+        &blocks,
+        None,
+        FinishedOrder::Prepend
+    )
+    .unwrap()
+    .is_empty());
+}
+
+#[test]
+fn first_link_dedupe_treats_titled_and_bare_links_as_duplicates() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    fs::write(&read_later, "- [Some Article](https://example.com/post)\n").unwrap();
+    let bare = entry("https://example.com/post");
+
+    assert_eq!(
+        dedupe_key(
+            "- [Some Article](https://example.com/post)",
+            DedupeBy::FirstLink
+        ),
+        dedupe_key("- https://example.com/post", DedupeBy::FirstLink)
+    );
+    assert!(matches!(
+        add_entry_sync(&read_later, &bare, None, DedupeBy::FirstLink, None).unwrap(),
+        AddOutcome::Duplicate
+    ));
+    assert!(matches!(
+        add_entry_sync(&read_later, &bare, None, DedupeBy::Exact, None).unwrap(),
+        AddOutcome::Added
+    ));
+}
+
+#[test]
+fn start_fresh_clears_peeked_and_random_history() {
+    let mut session = ListSession {
+        seen_random: [0, 1].into_iter().collect(),
+        ..test_session(
+            vec![entry("one"), entry("two")],
+            ListView::Selected {
+                return_to: Box::new(ListView::Menu),
+                index: 1,
+            },
+        )
+    };
+    let mut peeked: HashSet<String> = session.entries.iter().map(|e| e.block_string()).collect();
+
+    start_fresh(&mut session, &mut peeked);
+
+    assert!(peeked.is_empty());
+    assert!(session.seen_random.is_empty());
+    assert!(matches!(session.view, ListView::Menu));
+}
+
+#[test]
+fn extract_links_skips_links_below_min_length() {
+    let text = "see http://a and https://example.com/article";
+    assert_eq!(
+        extract_links(text, 0),
+        vec![
+            "http://a".to_string(),
+            "https://example.com/article".to_string()
+        ]
+    );
+    assert_eq!(
+        extract_links(text, 12),
+        vec!["https://example.com/article".to_string()]
+    );
+}
+
+#[test]
+fn is_bare_link_entry_detects_untitled_links() {
+    assert!(is_bare_link_entry(&entry("https://example.com/post")));
+    assert!(is_bare_link_entry(&entry(
+        "2026-10-16 https://example.com/post"
+    )));
+    assert!(is_bare_link_entry(&entry(
+        "https://example.com/post\nsome notes"
+    )));
+    assert!(!is_bare_link_entry(&entry(
+        "[Post](https://example.com/post)"
+    )));
+    assert!(!is_bare_link_entry(&entry(
+        "read this https://example.com/post"
+    )));
+    assert!(!is_bare_link_entry(&entry("plain note")));
+}
+
+#[test]
+fn set_entry_category_replaces_or_adds_prefix() {
+    assert_eq!(
+        set_entry_category("- [rust] async book\n  notes", "cooking"),
+        "- [cooking] async book\n  notes"
+    );
+    assert_eq!(
+        set_entry_category("- plain note", "misc"),
+        "- [misc] plain note"
+    );
+    assert_eq!(
+        set_entry_category("- [Post](https://example.com/post)", "web"),
+        "- [web] [Post](https://example.com/post)"
+    );
+    assert_eq!(
+        set_entry_category("- 2026-10-16 [old] dated", "new"),
+        "- 2026-10-16 [new] dated"
+    );
+    assert_eq!(set_entry_category("- [old] text", ""), "- text");
+}
+
+#[test]
+fn pick_poll_options_uses_unpeeked_first_lines() {
+    let entries: Vec<EntryBlock> = (0..15)
+        .map(|i| entry(&format!("item {}\nsecond line", i)))
+        .collect();
+    let mut peeked = HashSet::new();
+    peeked.insert(entries[0].block_string());
+
+    let options = pick_poll_options(&entries, &peeked, 3);
+    assert_eq!(options.len(), 3);
+    assert!(options.iter().all(|o| o.starts_with("item ")));
+    assert!(!options.contains(&"item 0".to_string()));
+
+    assert_eq!(pick_poll_options(&entries, &peeked, 50).len(), 10);
+
+    let long = entry(&"x".repeat(150));
+    let label = poll_option_label(&long);
+    assert_eq!(label.chars().count(), 100);
+    assert!(label.ends_with("..."));
+}
+
+#[test]
+fn view_entry_text_aggregates_page_links_when_enabled() {
+    let session = test_session(
+        vec![
+            entry("first https://example.com/a"),
+            entry("second https://example.com/b"),
+            entry("third https://example.com/c"),
+        ],
+        ListView::Peek {
+            mode: ListMode::Top,
+            page: 0,
+        },
+    );
+    let peeked = HashSet::new();
+
+    assert_eq!(
+        view_entry_text(&session, &peeked, false, DEFAULT_PAGE_SIZE),
+        None
+    );
+    let text = view_entry_text(&session, &peeked, true, DEFAULT_PAGE_SIZE).unwrap();
+    assert_eq!(
+        extract_links(&text, 0),
+        vec![
+            "https://example.com/a".to_string(),
+            "https://example.com/b".to_string(),
+            "https://example.com/c".to_string()
+        ]
+    );
+}
+
+#[test]
+fn entries_by_link_count_sorts_descending() {
+    let entries = vec![
+        entry("one https://a.example"),
+        entry("none"),
+        entry("three https://a.example https://b.example https://c.example"),
+        entry("two https://a.example\nhttps://b.example"),
+        entry("also one https://d.example"),
+    ];
+    let ranked: Vec<(usize, String)> = entries_by_link_count(&entries)
+        .into_iter()
+        .map(|(count, entry)| (count, entry.display_lines()[0].clone()))
+        .collect();
+    assert_eq!(
+        ranked,
+        vec![
+            (
+                3,
+                "three https://a.example https://b.example https://c.example".to_string()
+            ),
+            (2, "two https://a.example".to_string()),
+            (1, "one https://a.example".to_string()),
+            (1, "also one https://d.example".to_string()),
+        ]
+    );
+    assert!(build_link_heavy_text(&entries).contains("1) 3 links: three"));
+}
+
+#[test]
+fn finish_with_note_appends_note_line() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    let finished = temp.path().join("finished.md");
+    fs::write(
+        &read_later,
+        "- https://a.example\n  context\n- https://b.example\n",
+    )
+    .unwrap();
+    fs::write(&finished, "").unwrap();
+
+    let entry = "- https://a.example\n  context";
+    let updated = entry_with_note(entry, "  worth rereading\n\nthe intro  ");
+    assert_eq!(
+        updated,
+        "- https://a.example\n  context\n  - Note: worth rereading the intro"
+    );
+
+    move_to_finished_updated_sync(
+        &read_later,
+        &finished,
+        entry,
+        &updated,
+        None,
+        FinishedOrder::Prepend,
+    )
+    .unwrap();
+    let finished_contents = fs::read_to_string(&finished).unwrap();
+    assert!(finished_contents.contains("  - Note: worth rereading the intro"));
+    let (_, entries) = read_entries(&finished).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].block_string(), updated);
+    assert_eq!(read_entries(&read_later).unwrap().1.len(), 1);
+}
+
+#[test]
+fn near_duplicate_groups_by_first_link() {
+    let entries = vec![
+        entry("[Intro](https://a.example/post)"),
+        entry("https://b.example"),
+        entry("https://a.example/post "),
+        entry("no link here"),
+        entry("[Intro, revised](https://a.example/post) https://c.example"),
+        entry("https://c.example https://b.example"),
+    ];
+    let groups: Vec<(String, Vec<String>)> = near_duplicate_groups(&entries)
+        .into_iter()
+        .map(|(link, members)| {
+            let firsts = members
+                .iter()
+                .map(|entry| entry.display_lines()[0].clone())
+                .collect();
+            (link, firsts)
+        })
+        .collect();
+    assert_eq!(
+        groups,
+        vec![(
+            "https://a.example/post".to_string(),
+            vec![
+                "[Intro](https://a.example/post)".to_string(),
+                "https://a.example/post ".to_string(),
+                "[Intro, revised](https://a.example/post) https://c.example".to_string(),
+            ]
+        )]
+    );
+    assert_eq!(
+        build_near_duplicates_text(&entries[1..2]),
+        "No near-duplicates found."
+    );
+}
+
+#[test]
+fn auto_create_resource_path_only_when_no_files() {
+    let mut config = test_config();
+    config.default_resource_file = Some(PathBuf::from("Links.md"));
+    assert_eq!(auto_create_resource_path(&config, &[]), None);
+
+    config.auto_create_default_resource = true;
+    assert_eq!(
+        auto_create_resource_path(&config, &[]),
+        Some(config.resources_path.join("Links.md"))
+    );
+    assert_eq!(
+        auto_create_resource_path(&config, &[config.resources_path.join("Other.md")]),
+        None
+    );
+
+    config.default_resource_file = None;
+    assert_eq!(auto_create_resource_path(&config, &[]), None);
+}
+
+#[test]
+fn has_embedded_media_counts_unresolved_markers() {
+    let temp = TempDir::new().unwrap();
+    let mut config = test_config();
+    config.media_dir = temp.path().to_path_buf();
+    fs::write(temp.path().join("shot.png"), b"png").unwrap();
+
+    let resolved = entry("screenshot\n![[shot.png]]");
+    let missing = entry("gone ![[deleted.png|300]]");
+    assert!(has_embedded_media(&resolved));
+    assert!(has_embedded_media(&missing));
+    assert!(extract_embedded_paths(&missing.lines, &config).is_empty());
+
+    assert!(!has_embedded_media(&entry("https://example.com")));
+    assert!(!has_embedded_media(&entry("empty ![[ ]] and [[note]]")));
+    assert!(!has_embedded_media(&entry("unterminated ![[shot.png")));
+}
+
+#[test]
+fn undos_refresh_records_keeps_remaining_in_order() {
+    let record = |id: &str| UndoRecord {
+        id: id.to_string(),
+        kind: UndoKind::Delete,
+        entry: format!("- {}", id),
+        expires_at: now_ts() + 60,
+    };
+    let shown = vec![record("a"), record("b"), record("c")];
+    let current = vec![record("c"), record("a"), record("z")];
+
+    assert!(undos_refresh_records(false, &shown, &current).is_none());
+    let ids: Vec<String> = undos_refresh_records(true, &shown, &current)
+        .unwrap()
+        .into_iter()
+        .map(|record| record.id)
+        .collect();
+    assert_eq!(ids, vec!["a".to_string(), "c".to_string()]);
+    assert!(undos_refresh_records(true, &shown[1..2], &current).is_none());
+}
+
+#[test]
+fn refresh_pull_warning_only_on_failure() {
+    assert_eq!(refresh_pull_warning(None), None);
+    assert_eq!(refresh_pull_warning(Some(&Ok(PullOutcome::Pulled))), None);
+    assert_eq!(refresh_pull_warning(Some(&Ok(PullOutcome::UpToDate))), None);
+    assert_eq!(
+        refresh_pull_warning(Some(&Err(anyhow!("not a fast-forward")))),
+        Some("Pull failed, showing local list: not a fast-forward".to_string())
+    );
+}
+
+#[test]
+fn set_entry_due_adds_replaces_and_removes_marker() {
+    let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+    let entry = "- [work] file taxes https://example.com\n  notes";
+
+    let due = set_entry_due(entry, Some(date("2026-10-20")));
+    assert_eq!(
+        due,
+        "- [work] file taxes https://example.com (due: 2026-10-20)\n  notes"
+    );
+    assert_eq!(
+        due_of(&EntryBlock::from_block(&due)),
+        Some(date("2026-10-20"))
+    );
+
+    let moved = set_entry_due(&due, Some(date("2026-11-01")));
+    assert_eq!(
+        moved,
+        "- [work] file taxes https://example.com (due: 2026-11-01)\n  notes"
+    );
+    assert_eq!(set_entry_due(&moved, None), entry);
+
+    assert_eq!(
+        parse_due_input("2026-10-20"),
+        Some(Some(date("2026-10-20")))
+    );
+    assert_eq!(parse_due_input(" - "), Some(None));
+    assert_eq!(parse_due_input("next week"), None);
+}
+
+#[test]
+fn due_entries_keeps_today_and_overdue() {
+    let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let entries = vec![
+        entry("future (due: 2026-10-17)"),
+        entry("today (due: 2026-10-16)"),
+        entry("no due date"),
+        entry("overdue (due: 2026-10-01)"),
+        entry("bad marker (due: soon)"),
+    ];
+    let firsts: Vec<String> = due_entries(entries, today)
+        .iter()
+        .map(|entry| entry.display_lines()[0].clone())
+        .collect();
+    assert_eq!(
+        firsts,
+        vec![
+            "overdue (due: 2026-10-01)".to_string(),
+            "today (due: 2026-10-16)".to_string(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn check_links_bounded_limits_in_flight_checks() {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_seen = Arc::new(AtomicUsize::new(0));
+    let links: Vec<String> = (0..12).map(|i| format!("https://{}.example", i)).collect();
+    let results = check_links_bounded(links.clone(), 3, |link| {
+        let in_flight = in_flight.clone();
+        let max_seen = max_seen.clone();
+        async move {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_seen.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            if link.starts_with("https://1") {
+                LinkHealth::Status(404)
+            } else {
+                LinkHealth::Ok
+            }
+        }
+    })
+    .await;
+
+    assert_eq!(max_seen.load(Ordering::SeqCst), 3);
+    let checked: Vec<String> = results.iter().map(|(link, _)| link.clone()).collect();
+    assert_eq!(checked, links);
+    assert!(build_broken_links_text(&results).starts_with("Broken links (3 of 12)"));
+}
+
+#[test]
+fn positive_or_default_rejects_zero() {
+    assert_eq!(positive_or_default(None, 8usize, "n").unwrap(), 8);
+    assert_eq!(positive_or_default(Some(2usize), 8, "n").unwrap(), 2);
+    let err = positive_or_default(Some(0u64), 10_000, "link_check_timeout_ms").unwrap_err();
+    assert_eq!(err.to_string(), "link_check_timeout_ms must be positive");
+}
+
+#[test]
+fn display_normalize_links_only_changes_rendered_text() {
+    let stored = entry("[Intro](https://a.example/post) and more\nsee [docs](https://b.example)");
+    let session = test_session(vec![stored.clone()], ListView::Menu);
+    let mut config = test_config();
+
+    let (text, _) = build_selected_view("session", &session, 0, &HashSet::new(), &config);
+    assert!(text.contains("[Intro](https://a.example/post) and more"));
+
+    config.display_normalize_links = true;
+    let (text, _) = build_selected_view("session", &session, 0, &HashSet::new(), &config);
+    assert!(text.contains("https://a.example/post and more\nsee https://b.example"));
+    let (text, _) = build_peek_view(
+        "session",
+        &session,
+        ListMode::Top,
+        0,
+        &HashSet::new(),
+        &config,
+    );
+    assert!(text.contains("1) https://a.example/post and more"));
+    assert_eq!(session.entries[0].block_string(), stored.block_string());
+}
+
+#[test]
+fn chart_series_covers_last_two_weeks() {
+    let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let entries = vec![
+        entry("2026-10-16 today"),
+        entry("2026-10-16 also today"),
+        entry("2026-10-03 first day"),
+        entry("2026-10-02 too old"),
+        entry("undated"),
+    ];
+    let series = chart_series(&entries, today);
+    assert_eq!(series.len(), CHART_DAYS as usize);
+    assert_eq!(
+        series[0],
+        (NaiveDate::from_ymd_opt(2026, 10, 3).unwrap(), 1)
+    );
+    assert_eq!(series[13], (today, 2));
+    assert_eq!(series.iter().map(|(_, count)| count).sum::<usize>(), 3);
+
+    let png = render_chart_png(&series).unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+    assert!(build_chart_text(&series).ends_with("10-16   2 ████████████████████"));
+}
+
+#[test]
+fn unique_filename_appends_numeric_suffix() {
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path();
+    assert_eq!(unique_filename(dir, "scan.pdf"), "scan.pdf");
+
+    fs::write(dir.join("scan.pdf"), b"one").unwrap();
+    assert_eq!(unique_filename(dir, "scan.pdf"), "scan-1.pdf");
+    fs::write(dir.join("scan-1.pdf"), b"two").unwrap();
+    assert_eq!(unique_filename(dir, "scan.pdf"), "scan-2.pdf");
+
+    fs::write(dir.join("notes"), b"three").unwrap();
+    assert_eq!(unique_filename(dir, "notes"), "notes-1");
+}
+
+#[test]
+fn move_to_position_sync_moves_to_first_and_last() {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("read-later.md");
+    fs::write(&path, "# Read Later\n- one\n- two\n  details\n- three\n").unwrap();
+    let firsts = |path: &Path| -> Vec<String> {
+        read_entries(path)
+            .unwrap()
+            .1
+            .iter()
+            .map(|entry| entry.display_lines()[0].clone())
+            .collect()
+    };
+
+    assert_eq!(
+        move_to_position_sync(&path, "- two\n  details", 1).unwrap(),
+        Some(0)
+    );
+    assert_eq!(firsts(&path), vec!["two", "one", "three"]);
+
+    assert_eq!(
+        move_to_position_sync(&path, "- two\n  details", 99).unwrap(),
+        Some(2)
+    );
+    assert_eq!(firsts(&path), vec!["one", "three", "two"]);
+    assert!(fs::read_to_string(&path)
+        .unwrap()
+        .starts_with("# Read Later\n"));
+
+    assert_eq!(move_to_position_sync(&path, "- missing", 1).unwrap(), None);
+    assert_eq!(parse_position("0"), None);
+    assert_eq!(parse_position(" 3 "), Some(3));
+}
+
+#[test]
+fn resolve_token_reads_env_var_reference() {
+    std::env::set_var("READLATER_TEST_TOKEN_SET", "123:abc\n");
+    assert_eq!(
+        resolve_token("${READLATER_TEST_TOKEN_SET}").unwrap(),
+        "123:abc"
+    );
+    assert_eq!(resolve_token("123:literal").unwrap(), "123:literal");
+
+    let err = resolve_token("${READLATER_TEST_TOKEN_UNSET}").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "token refers to ${READLATER_TEST_TOKEN_UNSET}, but READLATER_TEST_TOKEN_UNSET is not set"
+    );
+}
+
+#[test]
+fn largest_entries_picks_top_n_by_size() {
+    let entries = vec![
+        entry("short"),
+        entry("the longest item\nwith pasted content below"),
+        entry("medium sized"),
+        entry("tiny"),
+    ];
+    let ranked: Vec<(usize, String)> = largest_entries(&entries, 2)
+        .into_iter()
+        .map(|(size, entry)| (size, entry.display_lines()[0].clone()))
+        .collect();
+    assert_eq!(
+        ranked,
+        vec![
+            (44, "the longest item".to_string()),
+            (14, "medium sized".to_string()),
+        ]
+    );
+    assert_eq!(largest_entries(&entries, 10).len(), 4);
+    assert_eq!(build_biggest_text(&[]), "Read Later is empty.");
+}
+
+#[test]
+fn finish_title_step_asks_which_link_when_several() {
+    let links = extract_links("[A](https://a.example) and https://b.example", 0);
+    assert_eq!(
+        finish_title_step(links.clone()),
+        FinishTitleStep::PickLink(links.clone())
+    );
+    assert_eq!(
+        finish_title_step(links[..1].to_vec()),
+        FinishTitleStep::Prompt("https://a.example".to_string())
+    );
+    assert_eq!(finish_title_step(Vec::new()), FinishTitleStep::NoLink);
+
+    let (text, kb) = build_finish_link_view("session", &links);
+    assert!(text.contains("2) https://b.example"));
+    assert_eq!(
+        callback_data(&kb),
+        vec![
+            "ls:session:finish_link:0",
+            "ls:session:finish_link:1",
+            "ls:session:finish_cancel",
+        ]
+    );
+}
+
+#[test]
+fn build_stats_text_counts_lists_and_links() {
+    let read_later = vec![
+        entry("https://a.example"),
+        entry("[B](https://b.example)"),
+        entry("no link"),
+    ];
+    let finished = vec![entry("newest done"), entry("middle"), entry("oldest done")];
+    assert_eq!(
+        build_stats_text(&read_later, &finished, 7, 0, FinishedOrder::Prepend),
+        "Read Later: 3\nFinished: 3\nResources: 7\nRead Later items with links: 2\n\n\
+         Newest finished: newest done\nOldest finished: oldest done"
+    );
+    let appended: Vec<EntryBlock> = finished.iter().rev().cloned().collect();
+    assert!(
+        build_stats_text(&read_later, &appended, 7, 0, FinishedOrder::Append)
+            .ends_with("Newest finished: newest done\nOldest finished: oldest done")
+    );
+    assert_eq!(
+        build_stats_text(&[], &[], 0, 0, FinishedOrder::Prepend),
+        "Read Later: 0\nFinished: 0\nResources: 0\nRead Later items with links: 0"
+    );
+}
+
+#[test]
+fn find_media_problems_flags_zero_byte_files() {
+    let temp = TempDir::new().unwrap();
+    let mut config = test_config();
+    config.media_dir = temp.path().to_path_buf();
+    fs::write(temp.path().join("ok.pdf"), b"%PDF").unwrap();
+    fs::write(temp.path().join("empty.pdf"), b"").unwrap();
+    fs::write(temp.path().join("broken.png"), b"not a png").unwrap();
+
+    let entries = vec![
+        entry("fine ![[ok.pdf]]"),
+        entry("bad download ![[empty.pdf]]"),
+        entry("gone ![[missing.jpg|200]]"),
+        entry("garbled ![[broken.png]]"),
+    ];
+    let problems = find_media_problems(&entries, &config);
+    let summary: Vec<(String, bool, String)> = problems
+        .iter()
+        .map(|(path, problem, first)| {
+            (
+                resource_file_label(path),
+                matches!(problem, MediaProblem::Unreadable(_)),
+                first.clone(),
+            )
+        })
+        .collect();
+    assert_eq!(problems[0].1, MediaProblem::Empty);
+    assert_eq!(problems[1].1, MediaProblem::Missing);
+    assert_eq!(
+        summary,
+        vec![
+            (
+                "empty.pdf".to_string(),
+                false,
+                "bad download ![[empty.pdf]]".to_string()
+            ),
+            (
+                "missing.jpg".to_string(),
+                false,
+                "gone ![[missing.jpg|200]]".to_string()
+            ),
+            (
+                "broken.png".to_string(),
+                true,
+                "garbled ![[broken.png]]".to_string()
+            ),
+        ]
+    );
+    assert!(
+        build_verify_media_text(&problems).starts_with("Media problems (3)\n\nempty.pdf (empty)")
+    );
+}
+
+#[test]
+fn apply_auto_tag_appends_tag_for_matching_domain() {
+    let tags: HashMap<String, String> = [
+        ("youtube.com".to_string(), "video".to_string()),
+        ("arxiv.org".to_string(), "paper".to_string()),
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(
+        apply_auto_tag("https://www.youtube.com/watch?v=1", &tags),
+        "https://www.youtube.com/watch?v=1 #video"
+    );
+    assert_eq!(
+        apply_auto_tag("[Paper](https://export.arxiv.org/abs/1)\nnotes", &tags),
+        "[Paper](https://export.arxiv.org/abs/1) #paper\nnotes"
+    );
+    assert_eq!(
+        apply_auto_tag("https://youtube.com/x #video", &tags),
+        "https://youtube.com/x #video"
+    );
+    assert_eq!(
+        apply_auto_tag("https://notyoutube.com/x", &tags),
+        "https://notyoutube.com/x"
+    );
+    assert_eq!(apply_auto_tag("no link", &tags), "no link");
+}
+
+#[test]
+fn age_bucket_uses_trailing_windows() {
+    let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let days_ago = |days: i64| today - chrono::Duration::days(days);
+    assert_eq!(age_bucket(today, today), AgeBucket::Today);
+    assert_eq!(age_bucket(days_ago(1), today), AgeBucket::ThisWeek);
+    assert_eq!(age_bucket(days_ago(6), today), AgeBucket::ThisWeek);
+    assert_eq!(age_bucket(days_ago(7), today), AgeBucket::ThisMonth);
+    assert_eq!(age_bucket(days_ago(29), today), AgeBucket::ThisMonth);
+    assert_eq!(age_bucket(days_ago(30), today), AgeBucket::Older);
+
+    let entries = vec![
+        entry("2026-10-16 new"),
+        entry("2026-10-12 recent"),
+        entry("2026-09-01 old"),
+        entry("undated"),
+    ];
+    assert_eq!(
+        build_age_text(&entries, today),
+        "Read Later by age\n\nToday: 1\nThis week: 1\nThis month: 0\nOlder: 1\nUndated: 1"
+    );
+}
+
+#[test]
+fn archive_finished_sync_moves_old_dated_entries_by_year() {
+    let temp = TempDir::new().unwrap();
+    let finished = temp.path().join("finished.md");
+    fs::write(
+        &finished,
+        "# Finished\n- 2026-10-10 recent\n- undated\n- 2026-01-05 january\n  note\n- 2025-12-30 last year\n",
+    )
+    .unwrap();
+    fs::write(
+        archive_file_path(temp.path(), 2025),
+        "- 2025-06-01 already archived\n",
+    )
+    .unwrap();
+    let cutoff = NaiveDate::from_ymd_opt(2026, 9, 16).unwrap();
+
+    assert_eq!(
+        archive_finished_sync(&finished, temp.path(), cutoff).unwrap(),
+        (2, 1)
+    );
+    assert_eq!(
+        fs::read_to_string(&finished).unwrap(),
+        "# Finished\n- 2026-10-10 recent\n- undated\n"
+    );
+    assert_eq!(
+        fs::read_to_string(archive_file_path(temp.path(), 2026)).unwrap(),
+        "- 2026-01-05 january\n  note\n"
+    );
+    assert_eq!(
+        fs::read_to_string(archive_file_path(temp.path(), 2025)).unwrap(),
+        "- 2025-12-30 last year\n- 2025-06-01 already archived\n"
+    );
+    assert_eq!(
+        archive_finished_sync(&finished, temp.path(), cutoff).unwrap(),
+        (0, 1)
+    );
+}
+
+#[tokio::test]
+async fn save_then_send_downloads_once_and_sends_saved_file() {
+    use std::sync::atomic::AtomicUsize;
+
+    let downloads = AtomicUsize::new(0);
+    let sent = std::sync::Mutex::new(Vec::new());
+    let (path, ()) = save_then_send(
+        || async {
+            downloads.fetch_add(1, Ordering::SeqCst);
+            Ok(PathBuf::from("/media/clip.mp4"))
+        },
+        |path| async {
+            sent.lock().unwrap().push(path);
+            Ok(())
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(downloads.load(Ordering::SeqCst), 1);
+    assert_eq!(path, PathBuf::from("/media/clip.mp4"));
+    assert_eq!(
+        *sent.lock().unwrap(),
+        vec![PathBuf::from("/media/clip.mp4")]
+    );
+}
+
+#[test]
+fn unpeek_entry_removes_single_match_and_rejects_ambiguous() {
+    let entries = vec![
+        entry("rust async book"),
+        entry("rust by example"),
+        entry("go tour"),
+    ];
+    let mut peeked: HashSet<String> = entries.iter().map(|e| e.block_string()).collect();
+
+    assert_eq!(unpeek_entry(&entries, &mut peeked, "async"), Ok(true));
+    assert!(!peeked.contains(&entries[0].block_string()));
+    assert_eq!(unpeek_entry(&entries, &mut peeked, "async"), Ok(false));
+
+    let err = unpeek_entry(&entries, &mut peeked, "rust").unwrap_err();
+    assert!(err.starts_with("2 items match"));
+    assert!(peeked.contains(&entries[1].block_string()));
+    assert!(unpeek_entry(&entries, &mut peeked, "python").is_err());
+}
+
+#[test]
+fn finished_timestamp_is_added_and_stripped_on_undo() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    let finished = temp.path().join("finished.md");
+    fs::write(
+        &read_later,
+        "- https://a.example\n  context\n- https://b.example\n",
+    )
+    .unwrap();
+    fs::write(&finished, "").unwrap();
+    let entry = "- https://a.example\n  context";
+    let date = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+
+    move_to_finished_sync(
+        &read_later,
+        &finished,
+        entry,
+        Some(date),
+        FinishedOrder::Prepend,
+    )
+    .unwrap();
+    let (_, entries) = read_entries(&finished).unwrap();
+    assert_eq!(
+        entries[0].block_string(),
+        "- https://a.example\n  context\n  - Finished: 2026-10-16"
+    );
+    assert_eq!(entry_finished_date(&entries[0]), Some(date));
+    assert_eq!(
+        stamp_finished(&entries[0], date).block_string(),
+        entries[0].block_string()
+    );
+
+    let undo = undo_op_for_record(
+        &UndoRecord {
+            id: "u".to_string(),
+            kind: UndoKind::MoveToFinished,
+            entry: entry.to_string(),
+            expires_at: 0,
+        },
+        &test_config(),
+    );
+    assert!(matches!(undo.kind, QueuedOpKind::MoveToReadLater));
+    let outcome = move_to_read_later_sync(&read_later, &finished, &undo.entry, None).unwrap();
+    assert!(matches!(outcome, ModifyOutcome::Applied));
+    let (_, restored) = read_entries(&read_later).unwrap();
+    assert_eq!(restored[0].block_string(), entry);
+    assert!(read_entries(&finished).unwrap().1.is_empty());
+}
+
+#[test]
+fn sync_commit_args_include_configured_author() {
+    let mut sync = SyncConfig {
+        repo_path: PathBuf::from("/repo"),
+        token_file: None,
+        author_name: None,
+        author_email: None,
+        auth: SyncAuth::Pat,
+    };
+    assert_eq!(sync_commit_args(&sync, "msg"), ["commit", "-m", "msg"]);
+
+    sync.author_name = Some("Read Later Bot".to_string());
+    sync.author_email = Some("bot@example.com".to_string());
+    assert_eq!(
+        sync_commit_args(&sync, "msg"),
+        [
+            "-c",
+            "user.name=Read Later Bot",
+            "-c",
+            "user.email=bot@example.com",
+            "commit",
+            "-m",
+            "msg"
+        ]
+    );
+}
+
+#[test]
+fn page_size_controls_peek_pages_and_pick_buttons() {
+    assert_eq!(page_size_or_default(None).unwrap(), DEFAULT_PAGE_SIZE);
+    assert_eq!(page_size_or_default(Some(7)).unwrap(), 7);
+    assert!(page_size_or_default(Some(0)).is_err());
+    assert!(page_size_or_default(Some(11)).is_err());
+
+    let entries: Vec<EntryBlock> = (0..9).map(|i| entry(&format!("item {}", i))).collect();
+    let peeked = HashSet::new();
+    assert_eq!(
+        peek_indices(&entries, &peeked, ListMode::Top, 1, 7),
+        vec![7, 8]
+    );
+
+    let mut config = test_config();
+    config.page_size = 7;
+    let session = ListSession {
+        id: "s".to_string(),
+        chat_id: 1,
+        ..test_session(
+            entries,
+            ListView::Peek {
+                mode: ListMode::Top,
+                page: 0,
+            },
+        )
+    };
+    let (_, kb) = build_peek_view("s", &session, ListMode::Top, 0, &peeked, &config);
+    let picks: Vec<String> = callback_data(&kb)
+        .into_iter()
+        .filter(|data| data.contains(":pick:"))
+        .collect();
+    assert_eq!(picks.len(), 7);
+    assert_eq!(picks.last().unwrap(), "ls:s:pick:7");
+}
+
+#[test]
+fn peek_toggle_buttons_follow_peeked_state() {
+    let item = entry("https://a.example");
+    let session = ListSession {
+        id: "s".to_string(),
+        chat_id: 1,
+        ..test_session(vec![item.clone()], ListView::Menu)
+    };
+    let config = test_config();
+    let mut peeked = HashSet::new();
+
+    let label_for = |peeked: &HashSet<String>| {
+        let (_, kb) = build_selected_view("s", &session, 0, peeked, &config);
+        let position = callback_data(&kb)
+            .iter()
+            .position(|data| data == "ls:s:togglepeek")?;
+        kb.inline_keyboard
+            .iter()
+            .flatten()
+            .nth(position)
+            .map(|button| button.text.clone())
+    };
+    assert_eq!(label_for(&peeked).as_deref(), Some("Mark peeked"));
+    assert!(toggle_peeked(&mut peeked, &item));
+    assert_eq!(label_for(&peeked).as_deref(), Some("Unpeek"));
+    assert!(!toggle_peeked(&mut peeked, &item));
+    assert!(peeked.is_empty());
+
+    let (_, kb) = build_peek_view("s", &session, ListMode::Top, 0, &peeked, &config);
+    assert!(callback_data(&kb).contains(&"ls:s:peekall".to_string()));
+}
+
+#[test]
+fn peek_item_media_leaves_peeked_and_view_alone() {
+    let temp = TempDir::new().unwrap();
+    let media_dir = temp.path().join("media");
+    fs::create_dir_all(&media_dir).unwrap();
+    fs::write(media_dir.join("image-1.jpg"), b"x").unwrap();
+    let mut config = test_config();
+    config.media_dir = media_dir.clone();
+
+    let session = ListSession {
+        id: "s".to_string(),
+        ..test_session(
+            vec![
+                entry("plain item"),
+                entry("first line\nsecond line\nthird ![[image-1.jpg]]"),
+            ],
+            ListView::Peek {
+                mode: ListMode::Top,
+                page: 0,
+            },
+        )
+    };
+    let peeked: HashSet<String> = HashSet::from(["- already seen".to_string()]);
+    let before = peeked.clone();
+
+    let (_, kb) = build_peek_view("s", &session, ListMode::Top, 0, &peeked, &config);
+    let data = callback_data(&kb);
+    assert!(data.contains(&"ls:s:peekmedia:2".to_string()));
+    assert!(!data.contains(&"ls:s:peekmedia:1".to_string()));
+
+    assert_eq!(
+        peek_item_media_paths(&session, &peeked, 2, &config),
+        vec![media_dir.join("image-1.jpg")]
+    );
+    assert!(peek_item_media_paths(&session, &peeked, 1, &config).is_empty());
+    assert_eq!(peeked, before);
+    assert!(matches!(session.view, ListView::Peek { page: 0, .. }));
+}
+
+#[test]
+fn source_delete_delay_is_off_by_default() {
+    let mut config = test_config();
+    assert_eq!(source_delete_delay(&config), None);
+    config.source_delete_delay_ms = 750;
+    assert_eq!(
+        source_delete_delay(&config),
+        Some(Duration::from_millis(750))
+    );
+}
+
+#[test]
+fn peeked_set_round_trips_and_prunes_missing_entries() {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("peeked.json");
+    assert!(load_peeked(&path).unwrap().is_empty());
+
+    let entries = vec![entry("kept item"), entry("other item")];
+    let mut peeked: HashSet<String> =
+        HashSet::from(["- kept item".to_string(), "- gone item".to_string()]);
+    save_peeked(&path, &peeked).unwrap();
+    assert_eq!(load_peeked(&path).unwrap(), peeked);
+
+    assert!(prune_peeked(&mut peeked, &entries));
+    assert_eq!(peeked, HashSet::from(["- kept item".to_string()]));
+    assert!(!prune_peeked(&mut peeked, &entries));
+
+    fs::write(&path, "not json").unwrap();
+    assert!(load_peeked(&path).unwrap().is_empty());
+}
+
+#[test]
+fn tag_counts_aggregate_across_entries() {
+    let entries = vec![
+        entry("https://a.example #Rust #async"),
+        entry("notes on #rust, again #rust\n  - see #video"),
+        entry("https://b.example/#anchor # heading"),
+        entry("#video"),
+        entry("untagged"),
+    ];
+    assert_eq!(extract_tags(&entries[1]), vec!["rust", "video"]);
+    assert!(extract_tags(&entries[2]).is_empty());
+
+    let counts = tag_counts(&entries);
+    assert_eq!(
+        counts,
+        vec![
+            ("rust".to_string(), 2),
+            ("video".to_string(), 2),
+            ("async".to_string(), 1),
+        ]
+    );
+    assert!(build_tags_text(&counts).starts_with("Tags (3)\n\n#rust (2)"));
+    assert_eq!(build_tags_text(&[]), "No tags found.");
+}
+
+#[test]
+fn ssh_sync_auth_sets_key_command() {
+    let sync: SyncConfig = toml::from_str(
+        "repo_path = \"/repo\"\nauth = { type = \"ssh\", key_file = \"/keys/it's\" }\n",
+    )
+    .unwrap();
+    assert_eq!(
+        sync.auth,
+        SyncAuth::Ssh {
+            key_file: Some(PathBuf::from("/keys/it's"))
+        }
+    );
+    let default: SyncConfig = toml::from_str("repo_path = \"/repo\"\n").unwrap();
+    assert_eq!(default.auth, SyncAuth::Pat);
+
+    assert_eq!(
+        ssh_auth_env(None),
+        vec![("GIT_TERMINAL_PROMPT", "0".to_string())]
+    );
+    let env = ssh_auth_env(Some(Path::new("/keys/it's")));
+    assert_eq!(
+        env[1],
+        (
+            "GIT_SSH_COMMAND",
+            "ssh -i '/keys/it'\\''s' -o IdentitiesOnly=yes".to_string()
+        )
+    );
+}
+
+#[test]
+fn sync_status_parses_left_right_counts() {
+    assert_eq!(parse_ahead_behind("3\t1\n"), Some((1, 3)));
+    assert_eq!(parse_ahead_behind("0 0"), Some((0, 0)));
+    assert_eq!(parse_ahead_behind("2"), None);
+    assert_eq!(parse_ahead_behind("x\t1"), None);
+
+    let status = SyncStatus {
+        ahead: 1,
+        behind: 3,
+        dirty: true,
+    };
+    assert_eq!(
+        build_sync_status_text(&status),
+        "1 ahead, 3 behind, working tree dirty"
+    );
+}
+
+#[test]
+fn empty_entry_preview_uses_placeholder() {
+    assert_eq!(entry("").preview_lines(), vec!["(no text)"]);
+    assert_eq!(
+        EntryBlock::from_block("-\n  ![[image-1.jpg]]").preview_lines(),
+        vec!["(no text)", "  ![[image-1.jpg]]"]
+    );
+    assert_eq!(entry("title").preview_lines(), vec!["title"]);
+
+    let session = ListSession {
+        id: "s".to_string(),
+        ..test_session(
+            vec![entry("")],
+            ListView::Peek {
+                mode: ListMode::Top,
+                page: 0,
+            },
+        )
+    };
+    let (text, _) = build_peek_view(
+        "s",
+        &session,
+        ListMode::Top,
+        0,
+        &HashSet::new(),
+        &test_config(),
+    );
+    assert!(text.contains("1) (no text)"));
+}
+
+#[test]
+fn session_export_contains_only_session_entries() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    fs::write(
+        &read_later,
+        "- rust async book\n  notes\n- cooking tips\n- rust nomicon\n",
+    )
+    .unwrap();
+    let (_, entries) = read_entries(&read_later).unwrap();
+    let matches = search_entries(&entries, "rust");
+    let session = ListSession {
+        id: "s".to_string(),
+        kind: SessionKind::Search {
+            query: "rust".to_string(),
+        },
+        ..test_session(matches.clone(), ListView::Menu)
+    };
+
+    let (name, content) = session_export(&session);
+    assert_eq!(name, "search-results.md");
+    assert_eq!(content, "- rust async book\n  notes\n- rust nomicon\n");
+    let exported: Vec<String> = parse_entries(&content)
+        .1
+        .iter()
+        .map(|e| e.block_string())
+        .collect();
+    let expected: Vec<String> = matches.iter().map(|e| e.block_string()).collect();
+    assert_eq!(exported, expected);
+
+    let (_, kb) = build_menu_view("s", &session);
+    assert!(callback_data(&kb).contains(&"ls:s:export".to_string()));
+}
+
+#[test]
+fn audio_option_extracts_m4a_and_is_sent_as_audio() {
+    let option = audio_m4a_option();
+    assert_eq!(option.label, "Audio (m4a)");
+
+    let template = Path::new("/media/%(id)s.%(ext)s");
+    let audio = ytdlp_download_args(template, "https://v.example/1", &option.format_selector);
+    let extract = ["-x", "--audio-format", "m4a"].map(String::from);
+    assert!(audio.windows(3).any(|w| w == extract));
+    assert_eq!(audio.last().unwrap(), "https://v.example/1");
+    let video = ytdlp_download_args(template, "https://v.example/1", "22");
+    assert!(!video.contains(&"-x".to_string()));
+
+    assert!(is_audio_path(Path::new("/media/talk.M4A")));
+    assert!(is_audio_path(Path::new("/media/episode.mp3")));
+    assert!(!is_audio_path(Path::new("/media/clip.mp4")));
+    assert!(!is_audio_path(Path::new("/media/noext")));
+}
+
+#[test]
+fn tg_retry_delay_retries_only_transient_errors() {
+    use teloxide::{ApiError, RequestError};
+
+    let network = RequestError::from(reqwest::Client::new().get("not a url").build().unwrap_err());
+    assert_eq!(
+        tg_retry_delay(&network, 1),
+        Some(Duration::from_millis(2 * SEND_RETRY_BACKOFF_MS))
+    );
+    assert_eq!(
+        tg_retry_delay(&RequestError::RetryAfter(Duration::from_secs(2)), 0),
+        Some(Duration::from_secs(2))
+    );
+    assert_eq!(
+        tg_retry_delay(&RequestError::RetryAfter(Duration::from_secs(60)), 0),
+        None
+    );
+    assert_eq!(
+        tg_retry_delay(&RequestError::Api(ApiError::MessageToEditNotFound), 0),
+        None
+    );
+    assert_eq!(
+        tg_retry_delay(&RequestError::Api(ApiError::MessageNotModified), 0),
+        None
+    );
+}
+
+#[tokio::test]
+async fn tg_retry_stops_on_terminal_error() {
+    use std::sync::atomic::AtomicUsize;
+    use teloxide::{ApiError, RequestError};
+
+    let calls = AtomicUsize::new(0);
+    let result: Result<(), RequestError> = tg_retry(3, || {
+        calls.fetch_add(1, Ordering::SeqCst);
+        async { Err(RequestError::Api(ApiError::MessageToEditNotFound)) }
+    })
+    .await;
+    assert!(result.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let calls = AtomicUsize::new(0);
+    let result = tg_retry(3, || {
+        let attempt = calls.fetch_add(1, Ordering::SeqCst);
+        async move {
+            if attempt < 2 {
+                Err(RequestError::RetryAfter(Duration::from_millis(1)))
+            } else {
+                Ok(attempt)
+            }
+        }
+    })
+    .await;
+    assert_eq!(result.unwrap(), 2);
+}
+
+#[test]
+fn downloaded_files_are_sent_by_kind() {
+    assert_eq!(
+        download_send_kind(Path::new("/media/clip.MP4")),
+        DownloadSendKind::Video
+    );
+    assert_eq!(
+        download_send_kind(Path::new("/media/clip.webm")),
+        DownloadSendKind::Video
+    );
+    assert_eq!(
+        download_send_kind(Path::new("/media/talk.m4a")),
+        DownloadSendKind::Audio
+    );
+    assert_eq!(
+        download_send_kind(Path::new("/media/paper.pdf")),
+        DownloadSendKind::Document
+    );
+    assert_eq!(
+        download_send_kind(Path::new("/media/noext")),
+        DownloadSendKind::Document
+    );
+}
+
+#[test]
+fn collapse_blank_lines_keeps_one_blank_between_paragraphs() {
+    let messy = EntryBlock::from_block("- title\n  first\n\n\n   \n  second\n\n");
+    let tidy = collapse_blank_lines(&messy).unwrap();
+    assert_eq!(tidy.block_string(), "- title\n  first\n\n  second\n");
+    assert!(collapse_blank_lines(&tidy).is_none());
+
+    let blank_header = EntryBlock::from_block("-\n\n\n  body");
+    let tidy = collapse_blank_lines(&blank_header).unwrap();
+    assert_eq!(tidy.lines, vec!["-", "", "  body"]);
+}
+
+#[test]
+fn playlist_option_downloads_every_item_up_to_the_limit() {
+    let single: serde_json::Value = serde_json::json!({"_type": "video"});
+    let playlist: serde_json::Value = serde_json::json!({"_type": "playlist"});
+    assert!(!offers_playlist("https://v.example/watch?v=1", &single));
+    assert!(offers_playlist(
+        "https://v.example/watch?v=1&list=PL1",
+        &single
+    ));
+    assert!(offers_playlist("https://v.example/p", &playlist));
+    assert_eq!(playlist_option().format_selector, PLAYLIST_FORMAT_SELECTOR);
+
+    let args = ytdlp_playlist_args(Path::new("/m/%(id)s.%(ext)s"), "https://v.example/p", 5);
+    assert!(!args.contains(&"--no-playlist".to_string()));
+    assert!(args.windows(2).any(|w| w == ["--playlist-items", "1:5"]));
+
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("a.mp4"), b"x").unwrap();
+    fs::write(temp.path().join("b.mp4"), b"x").unwrap();
+    let stdout = format!(
+        "a.mp4\n\n{}\nmissing.mp4\n",
+        temp.path().join("b.mp4").display()
+    );
+    assert_eq!(
+        playlist_paths_from_output(&stdout, temp.path()),
+        vec![temp.path().join("a.mp4"), temp.path().join("b.mp4")]
+    );
+
+    let dir = Path::new("/m");
+    assert_eq!(
+        playlist_summary(DownloadAction::Save, 3, 0, dir, 50),
+        "Saved 3 files to /m"
+    );
+    assert_eq!(
+        playlist_summary(DownloadAction::Send, 3, 1, dir, 50),
+        "Sent 2 of 3 files. 1 over the 50 B limit."
+    );
+    assert_eq!(
+        playlist_summary(DownloadAction::SendAndSave, 2, 0, dir, 50),
+        "Saved 2 files to /m. Sent 2 of 2 files."
+    );
+}
+
+#[test]
+fn subtitle_option_skips_download_and_finds_srt() {
+    let option = subtitles_option();
+    assert_eq!(option.format_selector, SUBTITLES_FORMAT_SELECTOR);
+
+    let args = ytdlp_subtitle_args(Path::new("/tmp/%(id)s.%(ext)s"), "https://v.example/1");
+    for flag in ["--write-auto-sub", "--skip-download"] {
+        assert!(args.contains(&flag.to_string()));
+    }
+    assert!(args.windows(2).any(|w| w == ["--sub-lang", "en"]));
+    assert!(args.windows(2).any(|w| w == ["--convert-subs", "srt"]));
+    assert_eq!(args.last().unwrap(), "https://v.example/1");
+
+    let temp = TempDir::new().unwrap();
+    assert_eq!(find_subtitle_file(temp.path()).unwrap(), None);
+    fs::write(temp.path().join("talk-1.en.vtt"), "WEBVTT").unwrap();
+    assert_eq!(find_subtitle_file(temp.path()).unwrap(), None);
+    let srt = temp.path().join("talk-1.en.srt");
+    fs::write(&srt, "1\n00:00:00,000 --> 00:00:01,000\nhi\n").unwrap();
+    assert_eq!(find_subtitle_file(temp.path()).unwrap(), Some(srt));
+}
+
+#[test]
+fn archived_items_leave_random_pool_when_configured() {
+    let entries = vec![
+        entry("https://a.example"),
+        mark_archived(&entry("https://b.example")).unwrap(),
+        entry("https://c.example"),
+    ];
+    assert!(is_archived(&entries[1]));
+    assert_eq!(entries[1].lines[0], "- https://b.example (archived)");
+    assert!(mark_archived(&entries[1]).is_none());
+
+    let seen = HashSet::new();
+    let none = HashSet::new();
+    let peeked = HashSet::from([entries[2].block_string()]);
+    assert_eq!(
+        random_candidates(&entries, &seen, &none, false),
+        vec![0, 1, 2]
+    );
+    assert_eq!(random_candidates(&entries, &seen, &none, true), vec![0, 2]);
+    assert_eq!(random_candidates(&entries, &seen, &peeked, true), vec![0]);
+}
+
+#[test]
+fn send_size_error_only_over_cap() {
+    assert_eq!(
+        send_size_error(DEFAULT_MAX_SEND_BYTES, DEFAULT_MAX_SEND_BYTES),
+        None
+    );
+    assert_eq!(send_size_error(0, DEFAULT_MAX_SEND_BYTES), None);
+    let message = send_size_error(80 * 1024 * 1024, DEFAULT_MAX_SEND_BYTES).unwrap();
+    assert_eq!(
+        message,
+        "File is 80.0 MB, over the 47.7 MB upload limit. Use Save instead."
+    );
+}
+
+#[test]
+fn next_digest_at_rolls_over_to_tomorrow() {
+    let day = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let at = NaiveTime::from_hms_opt(8, 30, 0).unwrap();
+    let before = day.and_hms_opt(7, 0, 0).unwrap();
+    assert_eq!(next_digest_at(before, at), day.and_time(at));
+
+    let tomorrow = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap();
+    assert_eq!(next_digest_at(day.and_time(at), at), tomorrow.and_time(at));
+    let late = day.and_hms_opt(23, 59, 59).unwrap();
+    assert_eq!(next_digest_at(late, at), tomorrow.and_time(at));
+
+    let year_end = NaiveDate::from_ymd_opt(2026, 12, 31).unwrap();
+    let new_year = NaiveDate::from_ymd_opt(2027, 1, 1).unwrap();
+    assert_eq!(
+        next_digest_at(year_end.and_hms_opt(9, 0, 0).unwrap(), at),
+        new_year.and_time(at)
+    );
+
+    assert_eq!(parse_digest_time(Some("08:30")).unwrap(), Some(at));
+    assert!(parse_digest_time(Some("8.30")).is_err());
+    assert_eq!(parse_digest_time(None).unwrap(), None);
+}
+
+#[test]
+fn build_digest_text_skips_peeked_items() {
+    let entries = vec![entry("one"), entry("two")];
+    let all: HashSet<String> = entries.iter().map(|e| e.block_string()).collect();
+    assert_eq!(build_digest_text(&entries, &all), None);
+
+    let peeked = HashSet::from([entries[0].block_string()]);
+    assert_eq!(
+        build_digest_text(&entries, &peeked).unwrap(),
+        "Digest: 1 unpeeked\n\n- two"
+    );
+}
+
+#[test]
+fn orphans_text_surfaces_preamble_lines() {
+    let (preamble, entries) = parse_entries("# Read Later\n\nstray note\n- item\n");
+    assert_eq!(entries.len(), 1);
+    let text = build_orphans_text(&[("Read Later", preamble), ("Finished", Vec::new())]);
+    assert_eq!(
+        text,
+        "Read Later (2 lines before the first item)\n  # Read Later\n  stray note"
+    );
+    assert_eq!(
+        build_orphans_text(&[("Read Later", vec![String::new()])]),
+        "Nothing before the first item."
+    );
+}
+
+#[test]
+fn hash_queries_match_whole_tags() {
+    let rust = entry("Pin explained #rust #Async");
+    let rustlang = entry("Weekly news #rustlang");
+    assert_eq!(extract_tags(&rust), vec!["rust", "async"]);
+
+    assert!(matches_query(&rust, "#rust"));
+    assert!(!matches_query(&rustlang, "#rust"));
+    assert!(matches_query(&rustlang, "rust"));
+    assert!(matches_query(&rust, "#RUST #async"));
+    assert!(!matches_query(&rust, "#rust #tokio"));
+    assert!(matches_query(&rustlang, "#rust OR #rustlang"));
+}
+
+#[test]
+fn oldest_is_last_unpeeked_entry() {
+    let entries = vec![entry("a"), entry("b"), entry("c")];
+    let peeked = HashSet::from([entries[2].block_string()]);
+    let oldest = ordered_unpeeked_indices(&entries, &peeked, ListMode::Bottom);
+    assert_eq!(oldest.first(), Some(&1));
+
+    let with_pin = vec![entry("📌 a"), entry("b"), entry("c")];
+    let oldest = ordered_unpeeked_indices(&with_pin, &HashSet::new(), ListMode::Bottom);
+    assert_eq!(oldest.first(), Some(&2));
+
+    let all: HashSet<String> = entries.iter().map(|e| e.block_string()).collect();
+    assert!(ordered_unpeeked_indices(&entries, &all, ListMode::Bottom).is_empty());
+}
+
+#[test]
+fn auto_reset_only_when_nothing_is_unpeeked() {
+    let mut config = test_config();
+    let entries = vec![entry("a"), entry("b")];
+    let all: HashSet<String> = entries.iter().map(|e| e.block_string()).collect();
+    let one = HashSet::from([entries[0].block_string()]);
+
+    assert!(!should_auto_reset_peeked(&config, &entries, &all));
+    config.auto_reset_when_all_peeked = true;
+    assert!(should_auto_reset_peeked(&config, &entries, &all));
+    assert!(!should_auto_reset_peeked(&config, &entries, &one));
+    assert!(!should_auto_reset_peeked(&config, &[], &HashSet::new()));
+}
+
+#[test]
+fn finish_to_resource_moves_three_ways_and_undoes() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    let finished = temp.path().join("finished.md");
+    let resource = temp.path().join("Links.md");
+    fs::write(
+        &read_later,
+        "# Read Later\n- https://a.example\n  notes\n- other\n",
+    )
+    .unwrap();
+    fs::write(&finished, "- done\n").unwrap();
+    fs::write(&resource, "- (Auto-Resource): existing\n").unwrap();
+    let entry = "- https://a.example\n  notes";
+
+    let (outcome, added) = finish_to_resource_sync(
+        &read_later,
+        &finished,
+        &resource,
+        entry,
+        None,
+        FinishedOrder::Prepend,
+    )
+    .unwrap();
+    assert!(matches!(outcome, ModifyOutcome::Applied));
+    assert!(added);
+    assert_eq!(
+        fs::read_to_string(&read_later).unwrap(),
+        "# Read Later\n- other\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&finished).unwrap(),
+        "- https://a.example\n  notes\n- done\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&resource).unwrap(),
+        "- (Auto-Resource): https://a.example\n  notes\n- (Auto-Resource): existing\n"
+    );
+    let (again, _) = finish_to_resource_sync(
+        &read_later,
+        &finished,
+        &resource,
+        entry,
+        None,
+        FinishedOrder::Prepend,
+    )
+    .unwrap();
+    assert!(matches!(again, ModifyOutcome::NotFound));
+
+    let undo = undo_op_for_record(
+        &UndoRecord {
+            id: "u".to_string(),
+            kind: UndoKind::FinishToResource {
+                resource_path: resource.clone(),
+                added: true,
+            },
+            entry: entry.to_string(),
+            expires_at: 0,
+        },
+        &test_config(),
+    );
+    assert!(matches!(undo.kind, QueuedOpKind::UndoFinishToResource));
+    assert_eq!(undo.resource_path.as_deref(), Some(resource.as_path()));
+    let outcome =
+        undo_finish_to_resource_sync(&read_later, &finished, &resource, entry, None).unwrap();
+    assert!(matches!(outcome, ModifyOutcome::Applied));
+    assert_eq!(
+        fs::read_to_string(&read_later).unwrap(),
+        "# Read Later\n- https://a.example\n  notes\n- other\n"
+    );
+    assert_eq!(fs::read_to_string(&finished).unwrap(), "- done\n");
+    assert_eq!(
+        fs::read_to_string(&resource).unwrap(),
+        "- (Auto-Resource): existing\n"
+    );
+}
+
+#[test]
+fn finish_to_resource_undo_keeps_block_it_did_not_add() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    let finished = temp.path().join("finished.md");
+    let resource = temp.path().join("Links.md");
+    fs::write(&read_later, "- https://a.example\n").unwrap();
+    fs::write(&finished, "").unwrap();
+    fs::write(&resource, "- (Auto-Resource): https://a.example\n").unwrap();
+    let entry = "- https://a.example";
+
+    let (outcome, added) = finish_to_resource_sync(
+        &read_later,
+        &finished,
+        &resource,
+        entry,
+        None,
+        FinishedOrder::Prepend,
+    )
+    .unwrap();
+    assert!(matches!(outcome, ModifyOutcome::Applied));
+    assert!(!added);
+
+    let undo = undo_op_for_record(
+        &UndoRecord {
+            id: "u".to_string(),
+            kind: UndoKind::FinishToResource {
+                resource_path: resource.clone(),
+                added,
+            },
+            entry: entry.to_string(),
+            expires_at: 0,
+        },
+        &test_config(),
+    );
+    assert!(matches!(undo.kind, QueuedOpKind::MoveToReadLater));
+    move_to_read_later_sync(&read_later, &finished, &undo.entry, None).unwrap();
+    assert_eq!(
+        fs::read_to_string(&read_later).unwrap(),
+        "- https://a.example\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&resource).unwrap(),
+        "- (Auto-Resource): https://a.example\n"
+    );
+}
+
+#[test]
+fn edit_undo_restores_original_text() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    fs::write(&read_later, "- old text\n- other\n").unwrap();
+    let edited = EntryBlock::from_text("new text\n  more");
+    update_entry_sync(&read_later, "- old text", &edited).unwrap();
+    assert_eq!(
+        fs::read_to_string(&read_later).unwrap(),
+        "- new text\n  more\n- other\n"
+    );
+
+    let undo = undo_op_for_record(
+        &UndoRecord {
+            id: "u".to_string(),
+            kind: UndoKind::Edit {
+                updated_entry: edited.block_string(),
+                resource_path: None,
+            },
+            entry: "- old text".to_string(),
+            expires_at: 0,
+        },
+        &test_config(),
+    );
+    assert!(matches!(undo.kind, QueuedOpKind::UpdateEntry));
+    let original = EntryBlock::from_block(undo.updated_entry.as_deref().unwrap());
+    let outcome = update_entry_sync(&read_later, &undo.entry, &original).unwrap();
+    assert!(matches!(outcome, ModifyOutcome::Applied));
+    assert_eq!(
+        fs::read_to_string(&read_later).unwrap(),
+        "- old text\n- other\n"
+    );
+}
+
+#[test]
+fn add_resource_undo_persists_path_and_deletes_block() {
+    let temp = TempDir::new().unwrap();
+    let resource = temp.path().join("Links.md");
+    let undo_path = temp.path().join("undo.json");
+    let block = resource_block_from_text("https://a.example");
+    add_resource_entry_sync(&resource, &block).unwrap();
+    add_resource_entry_sync(&resource, "- (Auto-Resource): keep").unwrap();
+
+    let mut records = vec![UndoRecord {
+        id: "u".to_string(),
+        kind: UndoKind::AddResource {
+            resource_path: resource.clone(),
+        },
+        entry: block.clone(),
+        expires_at: now_ts() + 60,
+    }];
+    prune_undo(&mut records);
+    save_undo(&undo_path, &records).unwrap();
+    let loaded = load_undo(&undo_path).unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert!(matches!(
+        &loaded[0].kind,
+        UndoKind::AddResource { resource_path } if *resource_path == resource
+    ));
+
+    let undo = undo_op_for_record(&loaded[0], &test_config());
+    assert!(matches!(undo.kind, QueuedOpKind::DeleteResource));
+    assert_eq!(undo.resource_path.as_deref(), Some(resource.as_path()));
+    delete_entry_sync(&resource, &undo.entry, None).unwrap();
+    assert_eq!(
+        fs::read_to_string(&resource).unwrap(),
+        "- (Auto-Resource): keep\n"
+    );
+}
+
+#[test]
+fn undo_ttl_defaults_and_rejects_zero() {
+    let mut config = test_config();
+    assert_eq!(undo_expires_at(&config, 1_000), 1_000 + 30 * 60);
+    config.undo_ttl_seconds = 4 * 60 * 60;
+    assert_eq!(undo_expires_at(&config, 1_000), 1_000 + 4 * 60 * 60);
+
+    assert_eq!(
+        positive_or_default(None, DEFAULT_UNDO_TTL_SECS, "undo_ttl_seconds").unwrap(),
+        1800
+    );
+    let err = positive_or_default(Some(0), DEFAULT_UNDO_TTL_SECS, "undo_ttl_seconds").unwrap_err();
+    assert_eq!(err.to_string(), "undo_ttl_seconds must be positive");
+}
+
+#[test]
+fn duplicate_groups_follow_dedupe_by() {
+    let entries = vec![
+        entry("[Post](https://example.com/post)"),
+        entry("note"),
+        entry("https://example.com/post read again"),
+        entry("other https://example.com/elsewhere"),
+        EntryBlock::from_block("- 2024-05-01 note"),
+    ];
+    let blocks = |group: &[EntryBlock]| group.iter().map(|e| e.block_string()).collect::<Vec<_>>();
+
+    let groups = duplicate_groups(&entries, DedupeBy::Exact);
+    assert_eq!(groups.len(), 1);
+    assert_eq!(
+        blocks(&groups[0]),
+        blocks(&[entries[1].clone(), entries[4].clone()])
+    );
+
+    let mut groups = duplicate_groups(&entries, DedupeBy::FirstLink);
+    assert_eq!(groups.len(), 2);
+    assert_eq!(
+        blocks(&groups[0]),
+        blocks(&[entries[0].clone(), entries[2].clone()])
+    );
+    assert_eq!(
+        blocks(&groups[1]),
+        blocks(&[entries[1].clone(), entries[4].clone()])
+    );
+
+    let (text, kb) = build_duplicates_view("d", &groups, 1);
+    assert!(text.starts_with("Duplicates (group 2/2)"));
+    let data = callback_data(&kb);
+    assert!(data.contains(&"dup:d:del:1".to_string()));
+    assert!(data.contains(&"dup:d:page:0".to_string()));
+
+    assert_eq!(remove_duplicate_member(&mut groups, 1, 0), 0);
+    assert_eq!(groups.len(), 1);
+}
+
+#[test]
+fn normalize_all_entries_rewrites_only_changed_items() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    fs::write(
+        &read_later,
+        "# Read Later\n- [A](https://a.example)\n- plain\n- see [B](https://b.example)\n",
+    )
+    .unwrap();
+    assert_eq!(normalize_all_entries_sync(&read_later).unwrap(), 2);
+    let content = fs::read_to_string(&read_later).unwrap();
+    assert!(content.starts_with("# Read Later\n"));
+    assert!(!content.contains("]("));
+    assert!(content.contains("- plain\n"));
+    assert_eq!(normalize_all_entries_sync(&read_later).unwrap(), 0);
+}
+
+#[test]
+fn single_bare_link_accepts_only_a_lone_url() {
+    assert_eq!(
+        single_bare_link("  https://example.com/a?b=1 \n"),
+        Some("https://example.com/a?b=1")
+    );
+    assert_eq!(single_bare_link("see https://example.com"), None);
+    assert_eq!(single_bare_link("[A](https://example.com)"), None);
+    assert_eq!(single_bare_link("ftp://example.com"), None);
+}
+
+#[test]
+fn extract_html_title_reads_and_cleans_title() {
+    let html = "<html><head><TITLE lang=\"en\">\n  Rust &amp; [Friends]\n</TITLE></head></html>";
+    assert_eq!(extract_html_title(html), Some("Rust & Friends".to_string()));
+    assert_eq!(extract_html_title("<title>   </title>"), None);
+    assert_eq!(extract_html_title("<p>no title</p>"), None);
+}
+
+#[test]
+fn is_html_content_type_ignores_parameters_and_case() {
+    assert!(is_html_content_type("text/html"));
+    assert!(is_html_content_type("Text/HTML; charset=utf-8"));
+    assert!(!is_html_content_type("application/pdf"));
+    assert!(!is_html_content_type("text/htmlx"));
+}
+
+#[test]
+fn queue_view_counts_kinds_and_confirms_clearing() {
+    let op = |kind| QueuedOp {
+        kind,
+        entry: "- a".to_string(),
+        resource_path: None,
+        updated_entry: None,
+        position: None,
+    };
+    let queue = vec![
+        op(QueuedOpKind::Add),
+        op(QueuedOpKind::Delete),
+        op(QueuedOpKind::Add),
+    ];
+    let (text, kb) = build_queue_view(&queue, 0);
+    assert_eq!(text, "3 queued writes pending:\nAdd x2\nDelete x1");
+    assert!(callback_data(&kb).contains(&"queue:retry".to_string()));
+
+    let (text, kb) = build_queue_view(&queue, 2);
+    assert_eq!(text, "Drop all 3 queued writes (2/2)?");
+    assert!(callback_data(&kb).contains(&"queue:clear2".to_string()));
+
+    let (text, _) = build_queue_view(&[], 0);
+    assert_eq!(text, "Queue is empty.");
+}
+
+#[test]
+fn queue_clear_confirm_is_bound_to_message_step_and_expiry() {
+    let confirm = QueueClearConfirm {
+        message_id: MessageId(7),
+        step: 1,
+        expires_at: 100,
+    };
+    let valid = |message_id, step, now| {
+        queue_clear_confirm_valid(Some(&confirm), MessageId(message_id), step, now)
+    };
+    assert!(valid(7, 1, 100));
+    assert!(!valid(7, 1, 101));
+    assert!(!valid(8, 1, 50));
+    assert!(!valid(7, 2, 50));
+    assert!(!queue_clear_confirm_valid(None, MessageId(7), 1, 50));
+}
+
+#[test]
+fn open_link_row_caps_buttons_and_skips_entries_without_links() {
+    let url_of = |button: &InlineKeyboardButton| match &button.kind {
+        teloxide::types::InlineKeyboardButtonKind::Url(url) => url.to_string(),
+        _ => String::new(),
+    };
+
+    let single = build_open_link_row(&entry("- [A](https://a.example/x)"), 0);
+    assert_eq!(single.len(), 1);
+    assert_eq!(single[0].text, "Open link");
+    assert_eq!(url_of(&single[0]), "https://a.example/x");
+
+    let many = build_open_link_row(
+        &entry("- https://a.example https://b.example https://c.example https://d.example"),
+        0,
+    );
+    assert_eq!(many.len(), OPEN_LINK_BUTTONS);
+    assert_eq!(many[2].text, "Open 3");
+
+    assert!(build_open_link_row(&entry("- no links here"), 0).is_empty());
+}
+
+#[test]
+fn ensure_parent_dir_creates_missing_folders_and_names_the_key() {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("vault/inbox/Read Later.md");
+    ensure_parent_dir(&path, "read_later_path").unwrap();
+    assert!(temp.path().join("vault/inbox").is_dir());
+
+    let blocker = temp.path().join("not-a-dir");
+    fs::write(&blocker, b"x").unwrap();
+    let err = ensure_parent_dir(&blocker.join("sub/Finished.md"), "finished_path").unwrap_err();
+    let message = format!("{:#}", err);
+    assert!(message.starts_with("finished_path: cannot create directory"));
+    assert!(message.contains("not-a-dir"));
+}
+
+#[test]
+fn export_markdown_adds_headings_only_for_several_sections() {
+    let single = build_export_markdown(
+        "Read Later",
+        &[(String::new(), vec![entry("- a"), entry("- b")])],
+    );
+    assert_eq!(single, "# Read Later\n- a\n- b\n");
+
+    let sections = vec![
+        ("Links.md".to_string(), vec![entry("- a")]),
+        ("Empty.md".to_string(), Vec::new()),
+        ("Tools.md".to_string(), vec![entry("- b\n  note")]),
+    ];
+    assert_eq!(
+        build_export_markdown("Resources", &sections),
+        "# Resources\n\n## Links.md\n- a\n\n## Tools.md\n- b\n  note\n"
+    );
+}
+
+#[test]
+fn finished_order_controls_insert_position_and_undo_still_works() {
+    for (order, expected) in [
+        (FinishedOrder::Prepend, vec!["- b", "- old"]),
+        (FinishedOrder::Append, vec!["- old", "- b"]),
+    ] {
+        let temp = TempDir::new().unwrap();
+        let read_later = temp.path().join("read-later.md");
+        let finished = temp.path().join("finished.md");
+        fs::write(&read_later, "- a\n- b\n").unwrap();
+        fs::write(&finished, "- old\n").unwrap();
+
+        move_to_finished_sync(&read_later, &finished, "- b", None, order).unwrap();
+        let done: Vec<String> = read_entries(&finished)
+            .unwrap()
+            .1
+            .iter()
+            .map(|e| e.block_string())
+            .collect();
+        assert_eq!(done, expected);
+
+        let outcome = move_to_read_later_sync(&read_later, &finished, "- b", None).unwrap();
+        assert!(matches!(outcome, ModifyOutcome::Applied));
+        assert_eq!(fs::read_to_string(&finished).unwrap(), "- old\n");
+        assert_eq!(read_entries(&read_later).unwrap().1.len(), 2);
+    }
+}
+
+#[test]
+fn finished_order_append_applies_to_updated_and_batch_moves() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    let finished = temp.path().join("finished.md");
+    fs::write(&read_later, "- a\n- b\n- c\n").unwrap();
+    fs::write(&finished, "- old\n").unwrap();
+
+    move_to_finished_updated_sync(
+        &read_later,
+        &finished,
+        "- a",
+        "- a\n  - Note: x",
+        None,
+        FinishedOrder::Append,
+    )
+    .unwrap();
+    let blocks = vec!["- b".to_string(), "- c".to_string()];
+    move_many_to_finished_sync(&read_later, &finished, &blocks, None, FinishedOrder::Append)
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(&finished).unwrap(),
+        "- old\n- a\n  - Note: x\n- b\n- c\n"
+    );
+}
+
+#[test]
+fn pinned_entries_list_first_and_keep_relative_order() {
+    let (_, entries) = parse_entries("# RL\n- a\n- 📌 b\n- c\n- 📌 d\n  note\n");
+    let blocks: Vec<String> = entries.iter().map(|e| e.block_string()).collect();
+    assert_eq!(blocks, vec!["- a", "- 📌 b", "- c", "- 📌 d\n  note"]);
+
+    assert_eq!(ordered_indices(&entries, ListMode::Top), vec![1, 3, 0, 2]);
+    assert_eq!(
+        ordered_indices(&entries, ListMode::Bottom),
+        vec![3, 1, 2, 0]
+    );
+    let peeked: HashSet<String> = HashSet::from(["- a".to_string()]);
+    assert_eq!(
+        peek_indices(&entries, &peeked, ListMode::Bottom, 0, 10),
+        vec![3, 1, 2]
+    );
+}
+
+#[test]
+fn finishing_a_pinned_entry_drops_the_pin_and_undo_restores_it() {
+    let temp = TempDir::new().unwrap();
+    let read_later = temp.path().join("read-later.md");
+    let finished = temp.path().join("finished.md");
+    fs::write(&read_later, "- 📌 https://a.example\n- other\n").unwrap();
+    fs::write(&finished, "").unwrap();
+    let date = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+
+    let outcome = move_to_finished_sync(
+        &read_later,
+        &finished,
+        "- 📌 https://a.example",
+        Some(date),
+        FinishedOrder::Prepend,
+    )
+    .unwrap();
+    assert!(matches!(outcome, ModifyOutcome::Applied));
+    assert_eq!(
+        fs::read_to_string(&finished).unwrap(),
+        "- https://a.example\n  - Finished: 2026-10-16\n"
+    );
+
+    let mut undo = vec![UndoRecord {
+        id: "u".to_string(),
+        kind: UndoKind::MoveToFinished,
+        entry: "- 📌 https://a.example".to_string(),
+        expires_at: now_ts() + 60,
+    }];
+    let (_, finished_entries) = read_entries(&finished).unwrap();
+    assert_eq!(compact_undo(&mut undo, &[], &finished_entries), 0);
+
+    move_to_read_later_sync(&read_later, &finished, "- 📌 https://a.example", None).unwrap();
+    assert_eq!(
+        fs::read_to_string(&read_later).unwrap(),
+        "- 📌 https://a.example\n- other\n"
+    );
+    assert_eq!(fs::read_to_string(&finished).unwrap(), "");
+}
+
+#[test]
+fn pinned_dated_entry_keeps_its_added_date() {
+    let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let dated = entry(&stamp_added_date("https://a.example", today));
+    let pinned = pin_entry(&dated).unwrap();
+    assert_eq!(entry_added_date(&pinned), Some(today));
+    assert_eq!(
+        strip_added_date(&pinned.block_string()),
+        "- 📌 https://a.example"
+    );
+}
+
+#[test]
+fn pin_and_unpin_round_trip() {
+    let original = entry("- https://a.example\n  context");
+    let pinned = pin_entry(&original).unwrap();
+    assert_eq!(pinned.block_string(), "- 📌 https://a.example\n  context");
+    assert!(is_pinned(&pinned));
+    assert!(pin_entry(&pinned).is_none());
+    let unpinned = unpin_entry(&pinned).unwrap();
+    assert_eq!(unpinned.block_string(), original.block_string());
+    assert!(unpin_entry(&original).is_none());
+}
+
+#[test]
+fn stale_session_ids_uses_last_activity() {
+    let session = |id: &str, last_activity| ListSession {
+        id: id.to_string(),
+        chat_id: 1,
+        last_activity,
+        ..test_session(Vec::new(), ListView::Menu)
+    };
+    let sessions: HashMap<String, ListSession> = [
+        ("old".to_string(), session("old", 100)),
+        ("edge".to_string(), session("edge", 400)),
+        ("fresh".to_string(), session("fresh", 900)),
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(
+        stale_session_ids(&sessions, 1_000, 600),
+        vec!["old".to_string()]
+    );
+    let mut all = stale_session_ids(&sessions, 10_000, 600);
+    all.sort();
+    assert_eq!(all, vec!["edge", "fresh", "old"]);
+}
+
+#[test]
+fn peek_view_offers_finish_page_for_lists_only() {
+    let mut session = ListSession {
+        id: "s".to_string(),
+        chat_id: 1,
+        ..test_session(
+            vec![entry("- a"), entry("- b")],
+            ListView::Peek {
+                mode: ListMode::Top,
+                page: 0,
+            },
+        )
+    };
+    let config = test_config();
+    let (_, kb) = build_peek_view("s", &session, ListMode::Top, 0, &HashSet::new(), &config);
+    assert!(callback_data(&kb).contains(&"ls:s:finishpage".to_string()));
+
+    session.kind = SessionKind::Search {
+        query: "a".to_string(),
+    };
+    let (_, kb) = build_peek_view("s", &session, ListMode::Top, 0, &HashSet::new(), &config);
+    assert!(!callback_data(&kb).contains(&"ls:s:finishpage".to_string()));
+}
+
+#[test]
+fn media_dirs_route_by_type_and_resolve_bare_names() {
+    let temp = TempDir::new().unwrap();
+    let mut config = test_config();
+    config.media_dir = temp.path().join("media");
+    config.image_dir = temp.path().join("images");
+    config.video_dir = temp.path().join("videos");
+    config.document_dir = config.media_dir.clone();
+
+    assert_eq!(media_dir_for(&config, Path::new("a.jpg")), config.image_dir);
+    assert_eq!(media_dir_for(&config, Path::new("a.mp4")), config.video_dir);
+    assert_eq!(media_dir_for(&config, Path::new("a.pdf")), config.media_dir);
+    assert_eq!(media_dirs(&config).len(), 3);
+
+    fs::create_dir_all(&config.video_dir).unwrap();
+    fs::write(config.video_dir.join("clip.mp4"), b"x").unwrap();
+    assert_eq!(
+        resolve_embedded_path("clip.mp4", &config),
+        Some(config.video_dir.join("clip.mp4"))
+    );
+    assert_eq!(resolve_embedded_path("missing.mp4", &config), None);
+    assert_eq!(
+        embedded_target_path("missing.mp4", &config),
+        Some(config.media_dir.join("missing.mp4"))
+    );
+}
+
+#[test]
+fn finished_pick_view_shows_entry_and_move_back_button() {
+    let config = test_config();
+    let (text, kb) =
+        build_finished_pick_view("p1", &entry("- https://a.example\n  notes"), &config);
+    assert_eq!(text, "From Finished:\n\nhttps://a.example\n  notes");
+    assert_eq!(
+        callback_data(&kb),
+        vec!["rf:p1:back", "rf:p1:another", "rf:p1:close"]
+    );
+}